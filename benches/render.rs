@@ -0,0 +1,82 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use tera::Context;
+use terarium::{Content, Template, TemplateGroupBuilder, Terarium, TerariumBuilder};
+
+/// Number of templates in the large catalog.
+const LARGE_CATALOG: usize = 10_000;
+
+
+fn make_template(key: usize) -> Template {
+    Template::new(vec![
+        Content::new(format!("Hello {{{{ name }}}} from template {}", key), vec!["en".to_owned()]),
+        Content::new(format!("Nazdar {{{{ name }}}} ze šablony {}", key), vec!["cs".to_owned()]),
+    ]).unwrap()
+}
+
+
+fn make_builder(templates: usize) -> TerariumBuilder {
+    let mut builder = TerariumBuilder::default();
+    for key in 0..templates {
+        builder.add_template(format!("template_{}", key), make_template(key)).unwrap();
+    }
+    builder
+}
+
+
+fn make_instance() -> Terarium {
+    let mut builder = make_builder(100);
+    let mut group = TemplateGroupBuilder::default();
+    for member in 0..10 {
+        group = group.add_member(format!("member_{}", member), format!("template_{}", member));
+    }
+    builder.add_group("group".to_owned(), group.build()).unwrap();
+    builder.build().unwrap()
+}
+
+
+fn make_context() -> Context {
+    let mut context = Context::new();
+    context.insert("name", "John");
+    context
+}
+
+
+fn render_template(c: &mut Criterion) {
+    let instance = make_instance();
+    let context = make_context();
+    c.bench_function("render_template", |b| {
+        b.iter(|| instance.render_template(&context, "template_1", "en", None).unwrap())
+    });
+}
+
+
+fn render_group(c: &mut Criterion) {
+    let instance = make_instance();
+    let context = make_context();
+    c.bench_function("render_group", |b| {
+        b.iter(|| instance.render_group(&context, "group", "en", None).unwrap())
+    });
+}
+
+
+fn fallback_resolution(c: &mut Criterion) {
+    let instance = make_instance();
+    let context = make_context();
+    c.bench_function("fallback_resolution", |b| {
+        b.iter(|| instance.render_template(&context, "template_1", "de", Some("cs")).unwrap())
+    });
+}
+
+
+fn build_large_catalog(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    group.bench_function("large_catalog", |b| {
+        b.iter_batched(|| make_builder(LARGE_CATALOG), |builder| builder.build().unwrap(), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+
+criterion_group!(benches, render_template, render_group, fallback_resolution, build_large_catalog);
+criterion_main!(benches);
@@ -0,0 +1,335 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use tera::ast::{Expr, ExprVal, Node};
+use tera::Tera;
+
+
+/// Call the `visit` for each node of the AST including nested nodes.
+pub(crate) fn walk<'a>(nodes: &'a [Node], visit: &mut impl FnMut(&'a Node)) {
+    for node in nodes {
+        visit(node);
+        match node {
+            Node::MacroDefinition(_, definition, _) => walk(&definition.body, visit),
+            Node::FilterSection(_, section, _) => walk(&section.body, visit),
+            Node::Block(_, block, _) => walk(&block.body, visit),
+            Node::Forloop(_, forloop, _) => {
+                walk(&forloop.body, visit);
+                if let Some(body) = &forloop.empty_body {
+                    walk(body, visit);
+                }
+            }
+            Node::If(condition, _) => {
+                condition.conditions.iter().for_each(|(_, _, body)| walk(body, visit));
+                if let Some((_, body)) = &condition.otherwise {
+                    walk(body, visit);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+
+/// Return names of templates included by the AST.
+pub(crate) fn includes(nodes: &[Node]) -> Vec<&str> {
+    let mut result = Vec::new();
+    walk(nodes, &mut |node| {
+        if let Node::Include(_, names, _) = node {
+            result.extend(names.iter().map(|n| n.as_str()));
+        }
+    });
+    result
+}
+
+
+/// Return names of templates included or extended by the AST.
+pub(crate) fn dependencies(nodes: &[Node]) -> Vec<&str> {
+    let mut result = includes(nodes);
+    for node in nodes {
+        if let Node::Extends(_, parent) = node {
+            result.push(parent);
+        }
+    }
+    result
+}
+
+
+/// Find cycle of includes and extends and return its path starting and ending with the same template.
+/// Templates are searched in alphabetical order, so the result is stable.
+pub(crate) fn find_cycle(tera: &Tera) -> Option<Vec<String>> {
+    fn visit<'a>(
+        tera: &'a Tera,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            return Some(path[start..].iter().chain(Some(&name)).map(|n| n.to_string()).collect());
+        }
+        if finished.contains(name) {
+            return None;
+        }
+        let (name, template) = tera.templates.get_key_value(name)?;
+        path.push(name);
+        let mut dependencies = dependencies(&template.ast);
+        dependencies.sort();
+        for dependency in dependencies {
+            if let Some(cycle) = visit(tera, dependency, path, finished) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        finished.insert(name);
+        None
+    }
+
+    let mut names: Vec<_> = tera.templates.keys().collect();
+    names.sort();
+    let mut finished = HashSet::new();
+    names.into_iter().find_map(|name| visit(tera, name, &mut Vec::new(), &mut finished))
+}
+
+
+/// Return the longest chain of nested includes starting in each template.
+/// Includes of missing templates and includes leading back to a template already in the chain are not followed.
+pub(crate) fn include_depths(tera: &Tera) -> HashMap<&str, usize> {
+    fn depth<'a>(
+        tera: &'a Tera,
+        name: &'a str,
+        chain: &mut HashSet<&'a str>,
+        depths: &mut HashMap<&'a str, usize>,
+    ) -> usize {
+        if let Some(depth) = depths.get(name) {
+            return *depth;
+        }
+        let Some((name, template)) = tera.templates.get_key_value(name) else { return 0 };
+        chain.insert(name);
+        let mut result = 0;
+        for included in includes(&template.ast) {
+            if tera.templates.contains_key(included) && !chain.contains(included) {
+                result = result.max(1 + depth(tera, included, chain, depths));
+            }
+        }
+        chain.remove(name.as_str());
+        depths.insert(name, result);
+        result
+    }
+
+    let mut depths = HashMap::new();
+    for name in tera.templates.keys() {
+        depth(tera, name, &mut HashSet::new(), &mut depths);
+    }
+    depths
+}
+
+
+/// Return root names of context variables the template always uses, including variables of templates it always
+/// includes or extends. Variables used only in conditions, loop bodies and macros or with the `default` filter are
+/// optional, so they are not returned.
+pub(crate) fn required_variables(tera: &Tera, name: &str) -> BTreeSet<String> {
+    fn visit<'a>(tera: &'a Tera, name: &'a str, visited: &mut HashSet<&'a str>, result: &mut BTreeSet<String>) {
+        let Some((name, template)) = tera.templates.get_key_value(name) else { return };
+        if !visited.insert(name) {
+            return;
+        }
+        let mut bound = HashSet::new();
+        let mut dependencies = Vec::new();
+        nodes_variables(&template.ast, &mut bound, &mut dependencies, result);
+        for dependency in dependencies {
+            visit(tera, dependency, visited, result);
+        }
+    }
+
+    let mut result = BTreeSet::new();
+    visit(tera, name, &mut HashSet::new(), &mut result);
+    result
+}
+
+
+/// Return root names of context variables referenced anywhere in the template and templates it includes or extends,
+/// in order of the first reference. Names set by templates themselves (`set`, loop variables and macro arguments)
+/// are skipped.
+pub(crate) fn referenced_variables(tera: &Tera, name: &str) -> Vec<String> {
+    fn visit<'a>(tera: &'a Tera, name: &'a str, visited: &mut HashSet<&'a str>, result: &mut Vec<String>) {
+        let Some((name, template)) = tera.templates.get_key_value(name) else { return };
+        if !visited.insert(name) {
+            return;
+        }
+        let mut bound = HashSet::from(["loop"]);
+        let mut variables = Vec::new();
+        walk(&template.ast, &mut |node| match node {
+            Node::VariableBlock(_, expr) => expr_variables(expr, false, &mut variables),
+            Node::Set(_, set) => {
+                expr_variables(&set.value, false, &mut variables);
+                bound.insert(&set.key);
+            }
+            Node::Forloop(_, forloop, _) => {
+                expr_variables(&forloop.container, false, &mut variables);
+                bound.insert(&forloop.value);
+                bound.extend(forloop.key.as_deref());
+            }
+            Node::If(condition, _) => {
+                condition.conditions.iter().for_each(|(_, expr, _)| expr_variables(expr, false, &mut variables));
+            }
+            Node::FilterSection(_, section, _) => {
+                section.filter.args.values().for_each(|arg| expr_variables(arg, false, &mut variables));
+            }
+            Node::MacroDefinition(_, definition, _) => bound.extend(definition.args.keys().map(|arg| arg.as_str())),
+            _ => {}
+        });
+        for variable in variables {
+            if !bound.contains(variable) && !variable.starts_with("__") && !result.iter().any(|v| v == variable) {
+                result.push(variable.to_owned());
+            }
+        }
+        for dependency in dependencies(&template.ast) {
+            visit(tera, dependency, visited, result);
+        }
+    }
+
+    let mut result = Vec::new();
+    visit(tera, name, &mut HashSet::new(), &mut result);
+    result
+}
+
+
+/// Return names of templates included or extended by the template in order of evaluation, templates included
+/// by an included template follow it. Missing templates and templates already listed are not followed.
+pub(crate) fn include_sequence(tera: &Tera, name: &str) -> Vec<String> {
+    fn visit<'a>(tera: &'a Tera, name: &'a str, result: &mut Vec<String>) {
+        let Some(template) = tera.templates.get(name) else { return };
+        for dependency in dependencies(&template.ast) {
+            if tera.templates.contains_key(dependency) && !result.iter().any(|listed| listed == dependency) {
+                result.push(dependency.to_owned());
+                visit(tera, dependency, result);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    visit(tera, name, &mut result);
+    result
+}
+
+
+/// Collect variables always used by the nodes, the `bound` are variables set by the template itself.
+fn nodes_variables<'a>(
+    nodes: &'a [Node],
+    bound: &mut HashSet<&'a str>,
+    dependencies: &mut Vec<&'a str>,
+    result: &mut BTreeSet<String>,
+) {
+    for node in nodes {
+        let mut variables = Vec::new();
+        match node {
+            Node::VariableBlock(_, expr) => expr_variables(expr, true, &mut variables),
+            Node::Set(_, set) => expr_variables(&set.value, true, &mut variables),
+            Node::Forloop(_, forloop, _) => expr_variables(&forloop.container, true, &mut variables),
+            Node::FilterSection(_, section, _) => {
+                section.filter.args.values().for_each(|arg| expr_variables(arg, true, &mut variables));
+                nodes_variables(&section.body, bound, dependencies, result);
+            }
+            Node::Block(_, block, _) => nodes_variables(&block.body, bound, dependencies, result),
+            Node::Include(_, names, false) => dependencies.extend(names.iter().map(|n| n.as_str())),
+            Node::Extends(_, parent) => dependencies.push(parent),
+            _ => {}
+        }
+        result.extend(variables.into_iter().filter(|v| !bound.contains(v) && !v.starts_with("__")).map(String::from));
+        if let Node::Set(_, set) = node {
+            bound.insert(&set.key);
+        }
+    }
+}
+
+
+/// Collect root names of variables evaluated by the expression, only variables always evaluated when `required`.
+fn expr_variables<'a>(expr: &'a Expr, required: bool, result: &mut Vec<&'a str>) {
+    if required && expr.filters.iter().any(|filter| filter.name == "default") {
+        return;
+    }
+    value_variables(&expr.val, required, result);
+    for filter in expr.filters.iter() {
+        filter.args.values().for_each(|arg| expr_variables(arg, required, result));
+    }
+}
+
+
+fn value_variables<'a>(value: &'a ExprVal, required: bool, result: &mut Vec<&'a str>) {
+    match value {
+        ExprVal::Ident(name) => result.extend(name.split(['.', '[']).next()),
+        ExprVal::Math(math) => {
+            expr_variables(&math.lhs, required, result);
+            expr_variables(&math.rhs, required, result);
+        }
+        ExprVal::Logic(logic) => {
+            expr_variables(&logic.lhs, required, result);
+            // right hand side of logic expressions may be short-circuited
+            if !required {
+                expr_variables(&logic.rhs, required, result);
+            }
+        }
+        ExprVal::In(test) => {
+            expr_variables(&test.lhs, required, result);
+            expr_variables(&test.rhs, required, result);
+        }
+        ExprVal::FunctionCall(call) => call.args.values().for_each(|arg| expr_variables(arg, required, result)),
+        ExprVal::MacroCall(call) => call.args.values().for_each(|arg| expr_variables(arg, required, result)),
+        ExprVal::Array(items) => items.iter().for_each(|item| expr_variables(item, required, result)),
+        ExprVal::StringConcat(concat) => {
+            concat.values.iter().for_each(|value| value_variables(value, required, result));
+        }
+        _ => {}
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_includes() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("a", "{% if x %}{% for i in y %}{% include \"b\" %}{% endfor %}{% endif %}"),
+            ("b", "{% include \"c\" %}{% include \"d\" ignore missing %}"),
+            ("c", "c"),
+        ]).unwrap();
+        assert_eq!(includes(&tera.templates["b"].ast), vec!["c", "d"]);
+        let depths = include_depths(&tera);
+        assert_eq!(depths["a"], 2);
+        assert_eq!(depths["c"], 0);
+        assert!(find_cycle(&tera).is_none());
+    }
+
+    #[test]
+    fn cycle() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("a", "{% include \"b\" %}"),
+            ("b", "{% block x %}{% include \"c\" %}{% endblock %}"),
+            ("c", "{% include \"b\" %}"),
+        ]).unwrap();
+        assert_eq!(find_cycle(&tera).unwrap(), vec!["b", "c", "b"]);
+    }
+
+    #[test]
+    fn required() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("layout", "{{ brand.name }}{% block body %}{% endblock %}"),
+            ("footer", "{{ year + offset }}{% include \"missing\" ignore missing %}"),
+            ("a", "{% extends \"layout\" %}{% block body %}{% set x = user.name ~ suffix %}{{ x }}\
+                {% include \"footer\" %}{{ title | default(value=name) }}{{ a or b }}{% if vip %}{{ c }}{% endif %}\
+                {% for i in items %}{{ i.d }}{% endfor %}{% endblock %}"),
+        ]).unwrap();
+        let required: Vec<_> = required_variables(&tera, "a").into_iter().collect();
+        assert_eq!(required, vec!["a", "brand", "items", "offset", "suffix", "user", "year"]);
+
+        let referenced = referenced_variables(&tera, "a");
+        assert_eq!(referenced, vec![
+            "user", "suffix", "title", "name", "a", "b", "vip", "c", "items", "year", "offset", "brand",
+        ]);
+        assert_eq!(include_sequence(&tera, "a"), vec!["footer", "layout"]);
+    }
+}
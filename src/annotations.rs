@@ -0,0 +1,82 @@
+use crate::Content;
+
+
+/// Prefix of comments holding notes for translators.
+const TRANSLATOR_PREFIX: &str = "translator:";
+
+
+/// Note for translators written in a content source as `{# translator: ... #}` comment.
+/// Notes are kept by the `Terarium` even when sources are dropped (see `Terarium::translator_notes()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranslatorNote {
+    /// Languages of the content containing the note.
+    pub languages: Vec<String>,
+    /// Variant of the content containing the note.
+    pub variant: Option<String>,
+    /// Line of the source (starting by 1).
+    pub line: usize,
+    /// Text of the note without the `translator:` prefix.
+    pub note: String,
+}
+
+
+impl TranslatorNote {
+    /// Return notes of the content in order of their occurrence.
+    pub(crate) fn extract(content: &Content) -> Vec<TranslatorNote> {
+        extract_translator_notes(&content.content)
+            .into_iter()
+            .map(|(line, note)| TranslatorNote {
+                languages: content.languages.clone(),
+                variant: content.variant.clone(),
+                line,
+                note,
+            })
+            .collect()
+    }
+}
+
+
+/// Return lines and texts of `{# translator: ... #}` comments of the source. The prefix is case-insensitive,
+/// whitespace control of comments is allowed and whitespaces inside notes are collapsed to single spaces.
+pub fn extract_translator_notes(source: &str) -> Vec<(usize, String)> {
+    let mut notes = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = source[offset..].find("{#").map(|start| offset + start) {
+        let Some(length) = source[start + 2..].find("#}") else { break };
+        let body = &source[start + 2..start + 2 + length];
+        let body = body.strip_prefix('-').unwrap_or(body);
+        let body = body.strip_suffix('-').unwrap_or(body).trim();
+        let is_note = body
+            .get(..TRANSLATOR_PREFIX.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(TRANSLATOR_PREFIX));
+        if is_note {
+            let line = source[..start].matches('\n').count() + 1;
+            let note = body[TRANSLATOR_PREFIX.len()..].split_whitespace().collect::<Vec<_>>().join(" ");
+            notes.push((line, note));
+        }
+        offset = start + 2 + length + 2;
+    }
+    notes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract() {
+        let source = "{# translator: Greeting\n  shown in header #}Hello\n{#- Translator:name is first name -#}\
+            {# internal note #}{{ name }}{# translator: unclosed";
+        assert_eq!(extract_translator_notes(source), vec![
+            (1, "Greeting shown in header".to_owned()),
+            (3, "name is first name".to_owned()),
+        ]);
+
+        let content = Content::new(source.to_owned(), vec!["en".to_owned()]).with_variant("b".to_owned());
+        let notes = TranslatorNote::extract(&content);
+        assert_eq!(notes[1].languages, vec!["en"]);
+        assert_eq!(notes[1].variant.as_deref(), Some("b"));
+        assert_eq!(notes[1].line, 3);
+    }
+}
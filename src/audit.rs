@@ -0,0 +1,31 @@
+use std::time::SystemTime;
+
+
+/// Kind of the catalog mutation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    /// New template with the key was added.
+    TemplateAdded(String),
+    /// Existing template with the key was replaced.
+    TemplateUpdated(String),
+    /// Template with the key was removed.
+    TemplateRemoved(String),
+    /// Whole catalog was replaced.
+    CatalogSwapped,
+}
+
+
+/// One record of the audit log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Who made the change.
+    pub actor: String,
+    /// When the change was made.
+    pub timestamp: SystemTime,
+    /// What was changed.
+    pub action: AuditAction,
+    /// Fingerprint of the changed item before the change (`None` when the item did not exist).
+    pub fingerprint_before: Option<u64>,
+    /// Fingerprint of the changed item after the change (`None` when the item was removed).
+    pub fingerprint_after: Option<u64>,
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use tera::Context;
+
+
+/// Brand-specific values used for white-label rendering.
+#[derive(Clone, Debug, Default)]
+pub struct Brand {
+    /// Values inserted into the render context (logo, colors, sender names, ...).
+    pub globals: Context,
+}
+
+
+impl Brand {
+    /// Create brand with the globals.
+    pub fn new(globals: Context) -> Self {
+        Self { globals }
+    }
+}
+
+
+/// Provide brands by their ids.
+/// Templates are overridden for the brand by contents with variant equal to the brand id.
+pub trait BrandResolver: Send + Sync {
+    /// Return the brand or `None` when the brand is unknown.
+    fn resolve(&self, brand_id: &str) -> Option<Brand>;
+
+    /// Return the default brand. Its globals are used when the brand does not define them.
+    fn default_brand(&self) -> Brand {
+        Brand::default()
+    }
+}
+
+
+/// Brands kept in memory.
+#[derive(Clone, Debug, Default)]
+pub struct StaticBrandResolver {
+    default: Brand,
+    brands: HashMap<String, Brand>,
+}
+
+
+impl StaticBrandResolver {
+    /// Create resolver with the default brand.
+    pub fn new(default: Brand) -> Self {
+        Self {
+            default,
+            brands: HashMap::new(),
+        }
+    }
+
+    /// Add brand identified by the `brand_id`.
+    pub fn add_brand(mut self, brand_id: String, brand: Brand) -> Self {
+        self.brands.insert(brand_id, brand);
+        self
+    }
+}
+
+
+impl BrandResolver for StaticBrandResolver {
+    fn resolve(&self, brand_id: &str) -> Option<Brand> {
+        self.brands.get(brand_id).cloned()
+    }
+
+    fn default_brand(&self) -> Brand {
+        self.default.clone()
+    }
+}
+
+
+/// Build render context of the brand.
+/// Globals of the default brand are overridden by globals of the brand and those by the `context`.
+pub(crate) fn brand_context(resolver: &dyn BrandResolver, brand_id: &str, context: &Context) -> Context {
+    let mut result = resolver.default_brand().globals;
+    if let Some(brand) = resolver.resolve(brand_id) {
+        result.extend(brand.globals);
+    }
+    result.extend(context.clone());
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_fallback() {
+        let mut default = Context::new();
+        default.insert("logo", "default.png");
+        default.insert("sender", "Terarium");
+        let mut acme = Context::new();
+        acme.insert("logo", "acme.png");
+        let resolver = StaticBrandResolver::new(Brand::new(default)).add_brand("acme".to_owned(), Brand::new(acme));
+        let mut ctx = Context::new();
+        ctx.insert("sender", "John");
+
+        let result = brand_context(&resolver, "acme", &ctx);
+        assert_eq!(result.get("logo").unwrap(), "acme.png");
+        assert_eq!(result.get("sender").unwrap(), "John");
+        let result = brand_context(&resolver, "unknown", &Context::new());
+        assert_eq!(result.get("logo").unwrap(), "default.png");
+    }
+}
@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+
+use tera::{Context, Tera};
+
+
+/// Sizing of the per-thread pool of output buffers reused by renders.
+/// Outputs written by the `Terarium::render_template_to()` return their buffers to the pool, so high throughput
+/// rendering does not allocate a new buffer for every output.
+/// The pool of the thread is shared by all instances, each instance takes only buffers fitting its own sizing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferPool {
+    /// Maximal number of idle buffers kept by each thread.
+    pub buffers_per_thread: usize,
+    /// Buffers with larger capacity in bytes are dropped instead of returned to the pool.
+    pub max_buffer_capacity: usize,
+}
+
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self {
+            buffers_per_thread: 4,
+            max_buffer_capacity: 1024 * 1024,
+        }
+    }
+}
+
+
+impl BufferPool {
+    /// Set maximal number of idle buffers kept by each thread, zero disables the pool.
+    pub fn buffers_per_thread(mut self, count: usize) -> Self {
+        self.buffers_per_thread = count;
+        self
+    }
+
+    /// Set maximal capacity of buffers returned to the pool.
+    pub fn max_buffer_capacity(mut self, bytes: usize) -> Self {
+        self.max_buffer_capacity = bytes;
+        self
+    }
+
+    /// Take the buffer from the pool of the current thread or allocate new one.
+    /// Buffers larger than the `max_buffer_capacity` returned by other instances are left in the pool.
+    pub(crate) fn take(&self) -> Vec<u8> {
+        if self.buffers_per_thread == 0 {
+            return Vec::new();
+        }
+        IDLE.with(|idle| {
+            let mut idle = idle.borrow_mut();
+            let index = idle.iter().rposition(|buffer| buffer.capacity() <= self.max_buffer_capacity)?;
+            Some(idle.swap_remove(index))
+        }).unwrap_or_default()
+    }
+
+    /// Return the buffer to the pool of the current thread.
+    pub(crate) fn recycle(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() > self.max_buffer_capacity {
+            return;
+        }
+        buffer.clear();
+        IDLE.with(|idle| {
+            let mut idle = idle.borrow_mut();
+            if idle.len() < self.buffers_per_thread {
+                idle.push(buffer);
+            }
+        });
+    }
+}
+
+
+thread_local! {
+    static IDLE: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+
+/// Render the content into a buffer taken from the pool of the current thread.
+pub(crate) fn render_pooled(
+    pool: &BufferPool,
+    tera: &Tera,
+    content_key: &str,
+    context: &Context,
+) -> tera::Result<String> {
+    let mut buffer = pool.take();
+    tera.render_to(content_key, context, &mut buffer)?;
+    String::from_utf8(buffer).map_err(|error| tera::Error::utf8_conversion_error(error, content_key.to_owned()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_buffers() {
+        let mut tera = Tera::default();
+        tera.add_raw_template("a", "{{ x }}").unwrap();
+        let mut context = Context::new();
+        context.insert("x", "hello");
+
+        let pool = BufferPool::default().buffers_per_thread(1).max_buffer_capacity(64);
+        let mut buffer = Vec::with_capacity(32);
+        buffer.extend_from_slice(b"old");
+        pool.recycle(buffer);
+        pool.recycle(Vec::with_capacity(16));
+        assert_eq!(IDLE.with(|idle| idle.borrow().len()), 1);
+
+        let output = render_pooled(&pool, &tera, "a", &context).unwrap();
+        assert_eq!(output, "hello");
+        assert_eq!(output.capacity(), 32);
+        pool.recycle(Vec::with_capacity(128));
+        assert_eq!(IDLE.with(|idle| idle.borrow().len()), 0);
+    }
+
+    #[test]
+    fn take_buffers_fitting_sizing() {
+        let large = BufferPool::default().buffers_per_thread(2).max_buffer_capacity(1024);
+        large.recycle(Vec::with_capacity(512));
+        large.recycle(Vec::with_capacity(32));
+
+        let small = BufferPool::default().max_buffer_capacity(64);
+        assert_eq!(small.take().capacity(), 32);
+        assert_eq!(small.take().capacity(), 0);
+        assert_eq!(BufferPool::default().buffers_per_thread(0).take().capacity(), 0);
+        assert_eq!(IDLE.with(|idle| idle.borrow().len()), 1);
+        assert_eq!(large.take().capacity(), 512);
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use sha2::{Digest, Sha256};
+use tera::Context;
+
+use crate::scope::with_current;
+
+
+/// Caching of rendered outputs of a template (see `Template::set_cache_policy()`).
+/// Outputs are cached by versions of all templates (the output depends on included and extended ones), the content,
+/// the render language and options and the context, so any change of templates makes cached outputs unreachable.
+/// Outputs are cached regardless of the `Determinism`, so templates calling `now()` or random functions return the
+/// cached value until it expires.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    tags: Vec<String>,
+    ttl: Option<Duration>,
+    stale_window: Option<Duration>,
+}
+
+
+impl CachePolicy {
+    /// Create policy without tags.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add tag to cached outputs, so they can be invalidated by the `Terarium::invalidate_tag()`.
+    /// The tag is rendered with the render context, e.g. `product:{{ id }}`.
+    pub fn tag(mut self, tag: String) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Set how long are outputs cached. Outputs are cached until invalidated by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Serve outputs older than the TTL for the `window` and re-render them in background (feature `async`).
+    /// Outputs are re-rendered before they are returned when the render does not run in a Tokio runtime or the instance
+    /// was not built by the `TerariumBuilder::build_shared()`.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_window = Some(window);
+        self
+    }
+
+    /// Return tags of cached outputs.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Return how long are outputs cached.
+    pub fn time_to_live(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Return how long are outputs served after their TTL while they are re-rendered.
+    pub fn stale_window(&self) -> Option<Duration> {
+        self.stale_window
+    }
+
+    /// Return how long must be outputs kept by the storage.
+    pub(crate) fn storage_ttl(&self) -> Option<Duration> {
+        self.ttl.map(|ttl| ttl + self.stale_window.unwrap_or_default())
+    }
+
+    /// Return freshness of the output stored at the time.
+    pub(crate) fn freshness(&self, stored_at: SystemTime) -> Freshness {
+        let Some(ttl) = self.ttl else { return Freshness::Fresh };
+        let age = stored_at.elapsed().unwrap_or_default();
+        if age < ttl {
+            Freshness::Fresh
+        } else if age < ttl + self.stale_window.unwrap_or_default() {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+}
+
+
+/// Freshness of the cached output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Freshness {
+    /// Output can be served.
+    Fresh,
+    /// Output can be served but it should be re-rendered.
+    Stale,
+    /// Output must be re-rendered.
+    Expired,
+}
+
+
+/// Rendered output stored in the `RenderCache`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedOutput {
+    /// Rendered output.
+    pub output: String,
+    /// Time when the output was stored.
+    pub stored_at: SystemTime,
+}
+
+
+/// Storage of rendered outputs used by the `Terarium` (see `TerariumBuilder::set_render_cache()`).
+/// Failures of the storage are not render failures, so methods do not return errors. Missing output is rendered.
+pub trait RenderCache: Send + Sync {
+    /// Return cached output stored under the key.
+    fn get(&self, key: &str) -> Option<CachedOutput>;
+
+    /// Store the output under the key with its tags for the `ttl` (or until invalidated when not set).
+    fn put(&self, key: &str, output: &str, tags: &[String], ttl: Option<Duration>);
+
+    /// Remove all outputs with the tag and return their number.
+    fn invalidate_tag(&self, tag: &str) -> usize;
+}
+
+
+/// Cache storing rendered outputs in memory of the process.
+#[derive(Debug, Default)]
+pub struct MemoryRenderCache {
+    state: Mutex<MemoryState>,
+}
+
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    entries: HashMap<String, MemoryEntry>,
+    tags: HashMap<String, HashSet<String>>,
+    /// Earliest expiration of stored entries, expired entries are swept by the `put()` after it passes.
+    next_expiration: Option<Instant>,
+}
+
+
+#[derive(Debug)]
+struct MemoryEntry {
+    output: CachedOutput,
+    tags: Vec<String>,
+    expires_at: Option<Instant>,
+}
+
+
+impl RenderCache for MemoryRenderCache {
+    fn get(&self, key: &str) -> Option<CachedOutput> {
+        let mut state = self.state.lock().ok()?;
+        let entry = state.entries.get(key)?;
+        match entry.expires_at {
+            Some(expires_at) if expires_at <= Instant::now() => {
+                state.remove(key);
+                None
+            }
+            _ => Some(entry.output.clone()),
+        }
+    }
+
+    fn put(&self, key: &str, output: &str, tags: &[String], ttl: Option<Duration>) {
+        let Ok(mut state) = self.state.lock() else { return };
+        state.sweep();
+        for tag in tags.iter() {
+            state.tags.entry(tag.clone()).or_default().insert(key.to_owned());
+        }
+        let entry = MemoryEntry {
+            output: CachedOutput { output: output.to_owned(), stored_at: SystemTime::now() },
+            tags: tags.to_vec(),
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        state.next_expiration = match (state.next_expiration, entry.expires_at) {
+            (Some(next), Some(expires_at)) => Some(next.min(expires_at)),
+            (next, expires_at) => next.or(expires_at),
+        };
+        if let Some(old) = state.entries.insert(key.to_owned(), entry) {
+            let stale_tags: Vec<_> = old.tags.into_iter().filter(|t| !tags.contains(t)).collect();
+            state.untag(key, &stale_tags);
+        }
+    }
+
+    fn invalidate_tag(&self, tag: &str) -> usize {
+        let Ok(mut state) = self.state.lock() else { return 0 };
+        let keys = state.tags.remove(tag).unwrap_or_default();
+        for key in keys.iter() {
+            state.remove(key);
+        }
+        keys.len()
+    }
+}
+
+
+impl MemoryState {
+    /// Remove the entry and its key from tags.
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.untag(key, &entry.tags);
+        }
+    }
+
+    /// Remove all expired entries once the earliest expiration passes.
+    fn sweep(&mut self) {
+        let now = Instant::now();
+        if self.next_expiration.map_or(true, |next| next > now) {
+            return;
+        }
+        let expired: Vec<_> = self.entries
+            .iter()
+            .filter(|(_, entry)| entry.expires_at.is_some_and(|expires_at| expires_at <= now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired.iter() {
+            self.remove(key);
+        }
+        self.next_expiration = self.entries.values().filter_map(|entry| entry.expires_at).min();
+    }
+
+    fn untag(&mut self, key: &str, tags: &[String]) {
+        for tag in tags {
+            if let Some(keys) = self.tags.get_mut(tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+    }
+}
+
+
+/// Return cache key of the content rendered with the context in the current render scope.
+/// Options of the scope changing the output (timezone, locale, fallback languages of snippets, rendering of missing
+/// variables, requested random seed and time) are part of the key.
+pub(crate) fn cache_key(templates_fingerprint: u64, content_key: &str, language: &str, context: &Context) -> String {
+    let mut hasher = Sha256::new();
+    for part in [&templates_fingerprint.to_string(), content_key, language] {
+        hasher.update(part.as_bytes());
+        hasher.update([0xff]);
+    }
+    with_current(|scope| {
+        let Some(scope) = scope else { return };
+        let time = scope.time.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok());
+        let parts = [
+            scope.timezone.clone().unwrap_or_default(),
+            scope.locale.clone().unwrap_or_default(),
+            scope.languages.join(","),
+            format!("{:?}", scope.missing_variables),
+            format!("{:?}", scope.requested_seed),
+            format!("{:?}", time.map(|time| time.as_nanos())),
+        ];
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0xff]);
+        }
+    });
+    hasher.update(context.clone().into_json().to_string().as_bytes());
+    format!("terarium:{:x}", hasher.finalize())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::MissingVariables;
+    use crate::scope::{RenderScope, with_scope};
+
+    use super::*;
+
+    #[test]
+    fn invalidate_tag() {
+        let cache = MemoryRenderCache::default();
+        cache.put("a", "A", &["product:1".to_owned(), "products".to_owned()], None);
+        cache.put("b", "B", &["product:2".to_owned(), "products".to_owned()], None);
+        assert_eq!(cache.invalidate_tag("product:1"), 1);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b").unwrap().output, "B");
+        assert_eq!(cache.invalidate_tag("products"), 1);
+        assert!(cache.state.lock().unwrap().tags.is_empty());
+    }
+
+    #[test]
+    fn expiration() {
+        let cache = MemoryRenderCache::default();
+        cache.put("a", "A", &[], Some(Duration::ZERO));
+        cache.put("b", "B", &[], Some(Duration::from_secs(60)));
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b").unwrap().output, "B");
+    }
+
+    #[test]
+    fn expired_entries_are_removed() {
+        let cache = MemoryRenderCache::default();
+        cache.put("a", "A", &["products".to_owned()], Some(Duration::ZERO));
+        assert_eq!(cache.get("a"), None);
+        assert!(!cache.state.lock().unwrap().entries.contains_key("a"));
+        assert!(cache.state.lock().unwrap().tags.is_empty());
+
+        cache.put("b", "B", &["products".to_owned()], Some(Duration::ZERO));
+        cache.put("c", "C", &[], None);
+        let state = cache.state.lock().unwrap();
+        assert!(!state.entries.contains_key("b"));
+        assert!(state.entries.contains_key("c"));
+        assert!(state.tags.is_empty());
+    }
+
+    #[test]
+    fn freshness() {
+        let policy = CachePolicy::new().ttl(Duration::from_secs(60)).stale_while_revalidate(Duration::from_secs(30));
+        let stored_at = |seconds: u64| SystemTime::now() - Duration::from_secs(seconds);
+        assert_eq!(policy.freshness(stored_at(10)), Freshness::Fresh);
+        assert_eq!(policy.freshness(stored_at(70)), Freshness::Stale);
+        assert_eq!(policy.freshness(stored_at(100)), Freshness::Expired);
+        assert_eq!(policy.storage_ttl(), Some(Duration::from_secs(90)));
+        assert_eq!(CachePolicy::new().freshness(stored_at(1000)), Freshness::Fresh);
+    }
+
+    #[test]
+    fn key_depends_on_context() {
+        let mut context = Context::new();
+        context.insert("id", &1);
+        let key = cache_key(1, "content", "en", &context);
+        assert_eq!(key, cache_key(1, "content", "en", &context.clone()));
+        assert_ne!(key, cache_key(2, "content", "en", &context));
+        context.insert("id", &2);
+        assert_ne!(key, cache_key(1, "content", "en", &context));
+    }
+
+    #[test]
+    fn key_depends_on_fallback_languages() {
+        let key = |languages: &[&str]| {
+            let scope = RenderScope {
+                languages: languages.iter().map(|l| l.to_string()).collect(),
+                ..RenderScope::default()
+            };
+            with_scope(scope, || cache_key(1, "content", "cs", &Context::new()))
+        };
+        assert_eq!(key(&["cs", "en"]), key(&["cs", "en"]));
+        assert_ne!(key(&["cs", "en"]), key(&["cs", "de"]));
+    }
+
+    #[test]
+    fn key_depends_on_render_options() {
+        let key = |scope: RenderScope| with_scope(scope, || cache_key(1, "content", "en", &Context::new()));
+        let base = key(RenderScope::default());
+        assert_ne!(base, key(RenderScope { missing_variables: Some(MissingVariables::Marker), ..RenderScope::default() }));
+        assert_ne!(base, key(RenderScope { requested_seed: Some(7), ..RenderScope::default() }));
+        assert_ne!(base, key(RenderScope { time: Some(SystemTime::UNIX_EPOCH), ..RenderScope::default() }));
+    }
+}
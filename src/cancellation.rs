@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+
+/// Signal aborting the `Terarium::render_group_async_cancellable()` between group members.
+/// Implemented for `AtomicBool` and for closures, so tokens of other crates can be used as
+/// `move || token.is_cancelled()` (e.g. `tokio_util::sync::CancellationToken`).
+pub trait Cancellation: Send + Sync {
+    /// Return `true` when the render should be aborted.
+    fn is_cancelled(&self) -> bool;
+}
+
+
+impl Cancellation for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+
+impl<F: Fn() -> bool + Send + Sync> Cancellation for F {
+    fn is_cancelled(&self) -> bool {
+        self()
+    }
+}
+
+
+impl<C: Cancellation + ?Sized> Cancellation for Arc<C> {
+    fn is_cancelled(&self) -> bool {
+        self.as_ref().is_cancelled()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancellation() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let closure = {
+            let flag = flag.clone();
+            move || flag.load(Ordering::Relaxed)
+        };
+        assert!(!flag.is_cancelled());
+        assert!(!closure.is_cancelled());
+        flag.store(true, Ordering::Relaxed);
+        assert!(flag.is_cancelled());
+        assert!(closure.is_cancelled());
+    }
+}
@@ -0,0 +1,86 @@
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::fingerprint::Fingerprint;
+use crate::TerariumError;
+
+
+/// Synthetic failures and delays injected into template renders, so retry and fallback logic of applications
+/// can be exercised (see `TerariumBuilder::set_chaos_mode()`).
+/// Chaos is never applied in the `Profile::Production`. Renders are picked pseudo-randomly from the seed,
+/// so the same sequence of renders is affected in each run.
+#[derive(Clone, Debug, Default)]
+pub struct ChaosMode {
+    failure_rate: f64,
+    delay_rate: f64,
+    delay: Duration,
+    seed: u64,
+    renders: Arc<AtomicU64>,
+}
+
+
+impl ChaosMode {
+    /// Create chaos mode affecting no renders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the `rate` (from 0 to 1) of template renders by the `TerariumError::InjectedFailure`.
+    pub fn failures(mut self, rate: f64) -> Self {
+        self.failure_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay the `rate` (from 0 to 1) of template renders by the `delay`.
+    pub fn delays(mut self, rate: f64, delay: Duration) -> Self {
+        self.delay_rate = rate.clamp(0.0, 1.0);
+        self.delay = delay;
+        self
+    }
+
+    /// Seed picking of affected renders.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Delay or fail the render of the template when it is picked.
+    pub(crate) fn apply(&self, template_key: &str) -> Result<(), TerariumError> {
+        let render = self.renders.fetch_add(1, Ordering::Relaxed);
+        if self.roll(render, 0) < self.delay_rate {
+            thread::sleep(self.delay);
+        }
+        match self.roll(render, 1) < self.failure_rate {
+            true => Err(TerariumError::InjectedFailure(template_key.to_owned())),
+            false => Ok(()),
+        }
+    }
+
+    /// Return pseudo-random number from 0 to 1 for the render and the purpose.
+    fn roll(&self, render: u64, purpose: u8) -> f64 {
+        let mut hasher = Fingerprint::default();
+        hasher.write_u64(self.seed);
+        hasher.write_u64(render);
+        hasher.write_u8(purpose);
+        (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates() {
+        let count_failures = |chaos: &ChaosMode| (0..1000).filter(|_| chaos.apply("tpl").is_err()).count();
+        assert_eq!(count_failures(&ChaosMode::new()), 0);
+        assert_eq!(count_failures(&ChaosMode::new().failures(1.0)), 1000);
+        let failures = count_failures(&ChaosMode::new().failures(0.3).seed(7));
+        assert!((200..400).contains(&failures), "{} failures", failures);
+        assert_eq!(count_failures(&ChaosMode::new().failures(0.3).seed(7)), failures);
+    }
+}
@@ -0,0 +1,77 @@
+use tera::Context;
+
+
+/// Marker required in rendered outputs by the `ComplianceRule`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComplianceMarker {
+    /// Output must contain the value of the context variable (e.g. `unsubscribe_url`).
+    Variable(String),
+    /// Output must contain the text (e.g. specific comment).
+    Text(String),
+}
+
+
+/// Guardrail requiring rendered HTML and text contents of templates with the tag to contain the marker
+/// (e.g. unsubscribe link in `marketing` templates). Render fails when the marker is missing.
+/// Rules are added by the `TerariumBuilder::add_compliance_rule()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComplianceRule {
+    /// Tag of templates the rule applies to (see `Template::add_tag()`).
+    pub tag: String,
+    /// Marker required in outputs.
+    pub marker: ComplianceMarker,
+}
+
+
+impl ComplianceRule {
+    /// Create rule requiring the value of the context `variable` in outputs of templates with the `tag`.
+    pub fn variable(tag: String, variable: String) -> Self {
+        Self { tag, marker: ComplianceMarker::Variable(variable) }
+    }
+
+    /// Create rule requiring the `text` in outputs of templates with the `tag`.
+    pub fn text(tag: String, text: String) -> Self {
+        Self { tag, marker: ComplianceMarker::Text(text) }
+    }
+
+    /// Return description of the violation when the `output` rendered with the `context` lacks the marker.
+    pub fn check(&self, output: &str, context: &Context) -> Result<(), String> {
+        match &self.marker {
+            ComplianceMarker::Variable(variable) => {
+                let value = match context.get(variable) {
+                    Some(tera::Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                    None => return Err(format!("Context variable {} required by tag {} is missing", variable, self.tag)),
+                };
+                match !value.is_empty() && output.contains(&value) {
+                    true => Ok(()),
+                    false => Err(format!("Output of template tagged {} must contain {}", self.tag, variable)),
+                }
+            }
+            ComplianceMarker::Text(text) => match output.contains(text.as_str()) {
+                true => Ok(()),
+                false => Err(format!("Output of template tagged {} must contain {}", self.tag, text)),
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let mut context = Context::new();
+        context.insert("unsubscribe_url", "https://example.com/u/1");
+        let rule = ComplianceRule::variable("marketing".to_owned(), "unsubscribe_url".to_owned());
+        assert!(rule.check("<a href=\"https://example.com/u/1\">", &context).is_ok());
+        assert!(rule.check("<p>no link</p>", &context).is_err());
+        assert!(rule.check("https://example.com/u/1", &Context::new()).is_err());
+
+        let rule = ComplianceRule::text("marketing".to_owned(), "<!-- unsubscribe -->".to_owned());
+        assert!(rule.check("<!-- unsubscribe -->", &context).is_ok());
+        assert!(rule.check("", &context).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+
+/// Resources used by one render call.
+/// Returned by the `Terarium::render()` when requested by the `RenderRequest::with_cost()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderCost {
+    /// Duration of the whole call.
+    pub wall_time: Duration,
+    /// Time spent in the `Tera` rendering contents. Outputs served from the cache take no time.
+    pub tera_time: Duration,
+    /// Time spent by post-processing and validation of outputs.
+    pub postprocess_time: Duration,
+    /// Total size of outputs in bytes.
+    pub output_bytes: usize,
+}
+
+
+thread_local! {
+    static METER: RefCell<Option<RenderCost>> = const { RefCell::new(None) };
+}
+
+
+/// Call the `f` and return its result with the cost of renders made by it.
+/// The output size is not measured.
+pub(crate) fn measure<R>(f: impl FnOnce() -> R) -> (R, RenderCost) {
+    let previous = METER.with(|meter| meter.replace(Some(RenderCost::default())));
+    let start = Instant::now();
+    let result = f();
+    let mut cost = METER.with(|meter| meter.replace(previous)).unwrap_or_default();
+    cost.wall_time = start.elapsed();
+    (result, cost)
+}
+
+
+/// Call the `f` and add its duration to the cost measured by the `measure()` by the `add`.
+pub(crate) fn timed<R>(add: impl FnOnce(&mut RenderCost, Duration), f: impl FnOnce() -> R) -> R {
+    let start = Instant::now();
+    let result = f();
+    METER.with(|meter| {
+        if let Some(cost) = meter.borrow_mut().as_mut() {
+            add(cost, start.elapsed());
+        }
+    });
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_nested() {
+        let ((_, inner), outer) = measure(|| {
+            timed(|cost, time| cost.tera_time += time, || std::thread::sleep(Duration::from_millis(2)));
+            measure(|| timed(|cost, time| cost.postprocess_time += time, || ()))
+        });
+        assert!(outer.tera_time >= Duration::from_millis(2));
+        assert_eq!(outer.postprocess_time, Duration::ZERO);
+        assert_eq!(inner.tera_time, Duration::ZERO);
+        assert!(outer.wall_time >= outer.tera_time);
+    }
+}
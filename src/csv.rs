@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use tera::{Error as TeraError, Result as TeraResult, Value};
+
+use crate::OutputValidator;
+
+
+/// Characters starting formulas in spreadsheet applications.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+
+/// Escape value as a CSV field. Text values starting like formulas are prefixed by apostrophe, so spreadsheets
+/// do not evaluate them, and fields containing the separator, quotes or line breaks are quoted.
+/// The separator is given by the `separator` argument (`,` by default, use `"\t"` for TSV).
+pub(crate) fn csv_escape_filter(value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let separator = match args.get("separator") {
+        Some(Value::String(separator)) => separator.as_str(),
+        Some(_) => return Err(TeraError::msg("Filter `csv_escape` requires string `separator` argument")),
+        None => ",",
+    };
+    let mut text = match value {
+        Value::String(s) if s.starts_with(FORMULA_PREFIXES) => format!("'{}", s),
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    if text.contains(separator) || text.contains(['"', '\n', '\r']) {
+        text = format!("\"{}\"", text.replace('"', "\"\""));
+    }
+    Ok(Value::String(text))
+}
+
+
+/// Check all records of the CSV output have the same number of fields.
+/// Quoted fields may contain separators and line breaks, empty lines are ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct CsvValidator {
+    separator: char,
+}
+
+
+impl CsvValidator {
+    /// Create validator of comma separated values.
+    pub fn csv() -> Self {
+        Self { separator: ',' }
+    }
+
+    /// Create validator of tab separated values.
+    pub fn tsv() -> Self {
+        Self { separator: '\t' }
+    }
+
+    /// Create validator of values separated by the `separator`.
+    pub fn new(separator: char) -> Self {
+        Self { separator }
+    }
+}
+
+
+impl Default for CsvValidator {
+    fn default() -> Self {
+        Self::csv()
+    }
+}
+
+
+impl OutputValidator for CsvValidator {
+    fn validate(&self, output: &str) -> Result<(), String> {
+        let mut expected = None;
+        let (mut fields, mut line, mut record_line) = (1, 1, 1);
+        let (mut quoted, mut empty) = (false, true);
+        let mut finish_record = |fields: usize, empty: bool, record_line: usize| match (expected, empty) {
+            (_, true) => Ok(()),
+            (None, false) => {
+                expected = Some(fields);
+                Ok(())
+            }
+            (Some(expected), false) if expected == fields => Ok(()),
+            (Some(expected), false) => {
+                Err(format!("Record on line {} has {} fields but {} expected", record_line, fields, expected))
+            }
+        };
+        for c in output.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                '\n' if !quoted => {
+                    finish_record(fields, empty, record_line)?;
+                    (fields, empty, record_line) = (1, true, line + 1);
+                }
+                c if c == self.separator && !quoted => fields += 1,
+                _ => {}
+            }
+            if c == '\n' {
+                line += 1;
+            } else if c != '\r' {
+                empty = false;
+            }
+        }
+        if quoted {
+            return Err(format!("Quoted field on line {} is not closed", record_line));
+        }
+        finish_record(fields, empty, record_line)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        let escape = |value: Value, separator: Option<&str>| {
+            let args = separator.map(|s| HashMap::from([("separator".to_owned(), Value::from(s))])).unwrap_or_default();
+            csv_escape_filter(&value, &args).unwrap()
+        };
+        assert_eq!(escape(Value::from("plain"), None), "plain");
+        assert_eq!(escape(Value::from("a,b"), None), "\"a,b\"");
+        assert_eq!(escape(Value::from("say \"hi\""), None), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape(Value::from("=SUM(A1:A2)"), None), "'=SUM(A1:A2)");
+        assert_eq!(escape(Value::from("-1,5"), Some("\t")), "'-1,5");
+        assert_eq!(escape(Value::from(-15), None), "-15");
+        assert_eq!(escape(Value::from("a\tb"), Some("\t")), "\"a\tb\"");
+    }
+
+    #[test]
+    fn validate() {
+        assert!(CsvValidator::csv().validate("name,note\njohn,\"a,\nb\"\n\ndoe,x\r\n").is_ok());
+        assert_eq!(
+            CsvValidator::csv().validate("a,b\nc\n").unwrap_err(),
+            "Record on line 2 has 1 fields but 2 expected",
+        );
+        assert_eq!(CsvValidator::csv().validate("a,\"b\nc").unwrap_err(), "Quoted field on line 1 is not closed");
+        assert!(CsvValidator::tsv().validate("a\tb,c\nd\te").is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+use tera::{Error as TeraError, Result as TeraResult, Tera, Value};
+
+use crate::fingerprint::Fingerprint;
+use crate::scope::with_current;
+
+
+/// Handling of nondeterministic template functions (`now()` and `get_random()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Determinism {
+    /// Functions work as usual.
+    #[default]
+    Allow,
+    /// Render fails when any of the functions is called.
+    Forbid,
+    /// The `now()` returns the `now` value of the render context and the `get_random()` returns pseudo-random
+    /// numbers seeded by the `RenderRequest::with_random_seed()` or by the `random_seed` value of the render context
+    /// (zero when missing).
+    Replace,
+}
+
+
+/// Register replacements of nondeterministic functions.
+pub(crate) fn register_functions(tera: &mut Tera, determinism: Determinism) {
+    match determinism {
+        Determinism::Allow => {}
+        Determinism::Forbid => {
+            tera.register_function("now", forbidden("now"));
+            tera.register_function("get_random", forbidden("get_random"));
+        }
+        Determinism::Replace => {
+            tera.register_function("now", replaced_now);
+            tera.register_function("get_random", replaced_get_random);
+        }
+    }
+}
+
+
+fn forbidden(name: &'static str) -> impl Fn(&HashMap<String, Value>) -> TeraResult<Value> + Send + Sync {
+    move |_| Err(TeraError::msg(format!("Function `{}` is forbidden in deterministic mode", name)))
+}
+
+
+fn replaced_now(_args: &HashMap<String, Value>) -> TeraResult<Value> {
+    with_current(|scope| scope.and_then(|s| s.now.clone()))
+        .ok_or_else(|| TeraError::msg("Function `now` requires `now` value in the context in deterministic mode"))
+}
+
+
+fn replaced_get_random(args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let start = args.get("start").map(|v| v.as_i64()).unwrap_or(Some(0));
+    let end = args.get("end").and_then(|v| v.as_i64());
+    let (Some(start), Some(end)) = (start, end) else {
+        return Err(TeraError::msg("Function `get_random` requires numeric `end` and optional numeric `start`"));
+    };
+    if start >= end {
+        return Err(TeraError::msg("Function `get_random` requires `start` lower than `end`"));
+    }
+    let random = with_current(|scope| {
+        let mut hasher = Fingerprint::default();
+        if let Some(scope) = scope {
+            hasher.write_u64(scope.random_seed);
+            hasher.write_u64(scope.random_calls.get());
+            scope.random_calls.set(scope.random_calls.get() + 1);
+        }
+        hasher.finish()
+    });
+    // the span of `i64` bounds does not fit into `i64`
+    let span = (end as i128 - start as i128) as u128;
+    Ok(Value::from((start as i128 + (random as u128 % span) as i128) as i64))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::{RenderScope, with_scope};
+
+    use super::*;
+
+    #[test]
+    fn replaced_functions() {
+        let args = HashMap::from([("end".to_owned(), Value::from(1000))]);
+        let render = || {
+            let scope = RenderScope { now: Some(Value::from("2024-01-01T00:00:00Z")), ..RenderScope::default() };
+            with_scope(scope, || {
+                let now = replaced_now(&HashMap::new()).unwrap();
+                let randoms: Vec<_> = (0..5).map(|_| replaced_get_random(&args).unwrap()).collect();
+                (now, randoms)
+            })
+        };
+        let (now, randoms) = render();
+        assert_eq!(now, "2024-01-01T00:00:00Z");
+        assert_eq!(render().1, randoms);
+        assert!(randoms.iter().any(|r| *r != randoms[0]));
+        assert!(replaced_now(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn random_within_bounds_of_i64() {
+        let args = HashMap::from([
+            ("start".to_owned(), Value::from(i64::MIN)),
+            ("end".to_owned(), Value::from(i64::MAX)),
+        ]);
+        with_scope(RenderScope::default(), || {
+            for _ in 0..10 {
+                let value = replaced_get_random(&args).unwrap().as_i64().unwrap();
+                assert!(value < i64::MAX);
+            }
+        });
+        let args = HashMap::from([
+            ("start".to_owned(), Value::from(i64::MAX - 1)),
+            ("end".to_owned(), Value::from(i64::MAX)),
+        ]);
+        assert_eq!(replaced_get_random(&args).unwrap(), i64::MAX - 1);
+    }
+}
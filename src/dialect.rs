@@ -0,0 +1,405 @@
+/// Template syntax of content sources translated into Tera syntax before the sources are parsed
+/// (see `TerariumBuilder::set_source_dialect()` and `translate_dialect()`).
+/// Only the common subset of differences is translated, e.g. positional filter arguments, Liquid filter names,
+/// `elsif`, `unless`, `assign`, `forloop` and `comment` blocks or iteration over `items()` of a map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SourceDialect {
+    /// Sources are Tera templates and are not translated.
+    #[default]
+    Tera,
+    /// Sources are Jinja2 templates.
+    Jinja2,
+    /// Sources are Liquid templates.
+    Liquid,
+}
+
+
+/// Construct of a source without Tera counterpart, it is left untouched by the translation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UntranslatedConstruct {
+    /// Line of the source (starting by 1).
+    pub line: usize,
+    /// The whole tag containing the construct.
+    pub tag: String,
+}
+
+
+/// Filters with their Tera names and names of positional arguments.
+const JINJA2_FILTERS: [(&str, &str, &[&str]); 10] = [
+    ("default", "default", &["value"]),
+    ("d", "default", &["value"]),
+    ("e", "escape", &[]),
+    ("join", "join", &["sep"]),
+    ("replace", "replace", &["from", "to"]),
+    ("truncate", "truncate", &["length"]),
+    ("round", "round", &["precision", "method"]),
+    ("tojson", "json_encode", &[]),
+    ("indent", "indent", &["width"]),
+    ("wordwrap", "wordwrap", &["width"]),
+];
+
+/// Filters with their Tera names and names of positional arguments.
+const LIQUID_FILTERS: [(&str, &str, &[&str]); 12] = [
+    ("default", "default", &["value"]),
+    ("upcase", "upper", &[]),
+    ("downcase", "lower", &[]),
+    ("strip", "trim", &[]),
+    ("strip_html", "striptags", &[]),
+    ("size", "length", &[]),
+    ("escape", "escape", &[]),
+    ("join", "join", &["sep"]),
+    ("replace", "replace", &["from", "to"]),
+    ("truncate", "truncate", &["length"]),
+    ("date", "date", &["format"]),
+    ("split", "split", &["pat"]),
+];
+
+/// Tags of Jinja2 without Tera counterpart.
+const JINJA2_UNSUPPORTED: [&str; 9] = [
+    "with", "endwith", "do", "call", "endcall", "trans", "endtrans", "autoescape", "endautoescape",
+];
+
+/// Tags of Liquid without Tera counterpart.
+const LIQUID_UNSUPPORTED: [&str; 12] = [
+    "case", "when", "endcase", "capture", "endcapture", "cycle", "tablerow", "endtablerow", "increment", "decrement",
+    "render", "liquid",
+];
+
+/// Attributes of the loop variable without Tera counterpart.
+const UNSUPPORTED_LOOP_ATTRIBUTES: [&str; 6] = ["length", "revindex", "revindex0", "cycle", "depth", "rindex"];
+
+
+/// Translate the source of the dialect into Tera syntax.
+/// Return the translated source and constructs which cannot be translated.
+pub fn translate_dialect(source: &str, dialect: SourceDialect) -> (String, Vec<UntranslatedConstruct>) {
+    let mut untranslated = Vec::new();
+    if dialect == SourceDialect::Tera {
+        return (source.to_owned(), untranslated);
+    }
+    let mut result = String::with_capacity(source.len());
+    let mut position = 0;
+    while let Some(start) = find_tag(source, position) {
+        result.push_str(&source[position..start]);
+        let line = source[..start].matches('\n').count() + 1;
+        let close = match &source[start..start + 2] {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let Some(length) = source[start + 2..].find(close) else {
+            position = start;
+            break;
+        };
+        let end = start + 2 + length + 2;
+        let tag = &source[start..end];
+        position = end;
+        if close == "#}" {
+            result.push_str(tag);
+            continue;
+        }
+        let (open_ws, body, close_ws) = split_whitespace_control(&tag[2..tag.len() - 2]);
+        let keyword = body.split_whitespace().next().unwrap_or_default();
+        if close == "%}" && matches!(keyword, "raw" | "comment") {
+            // contents of raw and comment blocks are kept as they are
+            let end_tag = format!("end{}", keyword);
+            let block_end = find_block_end(source, end, &end_tag).unwrap_or(source.len());
+            let block = &source[end..block_end];
+            match keyword {
+                "raw" => result.push_str(tag),
+                _ => result.push_str("{#"),
+            }
+            result.push_str(block);
+            position = block_end;
+            if keyword == "comment" {
+                result.push_str("#}");
+                position = source[block_end..].find("%}").map_or(source.len(), |i| block_end + i + 2);
+            }
+            continue;
+        }
+        let translated = match close {
+            "}}" => translate_expression(body, dialect).map(|expression| format!(" {} ", expression)),
+            _ => translate_statement(body, dialect).map(|statement| format!(" {} ", statement)),
+        };
+        match translated {
+            Some(translated) => {
+                result.push_str(&tag[..2]);
+                result.push_str(open_ws);
+                result.push_str(&translated);
+                result.push_str(close_ws);
+                result.push_str(&tag[tag.len() - 2..]);
+            }
+            None => {
+                untranslated.push(UntranslatedConstruct { line, tag: tag.to_owned() });
+                result.push_str(tag);
+            }
+        }
+    }
+    result.push_str(&source[position..]);
+    (result, untranslated)
+}
+
+
+/// Return start of the next tag at or after the position.
+fn find_tag(source: &str, position: usize) -> Option<usize> {
+    ["{{", "{%", "{#"].iter().filter_map(|open| source[position..].find(open)).min().map(|i| position + i)
+}
+
+
+/// Return start of the `{% <end_tag> %}` tag after the position.
+fn find_block_end(source: &str, position: usize, end_tag: &str) -> Option<usize> {
+    let mut search = position;
+    while let Some(start) = source[search..].find("{%").map(|i| search + i) {
+        let (_, body, _) = split_whitespace_control(source[start + 2..].split("%}").next().unwrap_or_default());
+        if body.trim() == end_tag {
+            return Some(start);
+        }
+        search = start + 2;
+    }
+    None
+}
+
+
+/// Split the tag body into the whitespace control markers and the trimmed body.
+/// The `+` marker of Jinja2 has no Tera counterpart and is dropped.
+pub(crate) fn split_whitespace_control(body: &str) -> (&str, &str, &str) {
+    let (open, body) = match body.strip_prefix('-') {
+        Some(body) => ("-", body),
+        None => ("", body.strip_prefix('+').unwrap_or(body)),
+    };
+    let (close, body) = match body.strip_suffix('-') {
+        Some(body) => ("-", body),
+        None => ("", body.strip_suffix('+').unwrap_or(body)),
+    };
+    (open, body.trim(), close)
+}
+
+
+/// Translate the statement (body of the `{% %}` tag) or return `None` when it cannot be translated.
+fn translate_statement(body: &str, dialect: SourceDialect) -> Option<String> {
+    let (keyword, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let rest = rest.trim();
+    let unsupported: &[&str] = match dialect {
+        SourceDialect::Liquid => &LIQUID_UNSUPPORTED,
+        _ => &JINJA2_UNSUPPORTED,
+    };
+    if unsupported.contains(&keyword) {
+        return None;
+    }
+    let expression = |expression: &str| translate_expression(expression, dialect);
+    match (dialect, keyword) {
+        (SourceDialect::Liquid, "elsif") => Some(format!("elif {}", expression(rest)?)),
+        (SourceDialect::Liquid, "unless") => Some(format!("if not ({})", expression(rest)?)),
+        (SourceDialect::Liquid, "endunless") => Some("endif".to_owned()),
+        (SourceDialect::Liquid, "assign") => Some(format!("set {}", expression(rest)?)),
+        (_, "for") => {
+            let (variables, collection) = rest.split_once(" in ")?;
+            let collection = collection.trim();
+            // filtered, recursive, limited or reversed loops
+            let extra = |word: &str| {
+                matches!(word, "if" | "recursive" | "reversed")
+                    || word.starts_with("limit:")
+                    || word.starts_with("offset:")
+            };
+            if split_code(collection).iter().any(|(code, literal)| !literal && code.split_whitespace().any(extra)) {
+                return None;
+            }
+            let collection = collection.strip_suffix(".items()").unwrap_or(collection);
+            Some(format!("for {} in {}", variables.trim(), expression(collection)?))
+        }
+        (_, "if" | "elif" | "set" | "set_global") => Some(format!("{} {}", keyword, expression(rest)?)),
+        _ => Some(body.to_owned()),
+    }
+}
+
+
+/// Translate the expression with filters or return `None` when it cannot be translated.
+fn translate_expression(expression: &str, dialect: SourceDialect) -> Option<String> {
+    let parts = split_top_level(expression, '|');
+    let mut result = translate_value(parts[0].trim(), dialect)?;
+    for filter in &parts[1..] {
+        result.push_str(" | ");
+        result.push_str(&translate_filter(filter.trim(), dialect)?);
+    }
+    Some(result)
+}
+
+
+/// Translate the value (expression without filters).
+fn translate_value(value: &str, dialect: SourceDialect) -> Option<String> {
+    let mut result = String::with_capacity(value.len());
+    for (code, literal) in split_code(value) {
+        if literal {
+            result.push_str(code);
+            continue;
+        }
+        let code = match dialect {
+            SourceDialect::Liquid => code.replace("forloop.", "loop."),
+            _ => code.to_owned(),
+        };
+        let unsupported = UNSUPPORTED_LOOP_ATTRIBUTES.iter().any(|attribute| {
+            code.match_indices("loop.").any(|(i, _)| {
+                let name = code[i + 5..].split(|c: char| !c.is_alphanumeric() && c != '_').next();
+                name == Some(attribute)
+            })
+        });
+        if unsupported {
+            return None;
+        }
+        result.push_str(&code);
+    }
+    Some(result)
+}
+
+
+/// Translate the filter (text between pipes) into Tera filter with named arguments.
+fn translate_filter(filter: &str, dialect: SourceDialect) -> Option<String> {
+    let (name, arguments) = match dialect {
+        SourceDialect::Liquid => match filter.split_once(':') {
+            Some((name, arguments)) => (name.trim(), Some(arguments.trim())),
+            None => (filter, None),
+        },
+        _ => match filter.split_once('(') {
+            Some((name, arguments)) => (name.trim(), Some(arguments.trim().strip_suffix(')')?.trim())),
+            None => (filter, None),
+        },
+    };
+    let table: &[(&str, &str, &[&str])] = match dialect {
+        SourceDialect::Liquid => &LIQUID_FILTERS,
+        _ => &JINJA2_FILTERS,
+    };
+    let known = table.iter().find(|(source_name, _, _)| *source_name == name);
+    let tera_name = known.map_or(name, |(_, tera_name, _)| *tera_name);
+    let arguments: Vec<&str> = match arguments {
+        Some(arguments) if !arguments.is_empty() => {
+            split_top_level(arguments, ',').into_iter().map(|a| a.trim()).collect()
+        }
+        _ => return Some(tera_name.to_owned()),
+    };
+    if arguments.iter().all(|argument| is_named(argument)) {
+        return Some(format!("{}({})", tera_name, arguments.join(", ")));
+    }
+    let parameters = known.map(|(_, _, parameters)| *parameters).unwrap_or_default();
+    if arguments.len() > parameters.len() {
+        return None;
+    }
+    let named: Vec<_> = arguments
+        .iter()
+        .zip(parameters)
+        .map(|(argument, parameter)| match is_named(argument) {
+            true => argument.to_string(),
+            false => format!("{}={}", parameter, argument),
+        })
+        .collect();
+    Some(format!("{}({})", tera_name, named.join(", ")))
+}
+
+
+/// Return `true` when the argument is named (`name=value`).
+fn is_named(argument: &str) -> bool {
+    let Some((code, false)) = split_code(argument).first().copied() else { return false };
+    let Some((name, value)) = code.split_once('=') else { return false };
+    let name = name.trim();
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') && !value.starts_with('=')
+}
+
+
+/// Split the text by the separator outside of string literals and brackets.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+
+/// Split the text into code and string literal segments, literals are marked by `true`.
+pub(crate) fn split_code(text: &str) -> Vec<(&str, bool)> {
+    let mut segments = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => {
+                segments.push((&text[start..i + 1], true));
+                start = i + 1;
+                quote = None;
+            }
+            Some(_) => {}
+            None if matches!(c, '"' | '\'' | '`') => {
+                if start < i {
+                    segments.push((&text[start..i], false));
+                }
+                start = i;
+                quote = Some(c);
+            }
+            None => {}
+        }
+    }
+    if start < text.len() {
+        segments.push((&text[start..], quote.is_some()));
+    }
+    segments
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jinja2() {
+        let source = "{{ name|default('guest')|e }} {%- for k, v in data.items() %}{{ k|replace('a', 'b') }}\
+            {% endfor -%}\n{# {{ x|d(1) }} #}{% raw %}{{ x|d(1) }}{% endraw %}{{ items|join(sep=', ') }}";
+        let (translated, untranslated) = translate_dialect(source, SourceDialect::Jinja2);
+        assert_eq!(
+            translated,
+            "{{ name | default(value='guest') | escape }} {%- for k, v in data %}{{ k | replace(from='a', to='b') }}\
+            {% endfor -%}\n{# {{ x|d(1) }} #}{% raw %}{{ x|d(1) }}{% endraw %}{{ items | join(sep=', ') }}",
+        );
+        assert!(untranslated.is_empty());
+
+        let source = "{{ loop.length }}\n{% with a = 1 %}{% for x in xs if x %}{{ x|truncate(1, 2) }}";
+        let (translated, untranslated) = translate_dialect(source, SourceDialect::Jinja2);
+        assert_eq!(translated, source);
+        let lines: Vec<_> = untranslated.iter().map(|u| (u.line, u.tag.as_str())).collect();
+        assert_eq!(lines, vec![
+            (1, "{{ loop.length }}"),
+            (2, "{% with a = 1 %}"),
+            (2, "{% for x in xs if x %}"),
+            (2, "{{ x|truncate(1, 2) }}"),
+        ]);
+    }
+
+    #[test]
+    fn liquid() {
+        let source = "{% assign greeting = 'Hi' | upcase %}{% unless vip %}{{ forloop.index }}{% elsif a %}\
+            {% endunless %}{{ title | truncate: 10 | default: \"x | y\" }}{% comment %}{{ ignored }}{% endcomment %}";
+        let (translated, untranslated) = translate_dialect(source, SourceDialect::Liquid);
+        assert_eq!(
+            translated,
+            "{% set greeting = 'Hi' | upper %}{% if not (vip) %}{{ loop.index }}{% elif a %}{% endif %}\
+            {{ title | truncate(length=10) | default(value=\"x | y\") }}{#{{ ignored }}#}",
+        );
+        assert!(untranslated.is_empty());
+
+        let (_, untranslated) = translate_dialect("{% case x %}{% for i in xs limit: 2 %}", SourceDialect::Liquid);
+        assert_eq!(untranslated.len(), 2);
+        assert_eq!(translate_dialect("{{ x|d(1) }}", SourceDialect::Tera).0, "{{ x|d(1) }}");
+    }
+}
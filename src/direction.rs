@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+
+
+/// Direction of the text in a language.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextDirection {
+    /// Left to right.
+    #[default]
+    Ltr,
+    /// Right to left.
+    Rtl,
+}
+
+
+impl TextDirection {
+    /// Return value of the HTML `dir` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ltr => "ltr",
+            Self::Rtl => "rtl",
+        }
+    }
+
+    /// Return direction of the `language` when languages in `rtl_languages` are right to left.
+    /// Region subtags are ignored when the full tag is not listed, so `ar-EG` is right to left when `ar` is.
+    pub(crate) fn of(rtl_languages: &HashSet<String>, language: &str) -> Self {
+        let primary = language.split(['-', '_']).next().unwrap_or(language);
+        match rtl_languages.contains(language) || rtl_languages.contains(primary) {
+            true => Self::Rtl,
+            false => Self::Ltr,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direction() {
+        let rtl = HashSet::from(["ar".to_owned(), "he-IL".to_owned()]);
+        assert_eq!(TextDirection::of(&rtl, "ar"), TextDirection::Rtl);
+        assert_eq!(TextDirection::of(&rtl, "ar_EG"), TextDirection::Rtl);
+        assert_eq!(TextDirection::of(&rtl, "he-IL"), TextDirection::Rtl);
+        assert_eq!(TextDirection::of(&rtl, "he"), TextDirection::Ltr);
+        assert_eq!(TextDirection::of(&rtl, "en").as_str(), "ltr");
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+
+/// Localized name and description of a template or group presented to users of admin UIs
+/// (see `Template::set_display_name()` and `Terarium::display_name()`).
+/// Both may contain `{variable}` placeholders replaced by the `key`, the `language` and display variables
+/// set by the `TerariumBuilder::add_display_variable()`. Unknown placeholders are kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisplayName {
+    /// Short name of the item.
+    pub name: String,
+    /// Longer description of the item.
+    pub description: Option<String>,
+}
+
+
+impl DisplayName {
+    /// Create display name without description.
+    pub fn new(name: String) -> Self {
+        Self { name, description: None }
+    }
+
+    /// Set description of the item.
+    pub fn with_description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Return copy with placeholders replaced by the `variables`.
+    pub(crate) fn interpolate(&self, variables: &HashMap<&str, &str>) -> Self {
+        Self {
+            name: interpolate(&self.name, variables),
+            description: self.description.as_deref().map(|description| interpolate(description, variables)),
+        }
+    }
+}
+
+
+/// Replace `{variable}` placeholders of the text by values of the variables.
+fn interpolate(text: &str, variables: &HashMap<&str, &str>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        match tail.find('}').and_then(|end| variables.get(tail[..end].trim()).map(|value| (end, value))) {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &tail[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = tail;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        let variables = HashMap::from([("product", "Shop"), ("language", "cs")]);
+        assert_eq!(interpolate("{product} welcome ({ language })", &variables), "Shop welcome (cs)");
+        assert_eq!(interpolate("{unknown} {product", &variables), "{unknown} {product");
+        let display_name = DisplayName::new("{product}".to_owned()).with_description("For {product}".to_owned());
+        assert_eq!(display_name.interpolate(&variables).description.as_deref(), Some("For Shop"));
+    }
+}
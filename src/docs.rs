@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use tera::escape_html;
+
+
+/// Format of the catalog documentation generated by the `Terarium::generate_docs()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Markdown document with one section per template.
+    #[default]
+    Markdown,
+    /// Standalone HTML page with one section per template.
+    Html,
+}
+
+
+/// Documentation of one template.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateDoc {
+    pub(crate) key: String,
+    pub(crate) languages: Vec<String>,
+    pub(crate) required_variables: Vec<String>,
+    /// Metadata names with their values, in order of appearance.
+    pub(crate) metadata: Vec<(&'static str, String)>,
+    pub(crate) groups: Vec<String>,
+    /// Output of the sample render or the error message.
+    pub(crate) sample: Option<Result<String, String>>,
+}
+
+
+/// Write documentation of templates in the format.
+pub(crate) fn write_docs(templates: &[TemplateDoc], format: DocsFormat) -> String {
+    let mut docs = String::new();
+    match format {
+        DocsFormat::Markdown => {
+            docs.push_str("# Template catalog\n");
+            templates.iter().for_each(|template| write_markdown(&mut docs, template));
+        }
+        DocsFormat::Html => {
+            docs.push_str("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Template catalog</title>");
+            docs.push_str("</head>\n<body>\n<h1>Template catalog</h1>\n");
+            templates.iter().for_each(|template| write_html(&mut docs, template));
+            docs.push_str("</body>\n</html>\n");
+        }
+    }
+    docs
+}
+
+
+fn write_markdown(docs: &mut String, template: &TemplateDoc) {
+    let list = |items: &[String]| match items.is_empty() {
+        true => "-".to_owned(),
+        false => items.iter().map(|item| format!("`{}`", item)).collect::<Vec<_>>().join(", "),
+    };
+    let _ = write!(docs, "\n## {}\n\n", template.key);
+    let _ = writeln!(docs, "- Languages: {}", list(&template.languages));
+    let _ = writeln!(docs, "- Required variables: {}", list(&template.required_variables));
+    let _ = writeln!(docs, "- Groups: {}", list(&template.groups));
+    for (name, value) in template.metadata.iter() {
+        let _ = writeln!(docs, "- {}: {}", name, value);
+    }
+    match &template.sample {
+        Some(Ok(output)) => {
+            let fence = "`".repeat(longest_backtick_run(output).max(2) + 1);
+            let _ = write!(docs, "\nSample:\n\n{}\n{}\n{}\n", fence, output, fence);
+        }
+        Some(Err(error)) => {
+            let _ = write!(docs, "\nSample render failed: {}\n", error);
+        }
+        None => {}
+    }
+}
+
+
+fn write_html(docs: &mut String, template: &TemplateDoc) {
+    let list = |items: &[String]| match items.is_empty() {
+        true => "-".to_owned(),
+        false => items.iter().map(|item| format!("<code>{}</code>", escape_html(item))).collect::<Vec<_>>().join(", "),
+    };
+    let _ = writeln!(docs, "<section>\n<h2>{}</h2>\n<ul>", escape_html(&template.key));
+    let _ = writeln!(docs, "<li>Languages: {}</li>", list(&template.languages));
+    let _ = writeln!(docs, "<li>Required variables: {}</li>", list(&template.required_variables));
+    let _ = writeln!(docs, "<li>Groups: {}</li>", list(&template.groups));
+    for (name, value) in template.metadata.iter() {
+        let _ = writeln!(docs, "<li>{}: {}</li>", name, escape_html(value));
+    }
+    docs.push_str("</ul>\n");
+    match &template.sample {
+        Some(Ok(output)) => {
+            let _ = writeln!(docs, "<p>Sample:</p>\n<pre>{}</pre>", escape_html(output));
+        }
+        Some(Err(error)) => {
+            let _ = writeln!(docs, "<p>Sample render failed: {}</p>", escape_html(error));
+        }
+        None => {}
+    }
+    docs.push_str("</section>\n");
+}
+
+
+/// Return length of the longest sequence of backticks, so fences never end inside the output.
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(|run| run.len()).max().unwrap_or_default()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_doc() -> TemplateDoc {
+        TemplateDoc {
+            key: "welcome".to_owned(),
+            languages: vec!["cs".to_owned(), "en".to_owned()],
+            required_variables: vec!["user".to_owned()],
+            metadata: vec![("Owner", "growth <team>".to_owned())],
+            groups: vec![],
+            sample: Some(Ok("Hi ```john```".to_owned())),
+        }
+    }
+
+    #[test]
+    fn markdown() {
+        let docs = write_docs(&[make_doc()], DocsFormat::Markdown);
+        assert_eq!(
+            docs,
+            "# Template catalog\n\n## welcome\n\n- Languages: `cs`, `en`\n- Required variables: `user`\n- Groups: -\n\
+            - Owner: growth <team>\n\nSample:\n\n````\nHi ```john```\n````\n",
+        );
+    }
+
+    #[test]
+    fn html() {
+        let mut doc = make_doc();
+        doc.sample = Some(Err("Variable `user` not found".to_owned()));
+        let docs = write_docs(&[doc], DocsFormat::Html);
+        assert!(docs.contains("<h2>welcome</h2>"));
+        assert!(docs.contains("<li>Owner: growth &lt;team&gt;</li>"));
+        assert!(docs.contains("<p>Sample render failed: Variable `user` not found</p>"));
+        assert!(docs.ends_with("</body>\n</html>\n"));
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use tera::{Filter, Result as TeraResult, Tera, Value};
+
+use crate::csv::csv_escape_filter;
+use crate::ics::ics_escape_filter;
+use crate::scope::with_current;
+
+
+/// Register all filters provided by the `Terarium`.
+pub(crate) fn register_filters(tera: &mut Tera) {
+    tera.register_filter("csv_escape", csv_escape_filter);
+    tera.register_filter("ics_escape", ics_escape_filter);
+    tera.register_filter("date", scoped_date_filter());
+    #[cfg(feature = "filters")]
+    crate::format_filters::register_format_filters(tera);
+}
+
+
+/// Wrap the built-in `date` filter, so timezone and locale of the render are used when not given explicitly.
+fn scoped_date_filter() -> impl Filter {
+    let builtins = Tera::default();
+    move |value: &Value, args: &HashMap<String, Value>| -> TeraResult<Value> {
+        let mut args = args.clone();
+        with_current(|scope| {
+            let Some(scope) = scope else { return };
+            if let Some(timezone) = &scope.timezone {
+                args.entry("timezone".to_owned()).or_insert_with(|| Value::String(timezone.clone()));
+            }
+            #[cfg(feature = "date-locale")]
+            if let Some(locale) = &scope.locale {
+                args.entry("locale".to_owned()).or_insert_with(|| Value::String(locale.clone()));
+            }
+        });
+        builtins.get_filter("date")?.filter(value, &args)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::{RenderScope, with_scope};
+
+    use super::*;
+
+    #[test]
+    fn date_in_scope_timezone() {
+        let filter = scoped_date_filter();
+        let value = Value::from(1_700_000_000);
+        let args = HashMap::from([("format".to_owned(), Value::from("%H:%M"))]);
+        assert_eq!(filter.filter(&value, &args).unwrap(), "22:13");
+
+        let scope = RenderScope { timezone: Some("Europe/Prague".to_owned()), ..RenderScope::default() };
+        with_scope(scope, || {
+            assert_eq!(filter.filter(&value, &args).unwrap(), "23:13");
+            let mut args = args.clone();
+            args.insert("timezone".to_owned(), Value::from("UTC"));
+            assert_eq!(filter.filter(&value, &args).unwrap(), "22:13");
+        });
+    }
+
+    #[cfg(feature = "date-locale")]
+    #[test]
+    fn date_in_scope_locale() {
+        let filter = scoped_date_filter();
+        let args = HashMap::from([("format".to_owned(), Value::from("%B"))]);
+        let scope = RenderScope { locale: Some("cs_CZ".to_owned()), ..RenderScope::default() };
+        let month = with_scope(scope, || filter.filter(&Value::from("2024-01-15"), &args)).unwrap();
+        assert_eq!(month, "ledna");
+    }
+}
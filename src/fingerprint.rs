@@ -0,0 +1,64 @@
+use std::hash::Hasher;
+
+
+const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const PRIME: u64 = 0x100000001b3;
+
+
+/// Stable FNV-1a hasher used for fingerprints.
+/// Unlike the `DefaultHasher`, the result is the same across processes and Rust releases.
+pub(crate) struct Fingerprint(u64);
+
+
+impl Fingerprint {
+    /// Write the string terminated by separator, so `("ab", "c")` and `("a", "bc")` differ.
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        self.write_u8(0xff);
+    }
+}
+
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self(OFFSET_BASIS)
+    }
+}
+
+
+impl Hasher for Fingerprint {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_is_stable() {
+        let mut hasher = Fingerprint::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn strings_are_separated() {
+        let mut a = Fingerprint::default();
+        a.write_str("ab");
+        a.write_str("c");
+        let mut b = Fingerprint::default();
+        b.write_str("a");
+        b.write_str("bc");
+        assert_ne!(a.finish(), b.finish());
+    }
+}
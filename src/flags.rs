@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tera::{Error as TeraError, Function, Result as TeraResult, Value};
+
+use crate::scope::with_current;
+
+
+/// Context variable identifying the recipient of the render, passed to the `FlagProvider` and the `ValueResolver`.
+pub const RECIPIENT_VARIABLE: &str = "recipient_id";
+
+
+/// Evaluate feature flags during render.
+/// Templates call `{{ feature(name="new_footer") }}`, the recipient is taken from the `recipient_id` context value
+/// unless given by the `recipient` argument.
+/// The provider is set by the `TerariumBuilder::set_flag_provider()`.
+pub trait FlagProvider: Send + Sync {
+    /// Return `true` when the flag is enabled for the recipient.
+    fn is_enabled(&self, flag: &str, recipient: Option<&Value>) -> bool;
+}
+
+
+impl<F> FlagProvider for F where F: Fn(&str, Option<&Value>) -> bool + Send + Sync {
+    fn is_enabled(&self, flag: &str, recipient: Option<&Value>) -> bool {
+        self(flag, recipient)
+    }
+}
+
+
+/// Create the `feature()` template function backed by the `provider`.
+pub(crate) fn feature_function(provider: Arc<dyn FlagProvider>) -> impl Function {
+    move |args: &HashMap<String, Value>| -> TeraResult<Value> {
+        let flag = args
+            .get("name")
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| TeraError::msg("Function `feature` requires `name` argument"))?;
+        let enabled = match args.get("recipient") {
+            Some(recipient) => provider.is_enabled(flag, Some(recipient)),
+            None => with_current(|scope| provider.is_enabled(flag, scope.and_then(|s| s.recipient.as_ref()))),
+        };
+        Ok(Value::Bool(enabled))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::{RenderScope, with_scope};
+
+    use super::*;
+
+    #[test]
+    fn feature() {
+        let function = feature_function(Arc::new(|flag: &str, recipient: Option<&Value>| {
+            flag == "new_footer" && recipient == Some(&Value::from(7))
+        }));
+        let args = |flag: &str| HashMap::from([("name".to_owned(), Value::from(flag))]);
+        let scope = RenderScope { recipient: Some(Value::from(7)), ..RenderScope::default() };
+
+        with_scope(scope, || {
+            assert_eq!(function.call(&args("new_footer")).unwrap(), Value::Bool(true));
+            assert_eq!(function.call(&args("other")).unwrap(), Value::Bool(false));
+        });
+        assert_eq!(function.call(&args("new_footer")).unwrap(), Value::Bool(false));
+        let mut with_recipient = args("new_footer");
+        with_recipient.insert("recipient".to_owned(), Value::from(7));
+        assert_eq!(function.call(&with_recipient).unwrap(), Value::Bool(true));
+        assert!(function.call(&HashMap::new()).is_err());
+    }
+}
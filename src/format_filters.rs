@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use tera::{Error as TeraError, Result as TeraResult, Tera, Value};
+
+use crate::scope::with_current;
+
+
+/// Non-breaking space used between number and unit.
+const NBSP: char = '\u{a0}';
+
+/// Maximal number of fraction digits accepted by the `decimals` argument.
+const MAX_DECIMALS: usize = 20;
+
+/// Units of the `filesize` filter.
+const SIZE_UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+
+/// Units of the `duration` filter with their lengths in seconds.
+const DURATION_UNITS: [(&str, u64); 4] = [("d", 86_400), ("h", 3_600), ("min", 60), ("s", 1)];
+
+
+/// Number formatting conventions of a language.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NumberFormat {
+    decimal: char,
+    group: Option<char>,
+    currency_first: bool,
+}
+
+
+impl NumberFormat {
+    /// Return conventions of the language (e.g. `cs`, `en-US` or `de_AT`), English ones are used for unknown languages.
+    fn of(language: &str) -> Self {
+        let primary = language.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        let (decimal, group, currency_first) = match primary.as_str() {
+            "cs" | "sk" | "pl" | "fr" | "ru" | "uk" | "sv" | "fi" | "nb" | "hu" => (',', Some(NBSP), false),
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "ro" | "hr" | "sl" => (',', Some('.'), false),
+            _ => ('.', Some(','), true),
+        };
+        Self { decimal, group, currency_first }
+    }
+
+    /// Format the number with `decimals` fraction digits.
+    fn format(&self, number: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, number.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let mut result = String::with_capacity(formatted.len() + integer.len() / 3 + 1);
+        if number < 0.0 && formatted.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            result.push('-');
+        }
+        for (i, c) in integer.chars().enumerate() {
+            if let Some(group) = self.group.filter(|_| i > 0 && (integer.len() - i) % 3 == 0) {
+                result.push(group);
+            }
+            result.push(c);
+        }
+        if !fraction.is_empty() {
+            result.push(self.decimal);
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+
+/// Register `money`, `filesize` and `duration` filters.
+pub(crate) fn register_format_filters(tera: &mut Tera) {
+    tera.register_filter("money", money_filter);
+    tera.register_filter("filesize", filesize_filter);
+    tera.register_filter("duration", duration_filter);
+}
+
+
+/// Format amount of money in the currency, e.g. `{{ price | money(currency="EUR") }}`.
+/// Number of fraction digits can be set by the `decimals` argument (at most 20).
+fn money_filter(value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let amount = number_value("money", value)?;
+    let currency = args
+        .get("currency")
+        .and_then(|c| c.as_str())
+        .ok_or_else(|| TeraError::msg("Filter `money` requires `currency` argument"))?;
+    let (symbol, default_decimals) = currency_symbol(currency);
+    let decimals = match args.get("decimals").and_then(|d| d.as_u64()) {
+        Some(decimals) if decimals > MAX_DECIMALS as u64 => {
+            return Err(TeraError::msg(format!("Filter `money` accepts at most {} decimals", MAX_DECIMALS)));
+        }
+        Some(decimals) => decimals as usize,
+        None => default_decimals,
+    };
+    let format = number_format(args);
+    let number = format.format(amount, decimals);
+
+    let formatted = match (format.currency_first, symbol == currency) {
+        (true, false) => match number.strip_prefix('-') {
+            Some(number) => format!("-{}{}", symbol, number),
+            None => format!("{}{}", symbol, number),
+        },
+        (true, true) => format!("{}{}{}", symbol, NBSP, number),
+        (false, _) => format!("{}{}{}", number, NBSP, symbol),
+    };
+    Ok(Value::String(formatted))
+}
+
+
+/// Format number of bytes with decimal units, e.g. `1.5 MB`.
+fn filesize_filter(value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let mut size = number_value("filesize", value)?;
+    let mut unit = 0;
+    while size.abs() >= 1000.0 && unit < SIZE_UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    let decimals = if unit == 0 || size.fract() == 0.0 { 0 } else { 1 };
+    let number = number_format(args).format(size, decimals);
+    Ok(Value::String(format!("{}{}{}", number, NBSP, SIZE_UNITS[unit])))
+}
+
+
+/// Format number of seconds as duration with two most significant units, e.g. `1 h 5 min`.
+fn duration_filter(value: &Value, _args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let seconds = number_value("duration", value)?;
+    if seconds < 0.0 {
+        return Err(TeraError::msg("Filter `duration` received negative value"));
+    }
+    let mut remaining = seconds.round() as u64;
+    let mut parts = Vec::new();
+    for (unit, length) in DURATION_UNITS {
+        if remaining >= length || (unit == "s" && parts.is_empty()) {
+            parts.push(format!("{}{}{}", remaining / length, NBSP, unit));
+            remaining %= length;
+        }
+        if parts.len() == 2 || (!parts.is_empty() && remaining == 0) {
+            break;
+        }
+    }
+    Ok(Value::String(parts.join(" ")))
+}
+
+
+/// Return conventions of the `lang` argument or of the language of the rendered content.
+fn number_format(args: &HashMap<String, Value>) -> NumberFormat {
+    match args.get("lang").and_then(|l| l.as_str()) {
+        Some(language) => NumberFormat::of(language),
+        None => with_current(|scope| NumberFormat::of(scope.and_then(|s| s.language.as_deref()).unwrap_or_default())),
+    }
+}
+
+
+fn number_value(filter: &str, value: &Value) -> TeraResult<f64> {
+    value.as_f64().ok_or_else(|| TeraError::msg(format!("Filter `{}` received non-numeric value", filter)))
+}
+
+
+/// Return symbol of the currency and its usual number of fraction digits.
+fn currency_symbol(currency: &str) -> (&str, usize) {
+    match currency {
+        "USD" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" => ("¥", 0),
+        "CZK" => ("Kč", 2),
+        "PLN" => ("zł", 2),
+        other => (other, 2),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::with_updated;
+
+    use super::*;
+
+    fn with_language<R>(language: &str, f: impl FnOnce() -> R) -> R {
+        with_updated(|scope| scope.language = Some(language.to_owned()), f)
+    }
+
+    fn args(values: &[(&str, &str)]) -> HashMap<String, Value> {
+        values.iter().map(|(k, v)| (k.to_string(), Value::from(*v))).collect()
+    }
+
+    #[test]
+    fn money() {
+        let amount = Value::from(-1234567.891);
+        let eur = args(&[("currency", "EUR")]);
+        assert_eq!(money_filter(&amount, &eur).unwrap(), "-€1,234,567.89");
+        assert_eq!(with_language("cs", || money_filter(&amount, &eur)).unwrap(), "-1\u{a0}234\u{a0}567,89\u{a0}€");
+        assert_eq!(with_language("de-AT", || money_filter(&amount, &eur)).unwrap(), "-1.234.567,89\u{a0}€");
+        let chf = args(&[("currency", "CHF"), ("lang", "en")]);
+        assert_eq!(money_filter(&Value::from(5), &chf).unwrap(), "CHF\u{a0}5.00");
+        assert!(money_filter(&amount, &HashMap::new()).is_err());
+        let mut precise = eur.clone();
+        precise.insert("decimals".to_owned(), Value::from(3));
+        assert_eq!(money_filter(&amount, &precise).unwrap(), "-€1,234,567.891");
+        precise.insert("decimals".to_owned(), Value::from(1_000_000_000));
+        assert!(money_filter(&amount, &precise).is_err());
+    }
+
+    #[test]
+    fn filesize() {
+        assert_eq!(filesize_filter(&Value::from(999), &HashMap::new()).unwrap(), "999\u{a0}B");
+        assert_eq!(filesize_filter(&Value::from(2_000_000), &HashMap::new()).unwrap(), "2\u{a0}MB");
+        let size = with_language("cs", || filesize_filter(&Value::from(1_540_000), &HashMap::new())).unwrap();
+        assert_eq!(size, "1,5\u{a0}MB");
+    }
+
+    #[test]
+    fn duration() {
+        let format = |seconds: u64| duration_filter(&Value::from(seconds), &HashMap::new()).unwrap().as_str().unwrap().replace(NBSP, " ");
+        assert_eq!(format(0), "0 s");
+        assert_eq!(format(45), "45 s");
+        assert_eq!(format(3_600), "1 h");
+        assert_eq!(format(3_725), "1 h 2 min");
+        assert_eq!(format(90_061), "1 d 1 h");
+    }
+}
@@ -0,0 +1,134 @@
+use crate::dialect::{split_code, split_whitespace_control};
+
+
+/// Indentation of one level of nested Tera blocks.
+const INDENT: &str = "    ";
+
+/// Statements opening Tera blocks.
+const OPENING_STATEMENTS: [&str; 5] = ["if", "for", "block", "macro", "filter"];
+
+/// Statements closing Tera blocks.
+const CLOSING_STATEMENTS: [&str; 5] = ["endif", "endfor", "endblock", "endmacro", "endfilter"];
+
+/// Statements continuing Tera blocks.
+const CONTINUING_STATEMENTS: [&str; 2] = ["elif", "else"];
+
+
+/// Normalize formatting of the content source, so sources differing only in formatting are the same.
+/// Line endings are converted to `\n`, trailing whitespaces are removed, whitespaces inside tags are collapsed to
+/// single spaces with one space after the opening and before the closing delimiter, and lines containing only
+/// a statement are indented by 4 spaces per level of nested blocks. Contents of `raw` blocks and comments are kept.
+/// Formatting may change whitespaces of rendered outputs.
+pub fn format_content(source: &str) -> String {
+    let source = source.replace("\r\n", "\n").replace('\r', "\n");
+    let mut result = String::with_capacity(source.len());
+    let mut depth = 0usize;
+    let mut raw = false;
+    for (index, line) in source.split('\n').enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        let line = line.trim_end();
+        let statement = only_statement(line);
+        if raw {
+            raw = statement != Some("endraw");
+            result.push_str(line);
+            continue;
+        }
+        let line = format_tags(line);
+        match statement {
+            Some(keyword) => {
+                if CLOSING_STATEMENTS.contains(&keyword) {
+                    depth = depth.saturating_sub(1);
+                }
+                let level = match CONTINUING_STATEMENTS.contains(&keyword) {
+                    true => depth.saturating_sub(1),
+                    false => depth,
+                };
+                result.push_str(&INDENT.repeat(level));
+                result.push_str(line.trim_start());
+                if OPENING_STATEMENTS.contains(&keyword) {
+                    depth += 1;
+                }
+                raw = keyword == "raw";
+            }
+            None => result.push_str(&line),
+        }
+    }
+    result
+}
+
+
+/// Return keyword of the statement when the line contains only one statement tag.
+fn only_statement(line: &str) -> Option<&str> {
+    let body = line.trim().strip_prefix("{%")?.strip_suffix("%}")?;
+    if body.contains("%}") {
+        return None;
+    }
+    split_whitespace_control(body).1.split_whitespace().next()
+}
+
+
+/// Collapse whitespaces inside expression and statement tags of the line.
+fn format_tags(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = ["{{", "{%"].iter().filter_map(|open| rest.find(open)).min() {
+        result.push_str(&rest[..start]);
+        let close = match &rest[start..start + 2] {
+            "{{" => "}}",
+            _ => "%}",
+        };
+        let Some(length) = rest[start + 2..].find(close) else {
+            rest = &rest[start..];
+            break;
+        };
+        let (open_ws, body, close_ws) = split_whitespace_control(&rest[start + 2..start + 2 + length]);
+        result.push_str(&rest[start..start + 2]);
+        result.push_str(open_ws);
+        result.push(' ');
+        for (code, literal) in split_code(body) {
+            match literal {
+                true => result.push_str(code),
+                false => {
+                    let words: Vec<_> = code.split_whitespace().collect();
+                    if code.starts_with(char::is_whitespace) && !result.ends_with(' ') {
+                        result.push(' ');
+                    }
+                    result.push_str(&words.join(" "));
+                    if code.ends_with(char::is_whitespace) && !words.is_empty() {
+                        result.push(' ');
+                    }
+                }
+            }
+        }
+        result.push(' ');
+        result.push_str(close_ws);
+        result.push_str(close);
+        rest = &rest[start + 2 + length + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let source = "<ul>  \r\n{%if items%}\r\n  {%-  for  item in items -%}\r\n<li>{{item |  upper}}</li>\r\n\
+            {% else %}\n{%endfor%}\n{% else %}\n{{ \"a  b\"~x }}\n {% endif %}\n</ul>";
+        let expected = "<ul>\n{% if items %}\n    {%- for item in items -%}\n<li>{{ item | upper }}</li>\n\
+            \x20   {% else %}\n    {% endfor %}\n{% else %}\n{{ \"a  b\"~x }}\n{% endif %}\n</ul>";
+        assert_eq!(format_content(source), expected);
+        assert_eq!(format_content(expected), expected);
+    }
+
+    #[test]
+    fn keep_raw_and_comments() {
+        let source = "{% raw %}\n{{x}}  \n{% endraw %}\n{#  a  #}{{x}}";
+        assert_eq!(format_content(source), "{% raw %}\n{{x}}\n{% endraw %}\n{#  a  #}{{ x }}");
+    }
+}
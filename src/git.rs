@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+use thiserror::Error;
+
+use crate::{DirectoryLoader, LoaderError, Template, Terarium, TerariumBuilderError};
+
+
+/// Load templates from a git repository pinned to a branch, tag or commit.
+///
+/// The repository is fetched into the local checkout directory by the `git` command, so it must be installed.
+/// The command is used instead of a git library (`git2` or `gix`) to keep native libraries and their large dependency
+/// trees out of the crate. Arguments are passed after `--` and the repository or reference starting with `-` is
+/// rejected, so they are never taken for options of the command.
+/// Templates are read from the checkout by the `DirectoryLoader` and each of them has the commit hash set as
+/// its revision (see `Template::set_revision()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitLoader {
+    repository: String,
+    reference: String,
+    checkout: PathBuf,
+    subdirectory: Option<PathBuf>,
+    revision: Option<String>,
+}
+
+
+impl GitLoader {
+    /// Create loader of the `reference` (branch, tag or commit) of the `repository` (URL or path).
+    /// The repository is checked out into the `checkout` directory.
+    pub fn new(repository: String, reference: String, checkout: impl Into<PathBuf>) -> Self {
+        Self { repository, reference, checkout: checkout.into(), subdirectory: None, revision: None }
+    }
+
+    /// Read templates from the subdirectory of the repository instead of its root.
+    pub fn subdirectory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.subdirectory = Some(path.into());
+        self
+    }
+
+    /// Return hash of the commit the templates were loaded from.
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// Fetch the reference, check it out and return loaded templates by their keys.
+    pub fn load(&mut self) -> Result<HashMap<String, Template>, GitError> {
+        let commit = self.fetch()?;
+        let templates = self.load_commit(&commit)?;
+        self.revision = Some(commit);
+        Ok(templates)
+    }
+
+    /// Fetch the reference and rebuild the catalog when the reference moved since the last load.
+    /// The new catalog is built by the `build` from loaded templates and swapped into the `terarium`.
+    /// Return `true` when the catalog was rebuilt. The revision is kept when loading or building fails, so the next
+    /// refresh tries again.
+    pub fn refresh<F>(&mut self, terarium: &mut Terarium, actor: &str, build: F) -> Result<bool, GitError>
+        where
+            F: FnOnce(HashMap<String, Template>) -> Result<Terarium, TerariumBuilderError>,
+    {
+        let commit = self.fetch()?;
+        if self.revision.as_ref() == Some(&commit) {
+            return Ok(false);
+        }
+        let templates = self.load_commit(&commit)?;
+        terarium.swap(build(templates)?, actor)?;
+        self.revision = Some(commit);
+        Ok(true)
+    }
+
+    /// Check out the commit and load templates from it.
+    fn load_commit(&self, commit: &str) -> Result<HashMap<String, Template>, GitError> {
+        self.git(&["checkout", "--quiet", "--force", "--detach", commit])?;
+        let root = match &self.subdirectory {
+            Some(subdirectory) => self.checkout.join(subdirectory),
+            None => self.checkout.clone(),
+        };
+        let mut templates = DirectoryLoader::new(root).load()?;
+        for template in templates.values_mut() {
+            template.set_revision(commit.to_owned());
+        }
+        Ok(templates)
+    }
+
+    /// Fetch the reference into the checkout and return hash of its commit.
+    fn fetch(&self) -> Result<String, GitError> {
+        for argument in [&self.repository, &self.reference] {
+            if argument.starts_with('-') {
+                return Err(GitError::InvalidArgument(argument.clone()));
+            }
+        }
+        if !self.checkout.join(".git").exists() {
+            run(Command::new("git").arg("init").arg("--quiet").arg("--").arg(&self.checkout))?;
+            self.git(&["remote", "add", "--", "origin", &self.repository])?;
+        }
+        self.git(&["fetch", "--quiet", "--tags", "--", "origin", &self.reference])?;
+        self.git(&["rev-parse", "FETCH_HEAD^{commit}"])
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String, GitError> {
+        run(Command::new("git").arg("-C").arg(&self.checkout).args(args))
+    }
+}
+
+
+/// Run the command and return its trimmed standard output.
+fn run(command: &mut Command) -> Result<String, GitError> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(GitError::CommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+
+/// Errors returned by the `GitLoader`.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GitError {
+    /// Cannot run the `git` command.
+    #[error("Cannot run git")]
+    Io(#[from] io::Error),
+    /// The `git` command failed.
+    #[error("Git failed: {0}")]
+    CommandFailed(String),
+    /// The repository or reference starts with `-`.
+    #[error("Invalid git argument {0}")]
+    InvalidArgument(String),
+    /// Templates cannot be loaded from the checkout.
+    #[error("Cannot load templates")]
+    LoadingFailed(#[from] LoaderError),
+    /// Catalog cannot be built from loaded templates.
+    #[error("Cannot build catalog")]
+    BuildingFailed(#[from] TerariumBuilderError),
+}
+
+
+impl GitError {
+    /// Return stable code of the error for machine handling.
+    /// Errors wrapping other errors of this crate return codes of the wrapped errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "GIT_UNAVAILABLE",
+            Self::CommandFailed(_) => "GIT_FAILED",
+            Self::InvalidArgument(_) => "GIT_INVALID_ARGUMENT",
+            Self::LoadingFailed(error) => error.code(),
+            Self::BuildingFailed(error) => error.code(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use tera::Context;
+
+    use crate::TerariumBuilder;
+
+    use super::*;
+
+    fn commit(repository: &Path, path: &str, content: &str) {
+        let file = repository.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+        let git = |args: &[&str]| run(Command::new("git").arg("-C").arg(repository).args(args)).unwrap();
+        git(&["add", "-A"]);
+        git(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "--quiet", "-m", path]);
+    }
+
+    fn build(templates: HashMap<String, Template>) -> Result<Terarium, TerariumBuilderError> {
+        let mut builder = TerariumBuilder::default();
+        for (key, template) in templates {
+            builder.add_template(key, template)?;
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn load_and_refresh() {
+        let root = std::env::temp_dir().join(format!("terarium_git_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let repository = root.join("origin");
+        run(Command::new("git").args(["init", "--quiet", "--initial-branch=main"]).arg(&repository)).unwrap();
+        commit(&repository, "templates/welcome/en.tera", "Hello");
+
+        let mut loader = GitLoader::new(
+            repository.to_string_lossy().into_owned(),
+            "main".to_owned(),
+            root.join("checkout"),
+        ).subdirectory("templates");
+        let templates = loader.load().unwrap();
+        let revision = loader.revision().unwrap().to_owned();
+        assert_eq!(templates["welcome"].revision(), Some(revision.as_str()));
+        let mut terarium = build(templates).unwrap();
+
+        assert!(!loader.refresh(&mut terarium, "git", build).unwrap());
+        commit(&repository, "templates/welcome/en.tera", "{{ broken");
+        assert!(loader.refresh(&mut terarium, "git", build).is_err());
+        assert_eq!(loader.revision(), Some(revision.as_str()));
+        commit(&repository, "templates/welcome/en.tera", "Hi");
+        assert!(loader.refresh(&mut terarium, "git", build).unwrap());
+        let result = terarium.render_template(&Context::new(), "welcome", "en", None);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_ne!(loader.revision(), Some(revision.as_str()));
+        assert_eq!(result.unwrap(), "Hi");
+    }
+
+    #[test]
+    fn reject_option_like_arguments() {
+        let checkout = std::env::temp_dir().join(format!("terarium_git_options_{}", std::process::id()));
+        let mut loader = GitLoader::new("--upload-pack=touch".to_owned(), "main".to_owned(), &checkout);
+        assert!(matches!(loader.load(), Err(GitError::InvalidArgument(_))));
+        let mut loader = GitLoader::new("origin".to_owned(), "--upload-pack=touch".to_owned(), &checkout);
+        assert!(matches!(loader.load(), Err(GitError::InvalidArgument(_))));
+        assert!(!checkout.exists());
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    Deprecation, DisplayName, KeyPattern, MemberAttachment, MemberEncoding, TerariumBuilderError, TransferEncoding,
+    ValidityWindow,
+};
+
+
+/// Group of templates rendered together.
+/// Each member of the group has its key and refers to one template.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TemplateGroup {
+    /// Template keys by member keys.
+    pub members: HashMap<String, String>,
+    /// Templates with keys matching any of patterns are members too, their member keys are the template keys.
+    /// Patterns are resolved when the group is rendered, so templates added at runtime are included.
+    pub member_patterns: Vec<KeyPattern>,
+    /// Set when the group should not be used anymore.
+    pub deprecation: Option<Deprecation>,
+    /// Time window when the group may be rendered.
+    pub validity: Option<ValidityWindow>,
+    /// Human readable description of the group.
+    pub description: Option<String>,
+    /// Free-form metadata of the group.
+    pub metadata: HashMap<String, String>,
+    /// Free-form labels of the group (e.g. `transactional`).
+    pub tags: HashSet<String>,
+    /// Names of the group presented to users by languages (see `Terarium::display_name()`).
+    pub display_names: HashMap<String, DisplayName>,
+    /// Member requirements checked when the `Terarium` is built.
+    /// Each item is a set of member keys where at least one of them must be present in the group.
+    pub required_members: Vec<Vec<String>>,
+    /// Language requirements of members checked when the `Terarium` is built.
+    /// Each item is a member key and languages its template must have content in.
+    pub required_languages: Vec<(String, RequiredLanguages)>,
+    /// Outputs of members inserted into contexts of other members.
+    /// Keys are member keys, values are keys of members whose outputs are used by context variable names.
+    pub member_outputs: HashMap<String, HashMap<String, String>>,
+    /// MIME types and transfer encodings of members by member keys.
+    /// Outputs of the members are encoded after group hooks and normalization.
+    pub member_encodings: HashMap<String, MemberEncoding>,
+    /// Members rendered as attachments by member keys.
+    pub attachments: HashMap<String, MemberAttachment>,
+    /// Key of the parent group whose members, requirements, member outputs, member encodings, attachments, metadata
+    /// and tags are inherited.
+    /// Members of this group override inherited members with the same keys.
+    /// Description, display names, deprecation and validity are not inherited.
+    pub extends: Option<String>,
+}
+
+
+/// Languages a group member must have content in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequiredLanguages {
+    /// All listed languages.
+    List(Vec<String>),
+    /// All languages the other member (identified by its key) has content in.
+    OfMember(String),
+}
+
+
+impl TemplateGroup {
+    /// Return template keys by member keys including templates matching member patterns.
+    /// Explicit members take precedence over matched templates with the same key.
+    pub fn resolve_members<'a>(
+        &'a self,
+        template_keys: impl IntoIterator<Item=&'a String>,
+    ) -> HashMap<&'a str, &'a str> {
+        let mut members = HashMap::new();
+        if !self.member_patterns.is_empty() {
+            for key in template_keys {
+                if self.member_patterns.iter().any(|p| p.matches(key)) {
+                    members.insert(key.as_str(), key.as_str());
+                }
+            }
+        }
+        members.extend(self.members.iter().map(|(m, t)| (m.as_str(), t.as_str())));
+        members
+    }
+
+    /// Return keys of the `members` ordered so each member comes after members whose outputs it uses.
+    /// Return the cycle when members use outputs of each other.
+    pub fn render_order<'a>(&'a self, members: &HashMap<&'a str, &'a str>) -> Result<Vec<&'a str>, Vec<String>> {
+        fn visit<'a>(
+            group: &'a TemplateGroup,
+            member_key: &'a str,
+            path: &mut Vec<&'a str>,
+            order: &mut Vec<&'a str>,
+        ) -> Result<(), Vec<String>> {
+            if let Some(start) = path.iter().position(|m| *m == member_key) {
+                return Err(path[start..].iter().chain(Some(&member_key)).map(|m| m.to_string()).collect());
+            }
+            if order.contains(&member_key) {
+                return Ok(());
+            }
+            path.push(member_key);
+            let mut sources: Vec<&str> = group.member_outputs
+                .get(member_key)
+                .map(|inputs| inputs.values().map(|s| s.as_str()).collect())
+                .unwrap_or_default();
+            sources.sort();
+            for source in sources {
+                visit(group, source, path, order)?;
+            }
+            path.pop();
+            order.push(member_key);
+            Ok(())
+        }
+
+        let mut keys: Vec<&str> = members.keys().copied().collect();
+        keys.sort();
+        let mut order = Vec::with_capacity(keys.len());
+        for key in keys {
+            visit(self, key, &mut Vec::new(), &mut order)?;
+        }
+        order.retain(|key| members.contains_key(key));
+        Ok(order)
+    }
+
+    /// Merge the `parent` into the group, values of the group take precedence.
+    fn inherit(&mut self, parent: &TemplateGroup) {
+        for (member_key, template_key) in parent.members.iter() {
+            self.members.entry(member_key.clone()).or_insert_with(|| template_key.clone());
+        }
+        self.member_patterns.splice(0..0, parent.member_patterns.iter().cloned());
+        self.required_members.extend(parent.required_members.iter().cloned());
+        self.required_languages.extend(parent.required_languages.iter().cloned());
+        for (member_key, inputs) in parent.member_outputs.iter() {
+            let own = self.member_outputs.entry(member_key.clone()).or_default();
+            for (variable, source) in inputs {
+                own.entry(variable.clone()).or_insert_with(|| source.clone());
+            }
+        }
+        for (member_key, encoding) in parent.member_encodings.iter() {
+            self.member_encodings.entry(member_key.clone()).or_insert_with(|| encoding.clone());
+        }
+        for (member_key, attachment) in parent.attachments.iter() {
+            self.attachments.entry(member_key.clone()).or_insert_with(|| attachment.clone());
+        }
+        for (key, value) in parent.metadata.iter() {
+            self.metadata.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        self.tags.extend(parent.tags.iter().cloned());
+    }
+
+    /// Remove the member together with its member outputs, encoding, attachment and language requirements.
+    pub(crate) fn remove_member(&mut self, member_key: &str) {
+        self.members.remove(member_key);
+        self.member_outputs.remove(member_key);
+        self.member_encodings.remove(member_key);
+        self.attachments.remove(member_key);
+        self.required_languages.retain(|(key, required)| {
+            key != member_key && *required != RequiredLanguages::OfMember(member_key.to_owned())
+        });
+    }
+
+    /// Return sets of required members not satisfied by the group.
+    pub fn missing_members(&self) -> Vec<Vec<String>> {
+        self.required_members
+            .iter()
+            .filter(|keys| !keys.iter().any(|k| self.members.contains_key(k)))
+            .cloned()
+            .collect()
+    }
+}
+
+
+/// Merge parent groups into groups extending them (see `TemplateGroup::extends`).
+pub(crate) fn resolve_inheritance(groups: &mut HashMap<String, TemplateGroup>) -> Result<(), TerariumBuilderError> {
+    fn resolve(
+        groups: &mut HashMap<String, TemplateGroup>,
+        group_key: &str,
+        path: &mut Vec<String>,
+        resolved: &mut HashSet<String>,
+    ) -> Result<(), TerariumBuilderError> {
+        if resolved.contains(group_key) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|g| g == group_key) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(group_key.to_owned());
+            return Err(TerariumBuilderError::CyclicDependency(cycle));
+        }
+        if let Some(parent_key) = groups[group_key].extends.clone() {
+            if !groups.contains_key(&parent_key) {
+                return Err(TerariumBuilderError::ParentGroupNotFound(group_key.to_owned(), parent_key));
+            }
+            path.push(group_key.to_owned());
+            resolve(groups, &parent_key, path, resolved)?;
+            path.pop();
+            let parent = groups[&parent_key].clone();
+            if let Some(group) = groups.get_mut(group_key) {
+                group.inherit(&parent);
+            }
+        }
+        resolved.insert(group_key.to_owned());
+        Ok(())
+    }
+
+    let mut keys: Vec<String> = groups.keys().cloned().collect();
+    keys.sort();
+    let mut resolved = HashSet::new();
+    for key in keys {
+        resolve(groups, &key, &mut Vec::new(), &mut resolved)?;
+    }
+    Ok(())
+}
+
+
+/// Behavior of the `TerariumBuilder` when group has no members.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmptyGroupPolicy {
+    /// Empty group is valid and renders to an empty map.
+    #[default]
+    Allow,
+    /// Empty group is rejected when the `Terarium` is built.
+    Reject,
+}
+
+
+/// Behavior of the `Terarium::remove_template()` when group members reference the removed template.
+/// It is applied by the `Terarium::swap()` to members whose templates are missing in the swapped catalog too.
+/// Members matching patterns are never dangling, they just stop matching the removed template.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DanglingMemberPolicy {
+    /// Members are kept and renders of their groups fail with the `TerariumError::BrokenMember`.
+    #[default]
+    MarkBroken,
+    /// Members referencing the template are removed from their groups with their member outputs, encodings,
+    /// attachments and language requirements.
+    /// Removal fails when any of the groups would not pass the validation of the `TerariumBuilder::build()`.
+    RemoveMember,
+    /// Removal fails with the `TerariumBuilderError::TemplateInUse`.
+    Reject,
+}
+
+
+impl From<HashMap<String, String>> for TemplateGroup {
+    fn from(members: HashMap<String, String>) -> Self {
+        Self {
+            members,
+            ..Self::default()
+        }
+    }
+}
+
+
+/// Simplify building template groups.
+#[derive(Clone, Default)]
+pub struct TemplateGroupBuilder {
+    group: TemplateGroup,
+}
+
+impl TemplateGroupBuilder {
+    /// Add new member to group.
+    pub fn add_member(mut self, member_key: String, template_key: String) -> Self {
+        self.group.members.insert(member_key, template_key);
+        self
+    }
+
+    /// Add all templates with keys matching the `pattern` as members (see `TemplateGroup::member_patterns`).
+    pub fn add_members_matching(mut self, pattern: impl Into<KeyPattern>) -> Self {
+        self.group.member_patterns.push(pattern.into());
+        self
+    }
+
+    /// Mark the group as deprecated.
+    pub fn deprecated(mut self, deprecation: Deprecation) -> Self {
+        self.group.deprecation = Some(deprecation);
+        self
+    }
+
+    /// Allow rendering of the group only inside the `window`, the fallback of the window is a group key.
+    pub fn valid(mut self, window: ValidityWindow) -> Self {
+        self.group.validity = Some(window);
+        self
+    }
+
+    /// Set description of the group.
+    pub fn description(mut self, description: String) -> Self {
+        self.group.description = Some(description);
+        self
+    }
+
+    /// Add metadata entry to the group.
+    pub fn add_metadata(mut self, key: String, value: String) -> Self {
+        self.group.metadata.insert(key, value);
+        self
+    }
+
+    /// Set name of the group presented to users speaking the `language`.
+    pub fn display_name(mut self, language: String, display_name: DisplayName) -> Self {
+        self.group.display_names.insert(language, display_name);
+        self
+    }
+
+    /// Label the group with the tag.
+    pub fn tag(mut self, tag: String) -> Self {
+        self.group.tags.insert(tag);
+        self
+    }
+
+    /// Require the member to be present in the group.
+    pub fn require_member(self, member_key: String) -> Self {
+        self.require_any_member(vec![member_key])
+    }
+
+    /// Require at least one of the members to be present in the group.
+    pub fn require_any_member(mut self, member_keys: Vec<String>) -> Self {
+        self.group.required_members.push(member_keys);
+        self
+    }
+
+    /// Require template of the member to have content in all `languages`.
+    pub fn require_languages(mut self, member_key: String, languages: Vec<String>) -> Self {
+        self.group.required_languages.push((member_key, RequiredLanguages::List(languages)));
+        self
+    }
+
+    /// Require template of the member to have content in every language the `other_member_key` has content in
+    /// (e.g. the `subject` must exist in every language of the `html`).
+    pub fn require_languages_of(mut self, member_key: String, other_member_key: String) -> Self {
+        self.group.required_languages.push((member_key, RequiredLanguages::OfMember(other_member_key)));
+        self
+    }
+
+    /// Insert rendered output of the `source_member_key` into the context of the `member_key` as the `variable`
+    /// (e.g. plain text summary embedded into the HTML). Members are rendered in order of these dependencies.
+    pub fn use_member_output(mut self, member_key: String, variable: String, source_member_key: String) -> Self {
+        self.group.member_outputs.entry(member_key).or_default().insert(variable, source_member_key);
+        self
+    }
+
+    /// Declare MIME type of the member output and encode the output by the `transfer_encoding` after the render
+    /// (e.g. base64 encoded `text/calendar` attachment or quoted-printable `text/plain` body).
+    /// Encoded members are returned in the `RenderOutcome::encodings`. Members assembled by the
+    /// `Terarium::render_group_mime()` are encoded by it, so they should not be encoded twice.
+    pub fn encode_member(mut self, member_key: String, mime_type: String, transfer_encoding: TransferEncoding) -> Self {
+        self.group.member_encodings.insert(member_key, MemberEncoding { mime_type, transfer_encoding });
+        self
+    }
+
+    /// Render the member as an attachment named by the `filename` template (see `MemberAttachment`).
+    /// The `Terarium::render()` moves outputs of attachment members from the group output to the
+    /// `RenderOutcome::attachments`, other render methods return them as regular members.
+    pub fn attach_member(mut self, member_key: String, filename: String, mime_type: String) -> Self {
+        self.group.attachments.insert(member_key, MemberAttachment { filename, mime_type });
+        self
+    }
+
+    /// Inherit members and requirements of the group with the `group_key` (see `TemplateGroup::extends`).
+    pub fn extends(mut self, group_key: String) -> Self {
+        self.group.extends = Some(group_key);
+        self
+    }
+
+    /// Build the group spec.
+    pub fn build(self) -> TemplateGroup {
+        self.group
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_members() {
+        let group = TemplateGroupBuilder::default()
+            .add_member("subject".to_owned(), "tpl".to_owned())
+            .add_member("html".to_owned(), "tpl".to_owned())
+            .require_member("subject".to_owned())
+            .require_any_member(vec!["text".to_owned(), "html".to_owned()])
+            .require_member("preheader".to_owned())
+            .build();
+        assert_eq!(group.missing_members(), vec![vec!["preheader".to_owned()]]);
+    }
+
+    #[test]
+    fn render_order() {
+        let group = TemplateGroupBuilder::default()
+            .use_member_output("html".to_owned(), "summary".to_owned(), "text".to_owned())
+            .use_member_output("a".to_owned(), "html".to_owned(), "html".to_owned())
+            .build();
+        let members = HashMap::from([("a", "a"), ("html", "html"), ("text", "text")]);
+        assert_eq!(group.render_order(&members).unwrap(), vec!["text", "html", "a"]);
+
+        let group = TemplateGroupBuilder { group }
+            .use_member_output("text".to_owned(), "a".to_owned(), "a".to_owned())
+            .build();
+        assert_eq!(group.render_order(&members).unwrap_err(), vec!["a", "html", "text", "a"]);
+    }
+
+    #[test]
+    fn resolve_members() {
+        let group = TemplateGroupBuilder::default()
+            .add_member("sections.intro".to_owned(), "intro".to_owned())
+            .add_members_matching("sections.*")
+            .build();
+        let keys = ["sections.intro".to_owned(), "sections.outro".to_owned(), "footer".to_owned()];
+        let members = group.resolve_members(keys.iter());
+        assert_eq!(members, HashMap::from([("sections.intro", "intro"), ("sections.outro", "sections.outro")]));
+    }
+
+    #[test]
+    fn inheritance() {
+        let base = TemplateGroupBuilder::default()
+            .add_member("header".to_owned(), "header".to_owned())
+            .add_member("body".to_owned(), "body".to_owned())
+            .require_member("body".to_owned())
+            .description("Base".to_owned())
+            .tag("email".to_owned())
+            .build();
+        let welcome = TemplateGroupBuilder::default()
+            .extends("base".to_owned())
+            .add_member("body".to_owned(), "welcome".to_owned())
+            .build();
+        let child = TemplateGroupBuilder::default().extends("welcome".to_owned()).build();
+        let mut groups = HashMap::from([
+            ("base".to_owned(), base), ("welcome".to_owned(), welcome), ("child".to_owned(), child),
+        ]);
+        resolve_inheritance(&mut groups).unwrap();
+        let expected = HashMap::from([
+            ("header".to_owned(), "header".to_owned()), ("body".to_owned(), "welcome".to_owned()),
+        ]);
+        assert_eq!(groups["welcome"].members, expected);
+        assert_eq!(groups["child"].members, expected);
+        assert_eq!(groups["child"].required_members, vec![vec!["body".to_owned()]]);
+        assert!(groups["child"].tags.contains("email"));
+        assert_eq!(groups["child"].description, None);
+
+        groups.get_mut("base").unwrap().extends = Some("child".to_owned());
+        assert!(matches!(resolve_inheritance(&mut groups), Err(TerariumBuilderError::CyclicDependency(_))));
+        groups.get_mut("base").unwrap().extends = Some("unknown".to_owned());
+        assert!(matches!(
+            resolve_inheritance(&mut groups),
+            Err(TerariumBuilderError::ParentGroupNotFound(g, p)) if g == "base" && p == "unknown"
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::loader::list_files;
+use crate::LoaderError;
+
+
+/// Extensions of handlebars partial files.
+const PARTIAL_EXTENSIONS: [&str; 2] = ["hbs", "handlebars"];
+
+/// Prefix of names of contents holding partials.
+const PARTIAL_PREFIX: &str = "partials/";
+
+
+/// Handlebars-style partials for projects migrating from handlebars.
+/// Partials added by the `TerariumBuilder::add_handlebars_partials()` can be included by any template but they are
+/// not rendered on their own. Partial calls in sources are translated into includes by the `translate_partials()`
+/// (or by the `TerariumBuilder::set_handlebars_compatibility()`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HandlebarsPartials {
+    partials: BTreeMap<String, String>,
+}
+
+
+impl HandlebarsPartials {
+    /// Load partials from `.hbs` and `.handlebars` files in the directory and its subdirectories.
+    /// Names of partials are paths relative to the directory without extension (e.g. `layout/header`).
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, LoaderError> {
+        let directory = directory.as_ref();
+        let mut partials = Self::default();
+        for path in list_files(directory)? {
+            let is_partial = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| PARTIAL_EXTENSIONS.contains(&e));
+            if !is_partial {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(directory)
+                .map_err(|_| LoaderError::InvalidPath(path.clone()))?
+                .with_extension("");
+            let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            let source = fs::read_to_string(&path).map_err(|e| LoaderError::Io(path.clone(), e))?;
+            partials.add_partial(name, source);
+        }
+        Ok(partials)
+    }
+
+    /// Add the partial, existing partial with the same name is replaced.
+    pub fn add_partial(&mut self, name: String, source: String) {
+        self.partials.insert(name, source);
+    }
+
+    /// Return names of partials, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.partials.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Add all partials of the `other`, existing partials with the same names are replaced.
+    pub fn extend(&mut self, other: HandlebarsPartials) {
+        self.partials.extend(other.partials);
+    }
+
+    /// Return names of contents and translated sources of the partials.
+    pub(crate) fn into_sources(self) -> impl Iterator<Item=(String, String)> {
+        self.partials.into_iter().map(|(name, source)| (partial_content(&name), translate_partials(&source)))
+    }
+}
+
+
+/// Translate handlebars partial calls (`{{> name}}`, `{{> "name"}}` and `{{~> name~}}`) into Tera includes of
+/// partials registered by the `HandlebarsPartials`. Calls with context or parameters and partial blocks have no
+/// Tera counterpart, they are left untouched and fail when the source is parsed.
+pub fn translate_partials(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let Some(end) = tag.find("}}") else { break };
+        match translate_call(&tag[2..end]) {
+            Some(include) => result.push_str(&include),
+            None => result.push_str(&tag[..end + 2]),
+        }
+        rest = &tag[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+
+/// Return include replacing the partial call when the tag (without braces) is a simple partial call.
+fn translate_call(tag: &str) -> Option<String> {
+    let (trim_left, tag) = match tag.strip_prefix('~') {
+        Some(tag) => ("-", tag),
+        None => ("", tag),
+    };
+    let (trim_right, tag) = match tag.strip_suffix('~') {
+        Some(tag) => ("-", tag),
+        None => ("", tag),
+    };
+    let name = tag.trim_start().strip_prefix('>')?.trim();
+    let name = match name.strip_prefix('"').and_then(|n| n.strip_suffix('"')) {
+        Some(quoted) => quoted,
+        None if name.contains(char::is_whitespace) => return None,
+        None => name,
+    };
+    if name.is_empty() || name.contains('"') {
+        return None;
+    }
+    Some(format!("{{%{} include \"{}\" {}%}}", trim_left, partial_content(name), trim_right))
+}
+
+
+fn partial_content(name: &str) -> String {
+    format!("{}{}", PARTIAL_PREFIX, name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tera::Context;
+
+    use crate::{Content, Template, TerariumBuilder};
+
+    use super::*;
+
+    #[test]
+    fn load() {
+        let root = std::env::temp_dir().join(format!("terarium_partials_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("layout")).unwrap();
+        fs::write(root.join("layout/header.hbs"), "Header").unwrap();
+        fs::write(root.join("footer.handlebars"), "Footer").unwrap();
+        fs::write(root.join("README.md"), "ignored").unwrap();
+        let partials = HandlebarsPartials::load(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(partials.unwrap().names(), vec!["footer", "layout/header"]);
+    }
+
+    #[test]
+    fn translate() {
+        assert_eq!(translate_partials("a {{> header}} b"), "a {% include \"partials/header\" %} b");
+        assert_eq!(translate_partials("{{~> \"layout/footer\" ~}}"), "{%- include \"partials/layout/footer\" -%}");
+        assert_eq!(translate_partials("{{> card item}} {{ name }} {{#> layout}}"), "{{> card item}} {{ name }} {{#> layout}}");
+        assert_eq!(translate_partials("{{ unclosed"), "{{ unclosed");
+    }
+
+    #[test]
+    fn render_partials() {
+        let mut partials = HandlebarsPartials::default();
+        partials.add_partial("header".to_owned(), "Hello {{ name }}".to_owned());
+        partials.add_partial("footer".to_owned(), "{{> header}}!".to_owned());
+        assert_eq!(partials.names(), vec!["footer", "header"]);
+
+        let mut builder = TerariumBuilder::default();
+        builder.add_handlebars_partials(partials);
+        builder.set_handlebars_compatibility(true);
+        let content = Content::new("{{> footer}}".to_owned(), vec!["en".to_owned()]);
+        builder.add_template("mail".to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+        let mut instance = builder.build().unwrap();
+        let mut context = Context::new();
+        context.insert("name", "john");
+        assert_eq!(instance.render_template(&context, "mail", "en", None).unwrap(), "Hello john!");
+
+        let content = Content::new("{{~> header }} again".to_owned(), vec!["en".to_owned()]);
+        instance.add_template("mail".to_owned(), Template::new(vec![content]).unwrap(), "test").unwrap();
+        assert_eq!(instance.render_template(&context, "mail", "en", None).unwrap(), "Hello john again");
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::SystemTime;
+
+use tera::Context;
+
+use crate::{Terarium, TerariumError};
+
+
+/// Dated snapshots of the catalog.
+/// Each snapshot is effective from its date until the date of the next snapshot, so historical documents can be
+/// regenerated by the catalog used when they were rendered first.
+#[derive(Clone, Default)]
+pub struct CatalogHistory {
+    snapshots: Vec<(SystemTime, Terarium)>,
+}
+
+
+impl CatalogHistory {
+    /// Create empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add snapshot of the catalog effective from the time.
+    /// Snapshot with the same time is replaced.
+    pub fn add_snapshot(&mut self, effective_from: SystemTime, catalog: Terarium) {
+        match self.snapshots.binary_search_by(|(time, _)| time.cmp(&effective_from)) {
+            Ok(index) => self.snapshots[index].1 = catalog,
+            Err(index) => self.snapshots.insert(index, (effective_from, catalog)),
+        }
+    }
+
+    /// Return the snapshot effective at the time.
+    pub fn at(&self, timestamp: SystemTime) -> Option<&Terarium> {
+        let index = self.snapshots.partition_point(|(time, _)| *time <= timestamp);
+        index.checked_sub(1).map(|index| &self.snapshots[index].1)
+    }
+
+    /// Render single template by the snapshot effective at the time.
+    pub fn render_template_at<K, LK>(
+        &self,
+        context: &Context,
+        template_key: &K,
+        language: &LK,
+        fallback_language: Option<&LK>,
+        timestamp: SystemTime,
+    ) -> Result<String, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+    {
+        self.at(timestamp)
+            .ok_or(TerariumError::CatalogNotFound)?
+            .render_template(context, template_key, language, fallback_language)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Content, Template, TerariumBuilder};
+
+    use super::*;
+
+    fn catalog(text: &str) -> Terarium {
+        let mut builder = TerariumBuilder::default();
+        let template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+        builder.add_template("invoice".to_owned(), template).unwrap();
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn render_at() {
+        let day = |n: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(n * 86400);
+        let mut history = CatalogHistory::new();
+        history.add_snapshot(day(10), catalog("v2"));
+        history.add_snapshot(day(1), catalog("v1"));
+
+        let render = |time| history.render_template_at(&Context::new(), "invoice", "en", None, time);
+        assert!(matches!(render(day(0)), Err(TerariumError::CatalogNotFound)));
+        assert_eq!(render(day(1)).unwrap(), "v1");
+        assert_eq!(render(day(9)).unwrap(), "v1");
+        assert_eq!(render(day(20)).unwrap(), "v2");
+    }
+}
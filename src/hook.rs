@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+
+
+/// Extension point invoked with rendered members of a group before the group render returns.
+/// Hooks are registered by the `TerariumBuilder::add_group_hook()` and invoked in order they were registered.
+pub trait GroupHook: Send + Sync {
+    /// Modify rendered `members` of the group (e.g. add derived members) or return reason why the result is rejected.
+    fn after_render(&self, group_key: &str, members: &mut HashMap<String, String>) -> Result<(), String>;
+}
+
+
+impl<F> GroupHook for F where F: Fn(&str, &mut HashMap<String, String>) -> Result<(), String> + Send + Sync {
+    fn after_render(&self, group_key: &str, members: &mut HashMap<String, String>) -> Result<(), String> {
+        self(group_key, members)
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use tera::{Result as TeraResult, Value};
+
+
+/// Maximal length of the content line in octets (without line break).
+const MAX_LINE_OCTETS: usize = 75;
+
+
+/// Turn rendered iCalendar content into valid iCalendar data.
+/// Empty lines are dropped, lines starting by space or tab are unfolded into the previous line (RFC 5545 §3.1),
+/// lines are folded to 75 octets, terminated by CRLF and the result is validated.
+pub(crate) fn finalize_icalendar(output: &str) -> Result<String, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in output.lines().map(|l| l.trim_end_matches('\r')).filter(|l| !l.is_empty()) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    let lines: Vec<&str> = lines.iter().map(|l| l.as_str()).collect();
+    validate(&lines)?;
+
+    let mut result = String::with_capacity(output.len() + output.len() / MAX_LINE_OCTETS * 3);
+    for line in lines {
+        fold_line(line, &mut result);
+    }
+    Ok(result)
+}
+
+
+/// Escape text value according to the RFC 5545.
+pub(crate) fn ics_escape_filter(value: &Value, _args: &HashMap<String, Value>) -> TeraResult<Value> {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    Ok(Value::String(escaped))
+}
+
+
+fn fold_line(line: &str, result: &mut String) {
+    let mut limit = MAX_LINE_OCTETS;
+    let mut used = 0;
+    for c in line.chars() {
+        if used + c.len_utf8() > limit {
+            result.push_str("\r\n ");
+            // the leading space of continuation line counts to the limit
+            limit = MAX_LINE_OCTETS - 1;
+            used = 0;
+        }
+        result.push(c);
+        used += c.len_utf8();
+    }
+    result.push_str("\r\n");
+}
+
+
+fn validate(lines: &[&str]) -> Result<(), String> {
+    if lines.first() != Some(&"BEGIN:VCALENDAR") || lines.last() != Some(&"END:VCALENDAR") {
+        return Err("iCalendar data must be wrapped in VCALENDAR component".to_owned());
+    }
+
+    let mut components = Vec::<(&str, Vec<&str>)>::new();
+    for line in lines {
+        let name = line.split([':', ';']).next().unwrap_or_default();
+        let value = line.split_once(':').map(|(_, v)| v).unwrap_or_default();
+        match name {
+            "BEGIN" => components.push((value, Vec::new())),
+            "END" => {
+                let (component, properties) = components.pop().ok_or_else(|| format!("Unexpected END:{}", value))?;
+                if component != value {
+                    return Err(format!("END:{} does not match BEGIN:{}", value, component));
+                }
+                check_required_properties(component, &properties)?;
+            }
+            _ => match components.last_mut() {
+                Some((_, properties)) => properties.push(name),
+                None => return Err(format!("Property {} is outside of any component", name)),
+            }
+        }
+    }
+    match components.last() {
+        Some((component, _)) => Err(format!("Component {} is not closed", component)),
+        None => Ok(()),
+    }
+}
+
+
+fn check_required_properties(component: &str, properties: &[&str]) -> Result<(), String> {
+    let required: &[&str] = match component {
+        "VCALENDAR" => &["VERSION", "PRODID"],
+        "VEVENT" | "VTODO" | "VJOURNAL" => &["UID", "DTSTAMP"],
+        _ => &[],
+    };
+    match required.iter().find(|p| !properties.contains(p)) {
+        Some(property) => Err(format!("Component {} requires property {}", component, property)),
+        None => Ok(()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENT: &str = "
+BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//terarium//EN
+
+BEGIN:VEVENT
+UID:1@example.com
+DTSTAMP:20240101T100000Z
+SUMMARY:Meeting
+END:VEVENT
+END:VCALENDAR
+";
+
+    #[test]
+    fn finalize() {
+        let result = finalize_icalendar(EVENT).unwrap();
+        assert!(result.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(result.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn unfold_continuation_lines() {
+        let event = EVENT.replace("SUMMARY:Meeting", "SUMMARY:Weekly \r\n  meeting \r\n\tof team  ");
+        let result = finalize_icalendar(&event).unwrap();
+        assert!(result.contains("\r\nSUMMARY:Weekly  meeting of team  \r\n"));
+    }
+
+    #[test]
+    fn fold_long_lines() {
+        let mut result = String::new();
+        fold_line(&format!("DESCRIPTION:{}", "č".repeat(60)), &mut result);
+        let lines: Vec<&str> = result.split("\r\n").filter(|l| !l.is_empty()).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1].starts_with(' '));
+    }
+
+    #[test]
+    fn missing_properties() {
+        let event = EVENT.replace("UID:1@example.com", "");
+        assert_eq!(finalize_icalendar(&event).unwrap_err(), "Component VEVENT requires property UID");
+        let event = EVENT.replace("END:VEVENT", "");
+        assert!(finalize_icalendar(&event).is_err());
+    }
+
+    #[test]
+    fn escape() {
+        let value = Value::String("a,b;c\\d\ne".to_owned());
+        let escaped = ics_escape_filter(&value, &HashMap::new()).unwrap();
+        assert_eq!(escaped, Value::String("a\\,b\\;c\\\\d\\ne".to_owned()));
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+
+/// Proof of which template versions produced the rendered outputs.
+/// Returned by the `Terarium::render()` when requested by the `RenderRequest::with_integrity()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderIntegrity {
+    /// Fingerprint of the whole catalog at the time of the render.
+    pub catalog_fingerprint: u64,
+    /// Integrity of outputs by template keys (single template) or member keys (group).
+    pub outputs: HashMap<String, OutputIntegrity>,
+}
+
+
+/// Integrity of one rendered output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputIntegrity {
+    /// Key of the template which produced the output.
+    pub template_key: String,
+    /// Fingerprint (version) of the template.
+    pub template_fingerprint: u64,
+    /// Hex encoded SHA-256 of the output.
+    pub sha256: String,
+}
+
+
+impl OutputIntegrity {
+    /// Compute integrity of the output produced by the template.
+    pub fn new(template_key: String, template_fingerprint: u64, output: &str) -> Self {
+        Self {
+            template_key,
+            template_fingerprint,
+            sha256: sha256_hex(output),
+        }
+    }
+
+    /// Check the output matches the hash.
+    pub fn verify(&self, output: &str) -> bool {
+        self.sha256 == sha256_hex(output)
+    }
+}
+
+
+fn sha256_hex(data: &str) -> String {
+    format!("{:x}", Sha256::digest(data.as_bytes()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash() {
+        let integrity = OutputIntegrity::new("tpl".to_owned(), 1, "abc");
+        assert_eq!(integrity.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert!(integrity.verify("abc"));
+        assert!(!integrity.verify("abd"));
+    }
+}
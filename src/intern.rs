@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::selection::TemplateContents;
+
+
+/// Pool of shared strings, each distinct string is allocated once.
+/// It shares language and variant names of contents, other keys and metadata keep their own strings.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+
+impl Interner {
+    /// Return shared instance of the `value`.
+    pub(crate) fn intern(&mut self, value: &str) -> Arc<str> {
+        match self.strings.get(value) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(value);
+                self.strings.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// Drop strings not used outside of the pool anymore.
+    pub(crate) fn prune(&mut self) {
+        self.strings.retain(|interned| Arc::strong_count(interned) > 1);
+    }
+}
+
+
+/// Resolved template returned by the `Terarium::template_handle()`.
+/// Rendering by the handle skips normalization and lookup of the template key.
+/// The handle keeps contents the template had when the handle was created, so obtain a new handle after the template
+/// is replaced or removed at runtime.
+#[derive(Clone, Debug)]
+pub struct TemplateHandle {
+    pub(crate) key: Arc<str>,
+    pub(crate) contents: Arc<TemplateContents>,
+}
+
+
+impl TemplateHandle {
+    /// Return key of the template.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern() {
+        let mut interner = Interner::default();
+        let a = interner.intern("en");
+        let b = interner.intern("en");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &interner.intern("cs")));
+    }
+
+    #[test]
+    fn prune() {
+        let mut interner = Interner::default();
+        let en = interner.intern("en");
+        interner.intern("cs");
+        interner.prune();
+        assert_eq!(interner.strings.len(), 1);
+        assert!(Arc::ptr_eq(&en, &interner.intern("en")));
+    }
+}
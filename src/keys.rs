@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+
+use crate::lookup::LookupMap;
+
+
+/// Separators collapsed by the `KeyNormalization::Normalized`.
+const SEPARATORS: [char; 4] = ['-', '_', '.', '/'];
+
+
+/// How template and group keys are matched when looked up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyNormalization {
+    /// Keys must match exactly.
+    #[default]
+    Exact,
+    /// Keys are matched regardless of letter case.
+    CaseInsensitive,
+    /// Keys are trimmed, lowercased and each run of separators (`-`, `_`, `.`, `/` and whitespace) is matched as
+    /// single `.`, so `" Emails/Welcome-Mail "` matches `"emails.welcome_mail"`.
+    Normalized,
+}
+
+
+impl KeyNormalization {
+    /// Return the normalized form of the key.
+    pub fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Exact => Cow::Borrowed(key),
+            Self::CaseInsensitive => Cow::Owned(key.to_lowercase()),
+            Self::Normalized => {
+                let mut result = String::with_capacity(key.len());
+                let mut separator = false;
+                for c in key.trim().chars() {
+                    if c.is_whitespace() || SEPARATORS.contains(&c) {
+                        separator = true;
+                        continue;
+                    }
+                    if separator && !result.is_empty() {
+                        result.push('.');
+                    }
+                    separator = false;
+                    result.extend(c.to_lowercase());
+                }
+                Cow::Owned(result)
+            }
+        }
+    }
+}
+
+
+/// Lookup of original keys by their normalized forms.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KeyIndex {
+    normalization: KeyNormalization,
+    keys: LookupMap<String, String>,
+}
+
+
+impl KeyIndex {
+    /// Build the index of the `keys` or return pair of conflicting keys.
+    pub(crate) fn new<'a>(
+        normalization: KeyNormalization,
+        keys: impl Iterator<Item=&'a String>,
+    ) -> Result<Self, (String, String)> {
+        let mut index = Self { normalization, keys: LookupMap::default() };
+        if normalization == KeyNormalization::Exact {
+            return Ok(index);
+        }
+        for key in keys {
+            let normalized = normalization.normalize(key).into_owned();
+            if let Some(other) = index.keys.insert(normalized, key.clone()) {
+                return Err((other, key.clone()));
+            }
+        }
+        Ok(index)
+    }
+
+    /// Get the normalization used by the index.
+    pub(crate) fn normalization(&self) -> KeyNormalization {
+        self.normalization
+    }
+
+    /// Remove the original `key` from the index.
+    pub(crate) fn remove(&mut self, key: &str) {
+        self.keys.remove(self.normalization.normalize(key).as_ref());
+    }
+
+    /// Return the original key matching the `key` or the `key` itself when nothing matches.
+    pub(crate) fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.normalization {
+            KeyNormalization::Exact => key,
+            _ => self.keys.get(self.normalization.normalize(key).as_ref()).map(|k| k.as_str()).unwrap_or(key),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(KeyNormalization::Exact.normalize(" Foo_Bar "), " Foo_Bar ");
+        assert_eq!(KeyNormalization::CaseInsensitive.normalize("Foo_Bar"), "foo_bar");
+        assert_eq!(KeyNormalization::Normalized.normalize(" Emails/Welcome--Mail "), "emails.welcome.mail");
+        assert_eq!(KeyNormalization::Normalized.normalize("_foo_"), "foo");
+    }
+
+    #[test]
+    fn resolve() {
+        let keys = ["emails.welcome".to_owned(), "emails.bye".to_owned()];
+        let index = KeyIndex::new(KeyNormalization::Normalized, keys.iter()).unwrap();
+        assert_eq!(index.resolve("Emails/Welcome"), "emails.welcome");
+        assert_eq!(index.resolve("unknown"), "unknown");
+    }
+
+    #[test]
+    fn conflict() {
+        let keys = ["foo".to_owned(), "FOO".to_owned()];
+        assert!(KeyIndex::new(KeyNormalization::CaseInsensitive, keys.iter()).is_err());
+    }
+}
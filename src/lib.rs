@@ -106,13 +106,182 @@
 //! ```
 //!
 //! See more examples in the project's repository.
+//!
+//! ## Templates in files
+//!
+//! Templates checked into a repository can be loaded in one call by the `DirectoryLoader`. By default, each directory
+//! with template files is one template whose key is the directory path relative to the root and each file is one
+//! language named `<language>[.<kind>].tera`:
+//!
+//! ```text
+//! templates/
+//! ├── emails/welcome/
+//! │   ├── cs.html.tera    -> template `emails/welcome`, language `cs`, kind `ContentKind::Html`
+//! │   └── en.html.tera    -> template `emails/welcome`, language `en`, kind `ContentKind::Html`
+//! └── sms/welcome/
+//!     └── en.tera         -> template `sms/welcome`, language `en`, kind `ContentKind::Text`
+//! ```
+//!
+//! Contents are named by their paths (e.g. `emails/welcome/en.html.tera`), so templates can include each other.
+//! Files without the `.tera` extension are ignored. The `PathConvention::LanguageDirectory` supports layout with
+//! top level language directories (e.g. `en/emails/welcome.html.tera`) instead.
+//!
+//! ```no_run
+//! use terarium::{DirectoryLoader, TerariumBuilder};
+//!
+//! let builder = TerariumBuilder::try_from(&DirectoryLoader::new("templates")).unwrap();
+//! let terarium = builder.build().unwrap();
+//! ```
+//!
+//! Only some of the files are loaded by the `TerariumBuilder::add_templates_from_dir()` with a pattern of their paths
+//! (e.g. `builder.add_templates_from_dir("templates", "emails/*")`).
+//!
+//! ## Panics
+//!
+//! The crate does not panic on malformed input. Invalid templates, groups, keys and contexts are reported as errors by
+//! the `TerariumBuilder` or by render methods. Panics raised by user callbacks (e.g. observers, hooks or resolvers) are
+//! propagated to the caller. Assertions of the `testing` module panic on failure as they are intended for tests.
 
 
 // re-export
 pub use tera;
 
+pub use annotations::*;
+pub use audit::*;
+pub use brand::*;
+pub use buffers::*;
+pub use cache::*;
+#[cfg(feature = "async")]
+pub use cancellation::*;
+pub use chaos::*;
+pub use compliance::*;
+pub use cost::*;
+pub use csv::*;
+pub use determinism::*;
+pub use dialect::*;
+pub use direction::*;
+pub use display::*;
+pub use docs::*;
+pub use flags::*;
+pub use formatting::*;
+#[cfg(feature = "git")]
+pub use git::*;
+pub use group::*;
+pub use handlebars::*;
+pub use history::*;
+pub use hook::*;
+pub use integrity::*;
+pub use intern::*;
+pub use keys::*;
+pub use limits::*;
+pub use links::*;
+pub use loader::*;
+pub use localization::*;
+#[cfg(feature = "markup")]
+pub use markup::*;
+pub use migration::*;
+pub use mime::*;
+pub use missing::*;
+pub use namespaces::*;
+pub use normalization::*;
+pub use observer::*;
+#[cfg(feature = "pdf")]
+pub use pdf::*;
+pub use overrides::*;
+pub use pattern::*;
+pub use placeholders::*;
+pub use preheader::*;
+pub use profile::*;
+pub use quota::*;
+pub use redaction::*;
+#[cfg(feature = "redis")]
+pub use redis_cache::*;
+pub use repeat::*;
+pub use resolver::*;
+pub use replay::*;
+pub use request::*;
+pub use schedule::*;
+pub use search::*;
+pub use stats::*;
+pub use storage::*;
 pub use templates::*;
+pub use trace::*;
+pub use validation::*;
 pub use crate::terarium::*;
 
+mod analysis;
+mod annotations;
+mod audit;
+mod brand;
+mod buffers;
+mod cache;
+#[cfg(feature = "async")]
+mod cancellation;
+mod chaos;
+mod compliance;
+mod cost;
+mod csv;
+mod determinism;
+mod dialect;
+mod direction;
+mod display;
+mod docs;
+mod filters;
+mod flags;
+mod fingerprint;
+#[cfg(feature = "filters")]
+mod format_filters;
+mod formatting;
+#[cfg(feature = "git")]
+mod git;
+mod group;
+mod handlebars;
+mod history;
+mod hook;
+mod ics;
+mod integrity;
+mod intern;
+mod keys;
+mod limits;
+mod links;
+mod loader;
+mod localization;
+mod lookup;
+#[cfg(feature = "markup")]
+mod markup;
+mod memo;
+mod migration;
+mod mime;
+mod missing;
+mod namespaces;
+mod normalization;
+mod observer;
+mod overrides;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod pattern;
+mod placeholders;
+mod preheader;
+mod preprocess;
+mod profile;
+mod quota;
+mod redaction;
+#[cfg(feature = "redis")]
+mod redis_cache;
+mod repeat;
+mod replay;
+mod request;
+mod resolver;
+mod schedule;
+mod schema;
+mod search;
+mod scope;
+mod selection;
+mod snippets;
+mod stats;
+mod storage;
 mod templates;
 mod terarium;
+pub mod testing;
+mod trace;
+mod validation;
@@ -0,0 +1,66 @@
+use tera::Tera;
+
+use crate::analysis::include_depths;
+use crate::{Template, TerariumBuilderError};
+
+
+/// Limits checked when templates are added, protecting deployments where templates come from untrusted sources.
+/// No limit is set by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuildLimits {
+    /// Maximal size of one content in bytes.
+    pub max_content_bytes: Option<usize>,
+    /// Maximal number of nested `{% include %}` statements.
+    pub max_include_depth: Option<usize>,
+    /// Maximal number of templates.
+    pub max_templates: Option<usize>,
+}
+
+
+impl BuildLimits {
+    /// Set maximal size of one content in bytes.
+    pub fn max_content_bytes(mut self, bytes: usize) -> Self {
+        self.max_content_bytes = Some(bytes);
+        self
+    }
+
+    /// Set maximal number of nested `{% include %}` statements.
+    pub fn max_include_depth(mut self, depth: usize) -> Self {
+        self.max_include_depth = Some(depth);
+        self
+    }
+
+    /// Set maximal number of templates.
+    pub fn max_templates(mut self, count: usize) -> Self {
+        self.max_templates = Some(count);
+        self
+    }
+
+    /// Check number of templates.
+    pub(crate) fn check_template_count(&self, count: usize) -> Result<(), TerariumBuilderError> {
+        match self.max_templates {
+            Some(limit) if count > limit => Err(TerariumBuilderError::TooManyTemplates(count, limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check sizes of all contents of the template.
+    pub(crate) fn check_template(&self, key: &str, template: &Template) -> Result<(), TerariumBuilderError> {
+        let Some(limit) = self.max_content_bytes else { return Ok(()) };
+        match template.contents().iter().map(|c| c.content.len()).find(|size| *size > limit) {
+            Some(size) => Err(TerariumBuilderError::ContentTooLarge(key.to_owned(), size, limit)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check include depth of all contents in the `tera`.
+    pub(crate) fn check_include_depth(&self, tera: &Tera) -> Result<(), TerariumBuilderError> {
+        let Some(limit) = self.max_include_depth else { return Ok(()) };
+        let mut depths: Vec<_> = include_depths(tera).into_iter().filter(|(_, depth)| *depth > limit).collect();
+        depths.sort();
+        match depths.first() {
+            Some((name, depth)) => Err(TerariumBuilderError::IncludeDepthExceeded(name.to_string(), *depth, limit)),
+            None => Ok(()),
+        }
+    }
+}
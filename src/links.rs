@@ -0,0 +1,169 @@
+use std::ops::Range;
+
+
+/// Attribute of the `<a>` element excluding the link from rewriting, the attribute itself is removed from output.
+pub const SKIP_REWRITE_ATTRIBUTE: &str = "data-no-rewrite";
+
+
+/// Rewrite URLs of links in HTML outputs (e.g. click tracking or UTM decoration).
+/// The rewriter is set by the `TerariumBuilder::set_link_rewriter()` and applied to `href` of every `<a>` element in
+/// contents of the `ContentKind::Html`. Links with the `data-no-rewrite` attribute are kept.
+pub trait LinkRewriter: Send + Sync {
+    /// Return the new URL of the link, the `url` has HTML entities of ampersands decoded.
+    fn rewrite(&self, url: &str) -> String;
+}
+
+
+impl<F> LinkRewriter for F where F: Fn(&str) -> String + Send + Sync {
+    fn rewrite(&self, url: &str) -> String {
+        self(url)
+    }
+}
+
+
+/// Attribute of a tag with offsets relative to the tag start.
+struct Attribute<'a> {
+    /// Whole attribute including leading whitespaces.
+    span: Range<usize>,
+    name: &'a str,
+    value: Option<Range<usize>>,
+}
+
+
+/// Rewrite `href` of all `<a>` elements in the `html`.
+pub(crate) fn rewrite_links(html: &str, rewriter: &dyn LinkRewriter) -> String {
+    let lowercase = html.to_ascii_lowercase();
+    let mut result = String::with_capacity(html.len());
+    let mut position = 0;
+
+    while let Some(offset) = lowercase[position..].find("<a") {
+        let start = position + offset;
+        let is_anchor = html[start + 2..].starts_with(|c: char| c.is_ascii_whitespace());
+        let Some(end) = tag_end(&html[start..]).filter(|_| is_anchor) else {
+            result.push_str(&html[position..start + 2]);
+            position = start + 2;
+            continue;
+        };
+        result.push_str(&html[position..start]);
+        result.push_str(&rewrite_tag(&html[start..=start + end], rewriter));
+        position = start + end + 1;
+    }
+    result.push_str(&html[position..]);
+    result
+}
+
+
+fn rewrite_tag(tag: &str, rewriter: &dyn LinkRewriter) -> String {
+    let attributes = parse_attributes(tag);
+    if attributes.iter().any(|a| a.name.eq_ignore_ascii_case(SKIP_REWRITE_ATTRIBUTE)) {
+        let mut result = String::with_capacity(tag.len());
+        let mut position = 0;
+        for attribute in attributes.iter().filter(|a| a.name.eq_ignore_ascii_case(SKIP_REWRITE_ATTRIBUTE)) {
+            result.push_str(&tag[position..attribute.span.start]);
+            position = attribute.span.end;
+        }
+        result.push_str(&tag[position..]);
+        return result;
+    }
+
+    let href = attributes.iter().find(|a| a.name.eq_ignore_ascii_case("href")).and_then(|a| a.value.clone());
+    let Some(range) = href else { return tag.to_owned() };
+    let url = rewriter.rewrite(&tag[range.clone()].replace("&amp;", "&"));
+    let url = url.replace('&', "&amp;").replace('"', "&quot;");
+    match tag[..range.start].ends_with(['"', '\'']) {
+        true => format!("{}{}{}", &tag[..range.start], url, &tag[range.end..]),
+        false => format!("{}\"{}\"{}", &tag[..range.start], url, &tag[range.end..]),
+    }
+}
+
+
+/// Parse attributes of the tag (starting by `<` and its name and ending by `>`).
+fn parse_attributes(tag: &str) -> Vec<Attribute<'_>> {
+    let bytes = tag.as_bytes();
+    let end = tag.len() - 1;
+    let skip_whitespaces = |mut i: usize| {
+        while i < end && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+    let mut attributes = Vec::new();
+    let mut i = tag.find(|c: char| c.is_ascii_whitespace()).unwrap_or(end);
+
+    while i < end {
+        let start = i;
+        let name_start = skip_whitespaces(i);
+        i = name_start;
+        while i < end && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'/') {
+            i += 1;
+        }
+        if i == name_start {
+            i += 1;
+            continue;
+        }
+        let name = &tag[name_start..i];
+        let mut value = None;
+        let equals = skip_whitespaces(i);
+        if equals < end && bytes[equals] == b'=' {
+            let value_start = skip_whitespaces(equals + 1);
+            if value_start < end && matches!(bytes[value_start], b'"' | b'\'') {
+                let quote = bytes[value_start] as char;
+                let value_end = tag[value_start + 1..end].find(quote).map_or(end, |o| value_start + 1 + o);
+                value = Some(value_start + 1..value_end);
+                i = (value_end + 1).min(end);
+            } else {
+                i = value_start;
+                while i < end && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                value = Some(value_start..i);
+            }
+        }
+        if name != "/" {
+            attributes.push(Attribute { span: start..i, name, value });
+        }
+    }
+    attributes
+}
+
+
+/// Return offset of the `>` closing the tag starting at the beginning of the `text`, quoted attributes are skipped.
+pub(crate) fn tag_end(text: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(url: &str) -> String {
+        format!("https://t.example.com/?u={}&c=1", url)
+    }
+
+    #[test]
+    fn rewrite() {
+        let html = "<p><A class=\"x\" HREF=\"https://a.com/?a=1&amp;b=2\">a</A> <abbr>b</abbr> <a href=/c>c</a></p>";
+        assert_eq!(
+            rewrite_links(html, &track),
+            "<p><A class=\"x\" HREF=\"https://t.example.com/?u=https://a.com/?a=1&amp;b=2&amp;c=1\">a</A> <abbr>b</abbr> \
+                <a href=\"https://t.example.com/?u=/c&amp;c=1\">c</a></p>",
+        );
+    }
+
+    #[test]
+    fn skip() {
+        let html = "<a href='https://a.com' data-no-rewrite>a</a><a name=\"top\">b</a>";
+        assert_eq!(rewrite_links(html, &track), "<a href='https://a.com'>a</a><a name=\"top\">b</a>");
+    }
+}
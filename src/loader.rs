@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{Content, ContentKind, KeyPattern, Template, TemplateError};
+
+
+/// Extension of template files.
+const TEMPLATE_EXTENSION: &str = "tera";
+
+
+/// Layout of template files in the directory loaded by the `DirectoryLoader`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathConvention {
+    /// Each directory containing template files is one template. Its path relative to the root (joined by `/`) is
+    /// the template key and files are named `<language>[.<kind>].tera` (e.g. `emails/welcome/en.html.tera`).
+    #[default]
+    KeyDirectory,
+    /// Top level directories are languages. Path of the file relative to the language directory without
+    /// extensions is the template key (e.g. `en/emails/welcome.html.tera`).
+    LanguageDirectory,
+}
+
+
+/// Load templates from files in a directory.
+///
+/// Template keys and languages are taken from paths of template files by the `PathConvention`, e.g.
+/// `emails/welcome/en.html.tera` is the English HTML content of the `emails/welcome` template by default.
+/// Kind of the content and its autoescape setting are inferred from the last extension before the `.tera`
+/// (see `ContentKind::from_extension()`), files with unknown extensions are rejected. Contents are named by their paths relative to the root, so they can be
+/// included by other templates. Other files are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryLoader {
+    root: PathBuf,
+    convention: PathConvention,
+    pattern: Option<KeyPattern>,
+}
+
+
+impl DirectoryLoader {
+    /// Create loader of the `root` directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), convention: PathConvention::default(), pattern: None }
+    }
+
+    /// Set layout of template files.
+    pub fn with_convention(mut self, convention: PathConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Load only files whose paths relative to the root (joined by `/`) match the `pattern`
+    /// (e.g. `emails/*.html.tera` or the `emails/` prefix). The `*` matches any characters including `/`.
+    pub fn matching(mut self, pattern: impl Into<KeyPattern>) -> Self {
+        self.pattern = Some(pattern.into());
+        self
+    }
+
+    /// Read all template files and return templates by their keys.
+    pub fn load(&self) -> Result<HashMap<String, Template>, LoaderError> {
+        let mut templates = HashMap::<String, Template>::new();
+        for path in list_files(&self.root)? {
+            if !self.is_selected(&path) {
+                continue;
+            }
+            let Some((template_key, content)) = self.read_content(&path)? else { continue };
+            templates
+                .entry(template_key.clone())
+                .or_default()
+                .add_content(content)
+                .map_err(|e| LoaderError::InvalidTemplate(template_key, e))?;
+        }
+        Ok(templates)
+    }
+
+    /// Return `true` when the file matches the pattern or no pattern is set.
+    fn is_selected(&self, path: &Path) -> bool {
+        let Some(pattern) = &self.pattern else { return true };
+        path.strip_prefix(&self.root).is_ok_and(|relative| pattern.matches(&relative_name(relative)))
+    }
+
+    /// Read the template file and return its template key and content.
+    /// Return `None` when the file is not a template file.
+    fn read_content(&self, path: &Path) -> Result<Option<(String, Content)>, LoaderError> {
+        let relative = path.strip_prefix(&self.root).map_err(|_| LoaderError::InvalidPath(path.to_owned()))?;
+        let Some(file_name) = relative.file_name().and_then(|n| n.to_str()) else { return Ok(None) };
+        let Some(stem) = file_name.strip_suffix(TEMPLATE_EXTENSION).and_then(|s| s.strip_suffix('.')) else {
+            return Ok(None);
+        };
+        let (stem, kind) = match stem.rsplit_once('.') {
+            Some((stem, extension)) => match ContentKind::from_extension(extension) {
+                Some(kind) => (stem, Some(kind)),
+                None => return Err(LoaderError::UnknownExtension(path.to_owned(), extension.to_owned())),
+            },
+            None => (stem, None),
+        };
+        let mut directories: Vec<_> = relative
+            .parent()
+            .map(|p| p.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect())
+            .unwrap_or_default();
+        let (template_key, language) = match self.convention {
+            PathConvention::KeyDirectory => (directories.join("/"), stem.to_owned()),
+            PathConvention::LanguageDirectory if !directories.is_empty() => {
+                let language = directories.remove(0);
+                directories.push(stem.to_owned());
+                (directories.join("/"), language)
+            }
+            PathConvention::LanguageDirectory => (String::new(), String::new()),
+        };
+        if template_key.is_empty() {
+            return Err(LoaderError::InvalidPath(path.to_owned()));
+        }
+        let name = relative_name(relative);
+
+        let text = fs::read_to_string(path).map_err(|e| LoaderError::Io(path.to_owned(), e))?;
+        let mut content = Content::new_named(text, vec![language], name);
+        if let Some(kind) = kind {
+            content = content.with_kind(kind).with_autoescape(kind.escapes_values());
+        }
+        Ok(Some((template_key, content)))
+    }
+}
+
+
+/// Return the relative path with components joined by `/`.
+fn relative_name(relative: &Path) -> String {
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+
+/// Return paths of all files in the directory and its subdirectories, sorted.
+/// Symbolic links to directories are followed, each directory is listed only once so link cycles end.
+pub(crate) fn list_files(directory: &Path) -> Result<Vec<PathBuf>, LoaderError> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut directories = vec![directory.to_owned()];
+    while let Some(directory) = directories.pop() {
+        let canonical = fs::canonicalize(&directory).map_err(|e| LoaderError::Io(directory.clone(), e))?;
+        if !visited.insert(canonical) {
+            continue;
+        }
+        let entries = fs::read_dir(&directory).map_err(|e| LoaderError::Io(directory.clone(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| LoaderError::Io(directory.clone(), e))?.path();
+            match path.is_dir() {
+                true => directories.push(path),
+                false => files.push(path),
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+
+/// Errors returned by the `DirectoryLoader`.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum LoaderError {
+    /// File or directory cannot be read.
+    #[error("Cannot read {0}")]
+    Io(PathBuf, #[source] io::Error),
+    /// Template file is not placed in a template directory.
+    #[error("Template file {0} is not in a template directory")]
+    InvalidPath(PathBuf),
+    /// Contents of the template are in conflict.
+    #[error("Invalid template {0}")]
+    InvalidTemplate(String, #[source] TemplateError),
+    /// Extension of the template file before the `.tera` is not a known content kind.
+    #[error("Template file {0} has unknown extension {1}")]
+    UnknownExtension(PathBuf, String),
+}
+
+
+impl LoaderError {
+    /// Return stable code of the error for machine handling.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "IO_FAILED",
+            Self::InvalidPath(_) => "INVALID_PATH",
+            Self::UnknownExtension(..) => "UNKNOWN_EXTENSION",
+            Self::InvalidTemplate(_, error) => error.code(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::TerariumBuilder;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terarium_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn load() {
+        let root = temp_dir("load");
+        write(&root, "emails/welcome/en.html.tera", "<p>Hello</p>");
+        write(&root, "emails/welcome/cs.html.tera", "<p>Ahoj</p>");
+        write(&root, "emails/subject/en.tera", "Hello");
+        write(&root, "emails/data/en.json.tera", "{}");
+        write(&root, "emails/welcome/README.md", "ignored");
+
+        let templates = DirectoryLoader::new(&root).load().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let mut keys: Vec<_> = templates.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["emails/data", "emails/subject", "emails/welcome"]);
+        let welcome = templates["emails/welcome"].contents();
+        assert_eq!(welcome.len(), 2);
+        assert_eq!(welcome[0].name.as_deref(), Some("emails/welcome/cs.html.tera"));
+        assert_eq!(welcome[0].kind, ContentKind::Html);
+        assert_eq!(welcome[0].autoescape, Some(true));
+        let data = &templates["emails/data"].contents()[0];
+        assert_eq!((data.kind, data.autoescape), (ContentKind::Json, Some(false)));
+        let subject = &templates["emails/subject"].contents()[0];
+        assert_eq!((subject.kind, subject.autoescape, subject.languages.clone()), (ContentKind::Text, None, vec!["en".to_owned()]));
+    }
+
+    #[test]
+    fn load_kind_from_last_extension() {
+        let root = temp_dir("last_extension");
+        write(&root, "emails/welcome/en.v2.html.tera", "<p>Hello</p>");
+        let templates = DirectoryLoader::new(&root).load().unwrap();
+        write(&root, "emails/welcome/cs.weird.tera", "Ahoj");
+        let error = DirectoryLoader::new(&root).load().err().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let content = &templates["emails/welcome"].contents()[0];
+        assert_eq!((content.kind, content.autoescape), (ContentKind::Html, Some(true)));
+        assert_eq!(content.languages, vec!["en.v2"]);
+        assert!(matches!(&error, LoaderError::UnknownExtension(_, extension) if extension == "weird"));
+        assert_eq!(error.code(), "UNKNOWN_EXTENSION");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn load_with_symlink_cycle() {
+        let root = temp_dir("symlink_cycle");
+        write(&root, "emails/welcome/en.tera", "Hello");
+        std::os::unix::fs::symlink(&root, root.join("emails/loop")).unwrap();
+
+        let templates = DirectoryLoader::new(&root).load();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(templates.unwrap().keys().collect::<Vec<_>>(), vec!["emails/welcome"]);
+    }
+
+    #[test]
+    fn builder_try_from_loader() {
+        let root = temp_dir("builder_try_from");
+        write(&root, "welcome/en.tera", "Hello");
+        let builder = TerariumBuilder::try_from(&DirectoryLoader::new(&root));
+        fs::remove_dir_all(&root).unwrap();
+        let instance = builder.unwrap().build().unwrap();
+        assert_eq!(instance.render_template(&tera::Context::new(), "welcome", "en", None).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn builder_from_dir() {
+        let root = temp_dir("from_dir");
+        write(&root, "emails/welcome/en.html.tera", "<p>Hello</p>");
+        write(&root, "emails/welcome/en.tera", "Hello");
+        write(&root, "sms/welcome/en.tera", "Hi");
+        let mut builder = TerariumBuilder::default();
+        let result = builder.add_templates_from_dir(&root, "emails/*.html.tera");
+        let missing = TerariumBuilder::default().add_templates_from_dir(root.join("missing"), "*");
+        fs::remove_dir_all(&root).unwrap();
+
+        result.unwrap();
+        let instance = builder.build().unwrap();
+        assert_eq!(instance.template_languages(), vec![("emails/welcome".to_owned(), "en".to_owned())]);
+        let output = instance.render_template(&tera::Context::new(), "emails/welcome", "en", None).unwrap();
+        assert_eq!(output, "<p>Hello</p>");
+        assert!(matches!(missing, Err(crate::TerariumBuilderError::LoadingFailed(LoaderError::Io(..)))));
+    }
+
+    #[test]
+    fn load_language_directories() {
+        let root = temp_dir("language_directories");
+        write(&root, "en/welcome.html.tera", "<p>Hello</p>");
+        write(&root, "cs/welcome.html.tera", "<p>Ahoj</p>");
+        write(&root, "en/emails/bye.tera", "Bye");
+
+        let templates = DirectoryLoader::new(&root).with_convention(PathConvention::LanguageDirectory).load().unwrap();
+        let outside = DirectoryLoader::new(&root).with_convention(PathConvention::LanguageDirectory);
+        write(&root, "welcome.tera", "Hello");
+        let outside = outside.load();
+        fs::remove_dir_all(&root).unwrap();
+
+        let welcome = templates["welcome"].contents();
+        assert_eq!(welcome.len(), 2);
+        assert_eq!(welcome[0].name.as_deref(), Some("cs/welcome.html.tera"));
+        assert_eq!((welcome[0].kind, welcome[0].languages.clone()), (ContentKind::Html, vec!["cs".to_owned()]));
+        assert_eq!(templates["emails/bye"].contents()[0].languages, vec!["en".to_owned()]);
+        assert!(matches!(outside, Err(LoaderError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn file_outside_of_template_directory() {
+        let root = temp_dir("outside");
+        write(&root, "en.tera", "Hello");
+        let result = DirectoryLoader::new(&root).load();
+        fs::remove_dir_all(&root).unwrap();
+        assert!(matches!(result, Err(LoaderError::InvalidPath(_))));
+    }
+}
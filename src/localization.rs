@@ -0,0 +1,99 @@
+use crate::{RenderObserver, Template, TerariumBuilderError};
+
+
+/// Enforcement of languages every template must have content in (see `TerariumBuilder::set_required_languages()`).
+/// Templates are checked when the `Terarium` is built and when they are added by the `Terarium::add_template()`,
+/// so new languages can be rolled out with warnings first and errors later.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocalizationEnforcement {
+    /// Templates are not checked.
+    #[default]
+    Off,
+    /// Missing languages are reported to the `RenderObserver::missing_localization()`.
+    Warn,
+    /// Templates with missing languages are rejected by the `TerariumBuilderError::MissingLocalization`.
+    Error,
+}
+
+
+/// Template without content in a required language.
+#[derive(Debug)]
+pub struct MissingLocalization<'a> {
+    /// Key of the template.
+    pub template_key: &'a str,
+    /// The required language.
+    pub language: &'a str,
+}
+
+
+/// Required languages with their enforcement.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LocalizationPolicy {
+    pub(crate) languages: Vec<String>,
+    pub(crate) enforcement: LocalizationEnforcement,
+}
+
+
+impl LocalizationPolicy {
+    /// Check the template has content in all required languages.
+    pub(crate) fn check(
+        &self,
+        template_key: &str,
+        template: &Template,
+        observer: Option<&dyn RenderObserver>,
+    ) -> Result<(), TerariumBuilderError> {
+        if self.enforcement == LocalizationEnforcement::Off {
+            return Ok(());
+        }
+        let missing = self.languages
+            .iter()
+            .filter(|language| !template.contents().iter().any(|content| content.languages.contains(language)));
+        for language in missing {
+            match (self.enforcement, observer) {
+                (LocalizationEnforcement::Error, _) => return Err(
+                    TerariumBuilderError::MissingLocalization(template_key.to_owned(), language.clone())
+                ),
+                (LocalizationEnforcement::Warn, Some(observer)) => {
+                    observer.missing_localization(&MissingLocalization { template_key, language });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::Content;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Collector(Mutex<Vec<String>>);
+
+    impl RenderObserver for Collector {
+        fn missing_localization(&self, missing: &MissingLocalization) {
+            self.0.lock().unwrap().push(format!("{} {}", missing.template_key, missing.language));
+        }
+    }
+
+    #[test]
+    fn check() {
+        let template = Template::new(vec![Content::new("a".to_owned(), vec!["en".to_owned()])]).unwrap();
+        let policy = |enforcement| LocalizationPolicy {
+            languages: vec!["en".to_owned(), "cs".to_owned(), "de".to_owned()],
+            enforcement,
+        };
+        let collector = Collector::default();
+        policy(LocalizationEnforcement::Off).check("tpl", &template, Some(&collector)).unwrap();
+        policy(LocalizationEnforcement::Warn).check("tpl", &template, Some(&collector)).unwrap();
+        assert_eq!(*collector.0.lock().unwrap(), vec!["tpl cs", "tpl de"]);
+
+        let result = policy(LocalizationEnforcement::Error).check("tpl", &template, None);
+        assert!(matches!(result, Err(TerariumBuilderError::MissingLocalization(t, l)) if t == "tpl" && l == "cs"));
+    }
+}
@@ -0,0 +1,13 @@
+use std::collections::HashMap;
+
+
+/// Hasher of maps looked up on every render, `ahash` is used when the `ahash` feature is enabled.
+#[cfg(feature = "ahash")]
+pub(crate) type LookupState = ahash::RandomState;
+
+/// Hasher of maps looked up on every render, `ahash` is used when the `ahash` feature is enabled.
+#[cfg(not(feature = "ahash"))]
+pub(crate) type LookupState = std::collections::hash_map::RandomState;
+
+/// Map looked up on every render.
+pub(crate) type LookupMap<K, V> = HashMap<K, V, LookupState>;
@@ -0,0 +1,181 @@
+use crate::links::tag_end;
+use crate::OutputValidator;
+
+
+/// HTML elements without closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// HTML elements which closing tag can be omitted.
+const IMPLICITLY_CLOSED_ELEMENTS: [&str; 15] = [
+    "p", "li", "dt", "dd", "tr", "td", "th", "thead", "tbody", "tfoot", "option", "optgroup", "colgroup", "rt", "rp",
+];
+
+/// HTML elements which content is not parsed.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+
+/// Check rendered HTML or XML output for unclosed tags and invalid nesting.
+/// The check is lightweight - it does not validate element names, attributes or content models.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkupValidator {
+    html: bool,
+}
+
+
+impl MarkupValidator {
+    /// Create validator following HTML rules (void elements, optional closing tags, case insensitive names).
+    pub fn html() -> Self {
+        Self { html: true }
+    }
+
+    /// Create validator following XML rules.
+    pub fn xml() -> Self {
+        Self { html: false }
+    }
+
+    fn check(&self, output: &str) -> Result<(), String> {
+        let mut open = Vec::<(String, usize)>::new();
+        let mut position = 0;
+        // line numbers are counted incrementally, so the output is scanned only once
+        let (mut line, mut counted) = (1, 0);
+
+        while let Some(offset) = output[position..].find('<') {
+            let start = position + offset;
+            let rest = &output[start..];
+            line += output[counted..start].matches('\n').count();
+            counted = start;
+
+            if rest.starts_with("<!--") {
+                position = start + skip_after(rest, "-->").ok_or_else(|| format!("Unclosed comment at line {}", line))?;
+            } else if rest.starts_with("<![CDATA[") {
+                position = start + skip_after(rest, "]]>").ok_or_else(|| format!("Unclosed CDATA at line {}", line))?;
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                position = start + skip_after(rest, ">").ok_or_else(|| format!("Unclosed declaration at line {}", line))?;
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let end = tag.find('>').ok_or_else(|| format!("Unclosed tag at line {}", line))?;
+                let name = self.normalize_name(tag[..end].trim());
+                self.close(&mut open, &name, line)?;
+                position = start + 2 + end + 1;
+            } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                let end = tag_end(rest).ok_or_else(|| format!("Unclosed tag at line {}", line))?;
+                let body = &rest[1..end];
+                let self_closing = body.ends_with('/');
+                let name_end = body.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(body.len());
+                let name = self.normalize_name(&body[..name_end]);
+                position = start + end + 1;
+
+                if self.html && RAW_TEXT_ELEMENTS.contains(&name.as_str()) && !self_closing {
+                    let closing = format!("</{}", name);
+                    let content_end = find_ignore_ascii_case(&output[position..], &closing)
+                        .ok_or_else(|| format!("Unclosed tag <{}> opened at line {}", name, line))?;
+                    position += content_end;
+                    open.push((name, line));
+                } else if !self_closing && !self.is_void(&name) {
+                    open.push((name, line));
+                }
+            } else if self.html {
+                position = start + 1;
+            } else {
+                return Err(format!("Unescaped '<' at line {}", line));
+            }
+        }
+
+        while let Some((name, line)) = open.pop() {
+            if !self.is_implicitly_closed(&name) {
+                return Err(format!("Unclosed tag <{}> opened at line {}", name, line));
+            }
+        }
+        Ok(())
+    }
+
+    fn close(&self, open: &mut Vec<(String, usize)>, name: &str, line: usize) -> Result<(), String> {
+        while let Some((open_name, open_line)) = open.pop() {
+            if open_name == name {
+                return Ok(());
+            }
+            if !self.is_implicitly_closed(&open_name) {
+                return Err(format!(
+                    "Closing tag </{}> at line {} does not match <{}> opened at line {}", name, line, open_name, open_line
+                ));
+            }
+        }
+        Err(format!("Unexpected closing tag </{}> at line {}", name, line))
+    }
+
+    fn normalize_name(&self, name: &str) -> String {
+        match self.html {
+            true => name.to_ascii_lowercase(),
+            false => name.to_owned(),
+        }
+    }
+
+    fn is_void(&self, name: &str) -> bool {
+        self.html && VOID_ELEMENTS.contains(&name)
+    }
+
+    fn is_implicitly_closed(&self, name: &str) -> bool {
+        self.html && IMPLICITLY_CLOSED_ELEMENTS.contains(&name)
+    }
+}
+
+
+impl OutputValidator for MarkupValidator {
+    fn validate(&self, output: &str) -> Result<(), String> {
+        self.check(output)
+    }
+}
+
+
+/// Return offset after the `terminator` or `None` if there is no terminator.
+fn skip_after(text: &str, terminator: &str) -> Option<usize> {
+    text.find(terminator).map(|i| i + terminator.len())
+}
+
+
+/// Return offset of the first occurrence of the ASCII `needle` in the `text` ignoring ASCII case.
+fn find_ignore_ascii_case(text: &str, needle: &str) -> Option<usize> {
+    text.as_bytes().windows(needle.len()).position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_html() {
+        let html = "<!DOCTYPE html>\n<html><body>\n<!-- <div> -->\n<p>foo<br>bar\n<ul><li>a<li>b</ul>\
+            <img src=\"a>b.png\"/><script>if (a < b) { x = '</div>'; }</script><P>x</p></body></html>";
+        assert_eq!(MarkupValidator::html().validate(html), Ok(()));
+    }
+
+    #[test]
+    fn raw_text_closing_tag_ignores_case() {
+        let html = "<p>\n<SCRIPT>x = '<div>';</Script>\n<div>";
+        assert_eq!(MarkupValidator::html().validate(html).unwrap_err(), "Unclosed tag <div> opened at line 3");
+    }
+
+    #[test]
+    fn unclosed_tag() {
+        let result = MarkupValidator::html().validate("<div>\n<span>foo</div>");
+        assert_eq!(result.unwrap_err(), "Closing tag </div> at line 2 does not match <span> opened at line 2");
+        let result = MarkupValidator::html().validate("<table>\n<div>foo");
+        assert_eq!(result.unwrap_err(), "Unclosed tag <div> opened at line 2");
+    }
+
+    #[test]
+    fn unexpected_closing_tag() {
+        let result = MarkupValidator::html().validate("foo</a>");
+        assert_eq!(result.unwrap_err(), "Unexpected closing tag </a> at line 1");
+    }
+
+    #[test]
+    fn xml() {
+        assert!(MarkupValidator::xml().validate("<?xml version=\"1.0\"?><a><b/><![CDATA[<c>]]></a>").is_ok());
+        assert!(MarkupValidator::xml().validate("<a><br></a>").is_err());
+        assert!(MarkupValidator::xml().validate("<a>1 < 2</a>").is_err());
+        assert!(MarkupValidator::xml().validate("<a><B></b></a>").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+
+/// Outputs by template keys and languages.
+type Outputs = HashMap<(String, String), String>;
+
+
+thread_local! {
+    static MEMO: RefCell<Option<Outputs>> = const { RefCell::new(None) };
+}
+
+
+/// Call the `f` with memoization of template outputs enabled, outputs are dropped when the `f` returns.
+pub(crate) fn with_memo<R>(f: impl FnOnce() -> R) -> R {
+    replace_memo(Some(Outputs::new()), f)
+}
+
+
+/// Call the `f` with memoization disabled (e.g. for renders with context different from the memoized ones).
+pub(crate) fn without_memo<R>(f: impl FnOnce() -> R) -> R {
+    replace_memo(None, f)
+}
+
+
+/// Return memoized output of the template in the language or call the `render` and memoize its output.
+pub(crate) fn memoized<E>(
+    template_key: &str,
+    language: &str,
+    render: impl FnOnce() -> Result<String, E>,
+) -> Result<String, E> {
+    let key = (template_key.to_owned(), language.to_owned());
+    let cached = MEMO.with(|memo| memo.borrow().as_ref().map(|outputs| outputs.get(&key).cloned()));
+    match cached {
+        None => render(),
+        Some(Some(output)) => Ok(output),
+        Some(None) => {
+            let output = render()?;
+            MEMO.with(|memo| {
+                if let Some(outputs) = memo.borrow_mut().as_mut() {
+                    outputs.insert(key, output.clone());
+                }
+            });
+            Ok(output)
+        }
+    }
+}
+
+
+/// Restore the previous outputs when dropped, so they are restored even when the render panics.
+struct MemoGuard(Option<Outputs>);
+
+
+impl Drop for MemoGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        MEMO.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+
+fn replace_memo<R>(memo: Option<Outputs>, f: impl FnOnce() -> R) -> R {
+    let _guard = MemoGuard(MEMO.with(|current| current.replace(memo)));
+    f()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memoize_in_scope() {
+        let mut renders = 0;
+        let mut render = |language: &str| memoized("tpl", language, || -> Result<_, ()> {
+            renders += 1;
+            Ok(format!("{} {}", language, renders))
+        });
+        assert_eq!(render("en"), Ok("en 1".to_owned()));
+        assert_eq!(render("en"), Ok("en 2".to_owned()));
+        with_memo(|| {
+            assert_eq!(render("en"), Ok("en 3".to_owned()));
+            assert_eq!(render("en"), Ok("en 3".to_owned()));
+            assert_eq!(render("cs"), Ok("cs 4".to_owned()));
+            assert_eq!(without_memo(|| render("en")), Ok("en 5".to_owned()));
+        });
+        assert_eq!(render("en"), Ok("en 6".to_owned()));
+    }
+
+    #[test]
+    fn memo_is_restored_after_panic() {
+        let result = std::panic::catch_unwind(|| with_memo(|| panic!("render failed")));
+        assert!(result.is_err());
+        assert!(MEMO.with(|memo| memo.borrow().is_none()));
+    }
+}
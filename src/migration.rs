@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{Template, TemplateGroup, TerariumBuilderError};
+
+
+/// Keywords of tags referencing other contents by their names.
+const REFERENCE_TAGS: [&str; 3] = ["include", "extends", "import"];
+
+
+/// Rename of template keys applied to templates and groups at once.
+///
+/// Group members are pointed to the new keys. Contents named by the `DirectoryLoader` convention (prefixed by
+/// the template key and `/`) are renamed too and references to them in `include`, `extends` and `import` tags of
+/// all contents are rewritten.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyMigration {
+    keys: HashMap<String, String>,
+}
+
+
+impl KeyMigration {
+    /// Rename the template key `from` to the `to`.
+    pub fn rename(mut self, from: String, to: String) -> Self {
+        self.keys.insert(from, to);
+        self
+    }
+
+    /// Return the new key of the template key.
+    pub fn new_key<'a>(&'a self, key: &'a str) -> &'a str {
+        self.keys.get(key).map(|k| k.as_str()).unwrap_or(key)
+    }
+
+    /// Iterate over renamed template keys with their new keys.
+    pub(crate) fn renames(&self) -> impl Iterator<Item=(&String, &String)> {
+        self.keys.iter()
+    }
+
+    /// Apply the migration to templates and groups.
+    /// Nothing is changed when any renamed template is missing or a new key is already used.
+    pub fn apply(
+        &self,
+        templates: &mut HashMap<String, Template>,
+        groups: &mut HashMap<String, TemplateGroup>,
+    ) -> Result<(), TerariumBuilderError> {
+        for (from, to) in self.keys.iter() {
+            if !templates.contains_key(from) {
+                return Err(TerariumBuilderError::TemplateNotFound(from.clone()));
+            }
+            let target_used = templates.contains_key(to) && !self.keys.contains_key(to);
+            if target_used || self.keys.iter().any(|(other, other_to)| other != from && other_to == to) {
+                return Err(TerariumBuilderError::KeyConflict(from.clone(), to.clone()));
+            }
+        }
+
+        let names = self.content_names(templates);
+        let mut migrated = HashMap::with_capacity(templates.len());
+        for (key, mut template) in templates.drain() {
+            template.update_contents(|content| {
+                if let Some(name) = content.name.as_mut() {
+                    if let Some(new_name) = names.get(name.as_str()) {
+                        *name = new_name.clone();
+                    }
+                }
+                content.content = rewrite_references(&content.content, &names);
+            });
+            migrated.insert(self.new_key(&key).to_owned(), template);
+        }
+        *templates = migrated;
+
+        for group in groups.values_mut() {
+            for template_key in group.members.values_mut() {
+                *template_key = self.new_key(template_key).to_owned();
+            }
+        }
+        Ok(())
+    }
+
+    /// Return new names of contents by their old names.
+    fn content_names(&self, templates: &HashMap<String, Template>) -> HashMap<String, String> {
+        let mut names = HashMap::new();
+        for (from, to) in self.keys.iter() {
+            let contents = templates.get(from).map(|t| t.contents()).unwrap_or_default();
+            for name in contents.iter().filter_map(|c| c.name.as_deref()) {
+                if let Some(rest) = name.strip_prefix(from.as_str()).and_then(|r| r.strip_prefix('/')) {
+                    names.insert(name.to_owned(), format!("{}/{}", to, rest));
+                }
+            }
+        }
+        names
+    }
+}
+
+
+/// Replace names in string literals of `include`, `extends` and `import` tags by their new names.
+fn rewrite_references(source: &str, names: &HashMap<String, String>) -> String {
+    if names.is_empty() {
+        return source.to_owned();
+    }
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{%") {
+        let Some(length) = rest[start..].find("%}") else { break };
+        let tag = &rest[start..start + length];
+        result.push_str(&rest[..start]);
+        let keyword = tag[2..].trim_start_matches('-').split_whitespace().next().unwrap_or_default();
+        match REFERENCE_TAGS.contains(&keyword) {
+            true => result.push_str(&rewrite_literals(tag, names)),
+            false => result.push_str(tag),
+        }
+        rest = &rest[start + length..];
+    }
+    result.push_str(rest);
+    result
+}
+
+
+/// Replace quoted string literals found in the `names` by their new names.
+fn rewrite_literals(tag: &str, names: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(tag.len());
+    let mut rest = tag;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let Some(length) = rest[start + 1..].find(quote) else { break };
+        let literal = &rest[start + 1..start + 1 + length];
+        result.push_str(&rest[..=start]);
+        result.push_str(names.get(literal).map(|n| n.as_str()).unwrap_or(literal));
+        result.push(quote);
+        rest = &rest[start + length + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{Content, TemplateGroupBuilder};
+
+    use super::*;
+
+    #[test]
+    fn rewrite() {
+        let names = HashMap::from([("old/en.tera".to_owned(), "new/en.tera".to_owned())]);
+        let source = "{{ \"old/en.tera\" }}{% include \"old/en.tera\" %}{%- import 'old/en.tera' as m -%}";
+        assert_eq!(
+            rewrite_references(source, &names),
+            "{{ \"old/en.tera\" }}{% include \"new/en.tera\" %}{%- import 'new/en.tera' as m -%}"
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let mut templates = HashMap::from([
+            ("header".to_owned(), Template::new(vec![
+                Content::new_named("Hi".to_owned(), vec!["en".to_owned()], "header/en.tera".to_owned()),
+            ]).unwrap()),
+            ("mail".to_owned(), Template::new(vec![
+                Content::new("{% include \"header/en.tera\" %}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap()),
+        ]);
+        let mut groups = HashMap::from([(
+            "group".to_owned(),
+            TemplateGroupBuilder::default().add_member("header".to_owned(), "header".to_owned()).build(),
+        )]);
+
+        let migration = KeyMigration::default().rename("header".to_owned(), "layout/header".to_owned());
+        migration.apply(&mut templates, &mut groups).unwrap();
+
+        assert!(!templates.contains_key("header"));
+        let header = &templates["layout/header"].contents()[0];
+        assert_eq!(header.name.as_deref(), Some("layout/header/en.tera"));
+        assert_eq!(templates["mail"].contents()[0].content, "{% include \"layout/header/en.tera\" %}");
+        assert_eq!(groups["group"].members["header"], "layout/header");
+    }
+
+    #[test]
+    fn conflict() {
+        let template = Template::new(vec![Content::new("".to_owned(), vec!["en".to_owned()])]).unwrap();
+        let mut templates = HashMap::from([("a".to_owned(), template.clone()), ("b".to_owned(), template)]);
+        let migration = KeyMigration::default().rename("a".to_owned(), "b".to_owned());
+        assert!(migration.apply(&mut templates, &mut HashMap::new()).is_err());
+        let migration = migration.rename("b".to_owned(), "a".to_owned());
+        assert!(migration.apply(&mut templates, &mut HashMap::new()).is_ok());
+        let migration = KeyMigration::default().rename("c".to_owned(), "d".to_owned());
+        assert!(migration.apply(&mut templates, &mut HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+use crate::fingerprint::Fingerprint;
+use crate::normalization::encode_base64;
+use crate::{encode_header, TerariumError};
+
+
+/// Maximal length of base64 encoded body lines (without line break) according to the RFC 2045.
+const MAX_BODY_LINE: usize = 76;
+
+
+/// Transfer encoding applied to the rendered group member (see `TemplateGroupBuilder::encode_member()`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransferEncoding {
+    /// Base64 (RFC 2045), suitable for attachments rendered from templates.
+    Base64,
+    /// Quoted-printable (RFC 2045), suitable for mostly ASCII mail bodies.
+    QuotedPrintable,
+}
+
+
+impl TransferEncoding {
+    /// Return value of the `Content-Transfer-Encoding` header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::QuotedPrintable => "quoted-printable",
+        }
+    }
+
+    /// Encode the output, lines are separated by CRLF and they are at most 76 characters long.
+    pub fn encode(&self, output: &str) -> String {
+        match self {
+            Self::Base64 => {
+                let encoded = encode_base64(output.as_bytes());
+                let lines: Vec<_> = encoded.as_bytes().chunks(MAX_BODY_LINE).map(String::from_utf8_lossy).collect();
+                lines.join("\r\n")
+            }
+            Self::QuotedPrintable => encode_quoted_printable(output),
+        }
+    }
+}
+
+
+/// MIME type and transfer encoding declared for the group member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberEncoding {
+    /// MIME type of the member output (e.g. `text/calendar`).
+    pub mime_type: String,
+    /// Transfer encoding applied to the member output after the render.
+    pub transfer_encoding: TransferEncoding,
+}
+
+
+/// Group member rendered as an attachment (see `TemplateGroupBuilder::attach_member()`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberAttachment {
+    /// Tera template of the file name rendered with the group context (e.g. `invoice-{{ number }}.csv`).
+    pub filename: String,
+    /// MIME type of the attachment (e.g. `text/csv`).
+    pub mime_type: String,
+}
+
+
+/// Attachment rendered by the group, returned in the `RenderOutcome::attachments`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    /// Key of the group member the attachment was rendered from.
+    pub member_key: String,
+    /// Rendered file name.
+    pub filename: String,
+    /// MIME type of the attachment.
+    pub mime_type: String,
+    /// Rendered output of the member.
+    pub data: Vec<u8>,
+}
+
+
+/// Assemble `subject`, `text` and `html` members of a rendered group into a raw RFC 2045 message with
+/// `multipart/alternative` body, so it can be sent by any mail library or directly over SMTP.
+/// The subject is encoded as RFC 2047 encoded words when needed and the bodies are encoded by base64.
+/// At least one of the `text` and `html` members is required, the `subject` is optional.
+pub fn to_mime_multipart(members: &HashMap<String, String>) -> Result<String, TerariumError> {
+    let parts: Vec<_> = [("text", "text/plain"), ("html", "text/html")]
+        .into_iter()
+        .filter_map(|(member_key, mime)| members.get(member_key).map(|body| (mime, body)))
+        .collect();
+    if parts.is_empty() {
+        return Err(TerariumError::MemberNotFound("text".to_owned()));
+    }
+    let boundary = boundary(members);
+
+    let mut message = String::from("MIME-Version: 1.0\r\n");
+    if let Some(subject) = members.get("subject") {
+        message.push_str(&format!("Subject: {}\r\n", encode_header(subject)));
+    }
+    message.push_str(&format!("Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n", boundary));
+    for (mime, body) in parts {
+        message.push_str(&format!("--{}\r\n", boundary));
+        message.push_str(&format!("Content-Type: {}; charset=utf-8\r\n", mime));
+        message.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+        if !body.is_empty() {
+            message.push_str(&TransferEncoding::Base64.encode(body));
+            message.push_str("\r\n");
+        }
+    }
+    message.push_str(&format!("--{}--\r\n", boundary));
+    Ok(message)
+}
+
+
+/// Encode the text as quoted-printable. Line breaks are kept as CRLF, longer lines are split by soft line breaks
+/// and whitespaces at ends of lines are encoded.
+fn encode_quoted_printable(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            result.push_str("\r\n");
+        }
+        let line = line.strip_suffix('\r').unwrap_or(line).as_bytes();
+        let mut length = 0;
+        for (j, byte) in line.iter().copied().enumerate() {
+            let encoded = match byte {
+                b' ' | b'\t' if j + 1 < line.len() => (byte as char).to_string(),
+                33..=60 | 62..=126 => (byte as char).to_string(),
+                _ => format!("={:02X}", byte),
+            };
+            // keep room for the `=` of the soft line break
+            if length + encoded.len() >= MAX_BODY_LINE {
+                result.push_str("=\r\n");
+                length = 0;
+            }
+            result.push_str(&encoded);
+            length += encoded.len();
+        }
+    }
+    result
+}
+
+
+/// Return boundary derived from the members, so the same outputs give the same message.
+/// The `=_` sequence never occurs in base64 encoded bodies, so the boundary cannot collide with them.
+fn boundary(members: &HashMap<String, String>) -> String {
+    let mut hasher = Fingerprint::default();
+    let mut keys: Vec<_> = members.keys().collect();
+    keys.sort();
+    for key in keys {
+        hasher.write_str(key);
+        hasher.write_str(&members[key]);
+    }
+    format!("=_terarium_{:016x}", hasher.finish())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multipart() {
+        let members = HashMap::from([
+            ("subject".to_owned(), "Pozdrav z Česka".to_owned()),
+            ("text".to_owned(), "Ahoj".to_owned()),
+            ("html".to_owned(), "<p>Ahoj</p>".to_owned()),
+        ]);
+        let message = to_mime_multipart(&members).unwrap();
+        let boundary = boundary(&members);
+        let expected = format!(
+            "MIME-Version: 1.0\r\nSubject: =?UTF-8?B?UG96ZHJhdiB6IMSMZXNrYQ==?=\r\n\
+            Content-Type: multipart/alternative; boundary=\"{0}\"\r\n\r\n\
+            --{0}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\nQWhvag==\r\n\
+            --{0}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n\
+            PHA+QWhvajwvcD4=\r\n--{0}--\r\n",
+            boundary,
+        );
+        assert_eq!(message, expected);
+        assert_eq!(to_mime_multipart(&members).unwrap(), message);
+    }
+
+    #[test]
+    fn long_body_and_missing_members() {
+        let members = HashMap::from([("html".to_owned(), "x".repeat(100))]);
+        let message = to_mime_multipart(&members).unwrap();
+        assert!(!message.contains("Subject:"));
+        assert!(message.lines().all(|line| line.len() <= MAX_BODY_LINE));
+
+        let members = HashMap::from([("subject".to_owned(), "Hi".to_owned())]);
+        assert!(matches!(to_mime_multipart(&members), Err(TerariumError::MemberNotFound(m)) if m == "text"));
+    }
+
+    #[test]
+    fn transfer_encoding() {
+        assert_eq!(TransferEncoding::Base64.encode("Ahoj"), "QWhvag==");
+        assert!(TransferEncoding::Base64.encode(&"x".repeat(100)).split("\r\n").all(|l| l.len() <= MAX_BODY_LINE));
+        let encoded = TransferEncoding::QuotedPrintable.encode("Dobrý den, a=b \nnový řádek\t");
+        assert_eq!(encoded, "Dobr=C3=BD den, a=3Db=20\r\nnov=C3=BD =C5=99=C3=A1dek=09");
+        let encoded = TransferEncoding::QuotedPrintable.encode(&"é".repeat(30));
+        assert!(encoded.split("\r\n").all(|line| line.len() <= MAX_BODY_LINE));
+        assert_eq!(encoded.replace("=\r\n", ""), "=C3=A9".repeat(30));
+    }
+}
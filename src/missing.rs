@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::error::Error as StdError;
+
+use tera::{Context, Error as TeraError, Map, Tera, Value};
+
+
+/// Maximal number of missing variables replaced by markers in one render.
+const MAX_MARKERS: usize = 256;
+
+
+/// Rendering of variables missing in the context.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingVariables {
+    /// Render fails as usual in `Tera`.
+    #[default]
+    Error,
+    /// Missing variables are rendered as visible markers (e.g. `⟦user.name⟧`), intended for QA environments.
+    Marker,
+}
+
+
+/// Render the content and replace missing variables by markers.
+pub(crate) fn render_with_markers(tera: &Tera, content_key: &str, context: &Context) -> Result<String, TeraError> {
+    let mut context = Cow::Borrowed(context);
+    let mut marked = HashSet::new();
+    loop {
+        let error = match tera.render(content_key, &context) {
+            Err(error) => error,
+            output => return output,
+        };
+        let variable = match missing_variable(&error) {
+            Some(variable) if marked.len() < MAX_MARKERS && !marked.contains(&variable) => variable,
+            _ => return Err(error),
+        };
+        if !insert_marker(context.to_mut(), &variable) {
+            return Err(error);
+        }
+        marked.insert(variable);
+    }
+}
+
+
+/// Return name of the missing variable causing the error.
+fn missing_variable(error: &TeraError) -> Option<String> {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(error);
+    while let Some(error) = source {
+        let message = error.to_string();
+        if let Some(rest) = message.strip_prefix("Variable `") {
+            return rest
+                .split_once("` not found in context")
+                .map(|(name, _)| name.to_owned())
+                .filter(|name| name.split('.').all(|part| !part.is_empty() && !part.contains(['[', ']'])));
+        }
+        source = error.source();
+    }
+    None
+}
+
+
+/// Insert marker of the variable into the context or return `false` when the path goes through non-object value.
+fn insert_marker(context: &mut Context, variable: &str) -> bool {
+    let marker = Value::String(format!("⟦{}⟧", variable));
+    let (root, path) = match variable.split_once('.') {
+        Some((root, path)) => (root, path),
+        None => {
+            context.insert(variable, &marker);
+            return true;
+        }
+    };
+    let mut value = context.get(root).cloned().unwrap_or_else(|| Value::Object(Map::new()));
+    let mut current = &mut value;
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let Some(object) = current.as_object_mut() else { return false };
+        if parts.peek().is_none() {
+            object.insert(part.to_owned(), marker);
+            break;
+        }
+        current = object.entry(part).or_insert_with(|| Value::Object(Map::new()));
+    }
+    context.insert(root, &value);
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers() {
+        let mut tera = Tera::default();
+        tera.add_raw_template("tpl", "{{ name }} {{ user.address.city }} {{ user.name }}").unwrap();
+        let mut context = Context::new();
+        context.insert("user", &serde_json::json!({"name": "John"}));
+        let output = render_with_markers(&tera, "tpl", &context).unwrap();
+        assert_eq!(output, "⟦name⟧ ⟦user.address.city⟧ John");
+    }
+
+    #[test]
+    fn non_object_path() {
+        let mut tera = Tera::default();
+        tera.add_raw_template("tpl", "{{ user.name }}").unwrap();
+        let mut context = Context::new();
+        context.insert("user", "John");
+        assert!(render_with_markers(&tera, "tpl", &context).is_err());
+    }
+}
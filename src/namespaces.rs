@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{RenderOutcome, RenderRequest, Terarium, TerariumError};
+
+
+/// Source of catalogs loaded by the `NamespacedCatalog` (e.g. templates of a tenant loaded from a database).
+pub trait CatalogSource: Send + Sync {
+    /// Build the catalog of the namespace or return description of the failure.
+    fn load(&self, namespace: &str) -> Result<Terarium, String>;
+}
+
+
+impl<F> CatalogSource for F
+    where
+        F: Fn(&str) -> Result<Terarium, String> + Send + Sync,
+{
+    fn load(&self, namespace: &str) -> Result<Terarium, String> {
+        self(namespace)
+    }
+}
+
+
+/// Catalogs of namespaces (e.g. tenants) loaded from the `CatalogSource` on first use.
+/// At most `capacity` catalogs are kept loaded, the least recently used one is evicted when another is loaded.
+pub struct NamespacedCatalog {
+    source: Arc<dyn CatalogSource>,
+    capacity: usize,
+    loaded: Mutex<HashMap<String, LoadedCatalog>>,
+}
+
+
+struct LoadedCatalog {
+    catalog: Arc<Terarium>,
+    last_used: Instant,
+}
+
+
+impl NamespacedCatalog {
+    /// Create catalog keeping at most `capacity` namespaces loaded.
+    pub fn new(source: impl CatalogSource + 'static, capacity: usize) -> Self {
+        Self {
+            source: Arc::new(source),
+            capacity: capacity.max(1),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the catalog of the namespace, load it when it is not loaded yet.
+    pub fn get(&self, namespace: &str) -> Result<Arc<Terarium>, TerariumError> {
+        if let Some(loaded) = self.lock().get_mut(namespace) {
+            loaded.last_used = Instant::now();
+            return Ok(loaded.catalog.clone());
+        }
+        let catalog = self.source
+            .load(namespace)
+            .map_err(|message| TerariumError::NamespaceUnavailable(namespace.to_owned(), message))?;
+
+        let mut loaded = self.lock();
+        if !loaded.contains_key(namespace) && loaded.len() >= self.capacity {
+            let idle = loaded.iter().min_by_key(|(_, l)| l.last_used).map(|(key, _)| key.clone());
+            if let Some(idle) = idle {
+                loaded.remove(&idle);
+            }
+        }
+        let loaded = loaded
+            .entry(namespace.to_owned())
+            .or_insert_with(|| LoadedCatalog { catalog: Arc::new(catalog), last_used: Instant::now() });
+        Ok(loaded.catalog.clone())
+    }
+
+    /// Render the request by the catalog of its namespace (see `RenderRequest::with_namespace()`).
+    /// Requests without namespace are rendered by the catalog of the empty namespace.
+    pub fn render(&self, request: &RenderRequest) -> Result<RenderOutcome, TerariumError> {
+        self.get(request.namespace().unwrap_or_default())?.render(request)
+    }
+
+    /// Return sorted namespaces which catalogs are loaded.
+    pub fn loaded(&self) -> Vec<String> {
+        let mut namespaces: Vec<_> = self.lock().keys().cloned().collect();
+        namespaces.sort();
+        namespaces
+    }
+
+    /// Unload the catalog of the namespace, it is loaded again on next use.
+    /// Return `false` when the catalog was not loaded.
+    pub fn evict(&self, namespace: &str) -> bool {
+        self.lock().remove(namespace).is_some()
+    }
+
+    /// Unload catalogs not used for longer than `max_idle` and return their number.
+    pub fn evict_idle(&self, max_idle: Duration) -> usize {
+        let mut loaded = self.lock();
+        let before = loaded.len();
+        loaded.retain(|_, l| l.last_used.elapsed() <= max_idle);
+        before - loaded.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, LoadedCatalog>> {
+        self.loaded.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tera::Context;
+
+    use crate::{Content, RenderOutput, Template, TerariumBuilder};
+
+    use super::*;
+
+    fn catalog(namespace: &str) -> Result<Terarium, String> {
+        if namespace == "unknown" {
+            return Err("No such tenant".to_owned());
+        }
+        let mut builder = TerariumBuilder::default();
+        let template = Template::new(vec![Content::new(namespace.to_owned(), vec!["en".to_owned()])]).unwrap();
+        builder.add_template("greeting".to_owned(), template).unwrap();
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn load_lazily() {
+        let loads = Arc::new(AtomicUsize::new(0));
+        let counter = loads.clone();
+        let catalogs = NamespacedCatalog::new(move |namespace: &str| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            catalog(namespace)
+        }, 2);
+        assert!(catalogs.loaded().is_empty());
+
+        let request = RenderRequest::template("greeting".to_owned(), Context::new())
+            .with_language("en".to_owned())
+            .with_namespace("a".to_owned());
+        let outcome = catalogs.render(&request).unwrap();
+        assert!(matches!(outcome.output, RenderOutput::Template(output) if output == "a"));
+        catalogs.get("a").unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        assert!(matches!(catalogs.get("unknown"), Err(TerariumError::NamespaceUnavailable(n, _)) if n == "unknown"));
+        assert!(catalogs.evict("a"));
+        assert!(!catalogs.evict("a"));
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let catalogs = NamespacedCatalog::new(catalog, 2);
+        catalogs.get("a").unwrap();
+        catalogs.get("b").unwrap();
+        catalogs.get("a").unwrap();
+        catalogs.get("c").unwrap();
+        assert_eq!(catalogs.loaded(), vec!["a".to_owned(), "c".to_owned()]);
+
+        assert_eq!(catalogs.evict_idle(Duration::from_secs(60)), 0);
+        assert_eq!(catalogs.evict_idle(Duration::ZERO), 2);
+        assert!(catalogs.loaded().is_empty());
+    }
+}
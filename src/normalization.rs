@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use unicode_normalization::UnicodeNormalization;
+
+
+/// Zero-width characters removed from rendered outputs.
+/// Joiners (U+200C and U+200D) are kept, emoji sequences and scripts like Persian or Devanagari need them.
+const ZERO_WIDTH_CHARS: [char; 3] = ['\u{200b}', '\u{2060}', '\u{feff}'];
+
+/// Prefix of the RFC 2047 encoded word.
+const ENCODED_WORD_PREFIX: &str = "=?UTF-8?B?";
+
+/// Suffix of the RFC 2047 encoded word.
+const ENCODED_WORD_SUFFIX: &str = "?=";
+
+/// Maximal number of bytes encoded into one encoded word, so the word is not longer than 75 characters.
+const ENCODED_WORD_BYTES: usize = 45;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+/// Post-processing of rendered outputs making them safe for e-mail headers.
+/// Set by the `TerariumBuilder::set_output_normalization()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputNormalization {
+    nfc: bool,
+    strip_zero_width: bool,
+    encoded_members: HashSet<String>,
+}
+
+
+impl OutputNormalization {
+    /// Create normalization converting outputs into NFC and stripping zero-width characters
+    /// (zero-width space, word joiner and BOM).
+    pub fn new() -> Self {
+        Self {
+            nfc: true,
+            strip_zero_width: true,
+            encoded_members: HashSet::new(),
+        }
+    }
+
+    /// Enable or disable conversion into the Unicode Normalization Form C.
+    pub fn nfc(mut self, enabled: bool) -> Self {
+        self.nfc = enabled;
+        self
+    }
+
+    /// Enable or disable stripping of zero-width characters.
+    pub fn strip_zero_width(mut self, enabled: bool) -> Self {
+        self.strip_zero_width = enabled;
+        self
+    }
+
+    /// Encode non-ASCII output of the group member (e.g. `subject`) as RFC 2047 encoded words.
+    /// Applied to every rendered group, including async renders, but not to the `Terarium::render_group_concat()`
+    /// which joins outputs of member templates into one text.
+    pub fn encode_member(mut self, member_key: String) -> Self {
+        self.encoded_members.insert(member_key);
+        self
+    }
+
+    /// Apply normalization to the rendered output.
+    pub(crate) fn normalize(&self, output: String) -> String {
+        let output = match self.strip_zero_width {
+            true if output.contains(ZERO_WIDTH_CHARS) => output.replace(ZERO_WIDTH_CHARS, ""),
+            _ => output,
+        };
+        match self.nfc {
+            true => output.nfc().collect(),
+            false => output,
+        }
+    }
+
+    /// Apply header encoding to the rendered group member.
+    pub(crate) fn encode(&self, member_key: &str, output: String) -> String {
+        match self.encoded_members.contains(member_key) {
+            true => encode_header(&output),
+            false => output,
+        }
+    }
+}
+
+
+/// Encode header value as RFC 2047 encoded words when it contains non-ASCII characters.
+/// Runs of whitespace (including line breaks) are collapsed into single space.
+pub fn encode_header(value: &str) -> String {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if value.is_ascii() {
+        return value;
+    }
+    let mut words = Vec::new();
+    let mut chunk_start = 0;
+    for (i, c) in value.char_indices() {
+        if i + c.len_utf8() - chunk_start > ENCODED_WORD_BYTES {
+            words.push(encoded_word(&value[chunk_start..i]));
+            chunk_start = i;
+        }
+    }
+    words.push(encoded_word(&value[chunk_start..]));
+    words.join(" ")
+}
+
+
+fn encoded_word(text: &str) -> String {
+    format!("{}{}{}", ENCODED_WORD_PREFIX, encode_base64(text.as_bytes()), ENCODED_WORD_SUFFIX)
+}
+
+
+/// Encode the bytes by the standard base64 alphabet with padding.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => result.push(BASE64_ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => result.push('='),
+            }
+        }
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let normalization = OutputNormalization::new();
+        assert_eq!(normalization.normalize("Zdrav\u{ed}m\u{200b}".to_owned()), "Zdravím");
+        assert_eq!(normalization.normalize("Zdravi\u{301}m".to_owned()), "Zdrav\u{ed}m");
+        let joined = "\u{1f469}\u{200d}\u{1f4bb} \u{645}\u{6cc}\u{200c}\u{62e}\u{648}\u{627}\u{647}\u{645}";
+        assert_eq!(normalization.normalize(joined.to_owned()), joined);
+        let normalization = OutputNormalization::new().nfc(false).strip_zero_width(false);
+        assert_eq!(normalization.normalize("i\u{301}\u{200b}".to_owned()), "i\u{301}\u{200b}");
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(encode_header(" Hello\n world "), "Hello world");
+        assert_eq!(encode_header("Příliš"), "=?UTF-8?B?UMWZw61sacWh?=");
+        assert_eq!(encoded_word("ab"), "=?UTF-8?B?YWI=?=");
+
+        let encoded = encode_header(&"žluťoučký kůň ".repeat(10));
+        assert!(encoded.split(' ').all(|word| word.len() <= 75 && word.ends_with("?=")));
+    }
+}
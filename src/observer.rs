@@ -0,0 +1,49 @@
+use crate::{AuditEntry, Deprecation, LeftoverPlaceholder, MissingLocalization, TerariumError};
+
+
+/// Key of the deprecated item used for rendering.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeprecatedKey {
+    /// Key of the deprecated template.
+    Template(String),
+    /// Key of the deprecated group.
+    Group(String),
+}
+
+
+/// Failed render of a template content.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RenderFailure<'a> {
+    /// Key of the template.
+    pub template_key: &'a str,
+    /// Language of the content.
+    pub language: &'a str,
+    /// Owner of the template (see `Template::set_owner()`).
+    pub owner: Option<&'a str>,
+    /// Error of the render.
+    /// Its messages may contain values of the context, report the `message` outside of the process instead.
+    pub error: &'a TerariumError,
+    /// Messages of the error and its sources with sensitive values of the context (see `Sensitive`) masked.
+    pub message: String,
+}
+
+
+/// Receive notifications about notable events during rendering.
+/// All methods have empty default implementation, so implement only those you are interested in.
+pub trait RenderObserver: Send + Sync {
+    /// Called every time deprecated template or group is rendered.
+    fn deprecated_usage(&self, _key: &DeprecatedKey, _deprecation: &Deprecation) {}
+
+    /// Called after every runtime mutation of the catalog.
+    fn catalog_mutated(&self, _entry: &AuditEntry) {}
+
+    /// Called every time content of a template fails to render.
+    fn render_failed(&self, _failure: &RenderFailure) {}
+
+    /// Called when template syntax is found in a rendered output (see `PlaceholderCheck::Warn`).
+    fn leftover_placeholder(&self, _leftover: &LeftoverPlaceholder) {}
+
+    /// Called when template has no content in a required language (see `LocalizationEnforcement::Warn`).
+    fn missing_localization(&self, _missing: &MissingLocalization) {}
+}
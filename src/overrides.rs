@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::time::SystemTime;
+
+
+/// Rule replacing content of a template by its variant in a date range and languages
+/// (e.g. Czech-only holiday banner in December).
+/// The variant content is added to the template as usual, see `Content::with_variant()`.
+/// Variants requested explicitly take precedence over overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverrideRule {
+    /// Identifier of the rule reported in the `RenderOutcome::overrides`.
+    pub id: String,
+    /// Variant of the template content rendered when the rule applies.
+    pub variant: String,
+    /// Languages the rule applies to, all languages when empty.
+    pub languages: Vec<String>,
+    /// Start of the range, the range is open to the past when not set.
+    pub valid_from: Option<SystemTime>,
+    /// End of the range (exclusive), the range is open to the future when not set.
+    pub valid_until: Option<SystemTime>,
+}
+
+
+impl OverrideRule {
+    /// Create rule applying the variant in all languages all the time.
+    pub fn new(id: String, variant: String) -> Self {
+        Self {
+            id,
+            variant,
+            languages: Vec::new(),
+            valid_from: None,
+            valid_until: None,
+        }
+    }
+
+    /// Restrict the rule to the language.
+    pub fn language(mut self, language: String) -> Self {
+        self.languages.push(language);
+        self
+    }
+
+    /// Set start of the range.
+    pub fn from(mut self, valid_from: SystemTime) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    /// Set end of the range.
+    pub fn until(mut self, valid_until: SystemTime) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    /// Return `true` when the rule applies to the language at the time.
+    pub fn applies(&self, language: &str, time: SystemTime) -> bool {
+        (self.languages.is_empty() || self.languages.iter().any(|l| l == language))
+            && self.valid_from.map_or(true, |from| from <= time)
+            && self.valid_until.map_or(true, |until| time < until)
+    }
+}
+
+
+thread_local! {
+    static APPLIED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+
+/// Call the `f` and return its result with ids of override rules applied by it.
+pub(crate) fn collect_overrides<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let previous = APPLIED.with(|applied| applied.replace(Some(Vec::new())));
+    let result = f();
+    let applied = APPLIED.with(|applied| applied.replace(previous)).unwrap_or_default();
+    (result, applied)
+}
+
+
+/// Record the rule applied in the `collect_overrides()`.
+pub(crate) fn record_override(id: &str) {
+    APPLIED.with(|applied| {
+        if let Some(applied) = applied.borrow_mut().as_mut() {
+            applied.push(id.to_owned());
+        }
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn applies() {
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let rule = OverrideRule::new("xmas".to_owned(), "xmas".to_owned())
+            .language("cs".to_owned())
+            .from(time(10))
+            .until(time(20));
+        assert!(rule.applies("cs", time(10)));
+        assert!(!rule.applies("en", time(10)));
+        assert!(!rule.applies("cs", time(20)));
+        assert!(OverrideRule::new("a".to_owned(), "a".to_owned()).applies("en", time(0)));
+    }
+
+    #[test]
+    fn collect() {
+        let (_, applied) = collect_overrides(|| {
+            record_override("a");
+            record_override("b");
+        });
+        assert_eq!(applied, vec!["a", "b"]);
+    }
+}
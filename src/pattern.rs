@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter, Write};
+
+
+/// Pattern matching template keys.
+/// Pattern containing `*` (any sequence of characters) or `?` (exactly one character) is a glob pattern matching
+/// whole key. Any other pattern is a prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyPattern {
+    pattern: Vec<char>,
+    glob: bool,
+}
+
+
+impl KeyPattern {
+    /// Create new pattern.
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.chars().collect(),
+            glob: pattern.contains(['*', '?']),
+        }
+    }
+
+    /// Return `true` if the `key` matches the pattern.
+    pub fn matches(&self, key: &str) -> bool {
+        let key: Vec<char> = key.chars().collect();
+        if self.glob {
+            glob_matches(&self.pattern, &key)
+        } else {
+            key.starts_with(&self.pattern)
+        }
+    }
+}
+
+
+impl Display for KeyPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.pattern.iter().try_for_each(|c| f.write_char(*c))
+    }
+}
+
+
+impl From<&str> for KeyPattern {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+
+fn glob_matches(pattern: &[char], key: &[char]) -> bool {
+    let (mut p, mut k) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, k));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_k)) => {
+                    p = star_p + 1;
+                    k = star_k + 1;
+                    backtrack = Some((star_p, star_k + 1));
+                }
+                None => return false,
+            }
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix() {
+        let pattern = KeyPattern::new("emails.digest.");
+        assert!(pattern.matches("emails.digest.header"));
+        assert!(!pattern.matches("emails.welcome"));
+    }
+
+    #[test]
+    fn glob() {
+        let pattern = KeyPattern::new("emails.*.header");
+        assert!(pattern.matches("emails.digest.header"));
+        assert!(pattern.matches("emails.a.b.header"));
+        assert!(!pattern.matches("emails.digest.footer"));
+        assert!(KeyPattern::new("sections.?").matches("sections.1"));
+        assert!(!KeyPattern::new("sections.?").matches("sections.10"));
+        assert!(KeyPattern::new("*").matches(""));
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use thiserror::Error;
+
+
+/// Converts rendered HTML into PDF document.
+pub trait PdfBackend: Send + Sync {
+    /// Convert the `html` into PDF and return its bytes.
+    fn convert(&self, html: &str) -> Result<Vec<u8>, PdfError>;
+}
+
+
+/// Backend running external command which reads HTML from stdin and writes PDF to stdout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandPdfBackend {
+    program: String,
+    args: Vec<String>,
+}
+
+
+impl CommandPdfBackend {
+    /// Create backend running the `program` with the `args`.
+    pub fn new(program: String, args: Vec<String>) -> Self {
+        Self { program, args }
+    }
+
+    /// Create backend running the `weasyprint` tool.
+    pub fn weasyprint() -> Self {
+        Self::new("weasyprint".to_owned(), vec!["-".to_owned(), "-".to_owned()])
+    }
+
+    /// Create backend running the `wkhtmltopdf` tool.
+    pub fn wkhtmltopdf() -> Self {
+        Self::new("wkhtmltopdf".to_owned(), vec!["--quiet".to_owned(), "-".to_owned(), "-".to_owned()])
+    }
+}
+
+
+impl PdfBackend for CommandPdfBackend {
+    fn convert(&self, html: &str) -> Result<Vec<u8>, PdfError> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        // stdin is written by another thread, so the backend never blocks on full stdout or stderr pipe
+        let (written, output) = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || match stdin {
+                Some(mut stdin) => stdin.write_all(html.as_bytes()),
+                None => Ok(()),
+            });
+            let output = child.wait_with_output();
+            (writer.join().unwrap_or(Ok(())), output)
+        });
+        let output = output?;
+        // backend which exits early closes its stdin, so its exit status is more relevant than the write error
+        if !output.status.success() {
+            return Err(PdfError::BackendFailed(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+        written?;
+        Ok(output.stdout)
+    }
+}
+
+
+/// Errors returned by PDF backends.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PdfError {
+    /// Cannot communicate with the backend.
+    #[error("Cannot run PDF backend")]
+    Io(#[from] std::io::Error),
+    /// Backend reported failure.
+    #[error("PDF backend failed: {0}")]
+    BackendFailed(String),
+}
+
+
+impl PdfError {
+    /// Return stable code of the error for machine handling.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "PDF_BACKEND_UNAVAILABLE",
+            Self::BackendFailed(_) => "PDF_BACKEND_FAILED",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn command_backend() {
+        let backend = CommandPdfBackend::new("cat".to_owned(), vec![]);
+        assert_eq!(backend.convert("<p>foo</p>").unwrap(), b"<p>foo</p>".to_vec());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn command_backend_with_large_document() {
+        let backend = CommandPdfBackend::new("cat".to_owned(), vec![]);
+        let html = "<p>foo</p>".repeat(100_000);
+        assert_eq!(backend.convert(&html).unwrap().len(), html.len());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_command_backend() {
+        let backend = CommandPdfBackend::new("false".to_owned(), vec![]);
+        assert!(matches!(backend.convert("<p>foo</p>"), Err(PdfError::BackendFailed(_))));
+    }
+}
@@ -0,0 +1,55 @@
+/// Sequences of template syntax which should never appear in rendered outputs.
+const PLACEHOLDER_SEQUENCES: [&str; 3] = ["{{", "{%", "{#"];
+
+
+/// Handling of template syntax (`{{`, `{%` or `{#`) left in rendered outputs.
+/// Such leftovers usually come from escaped placeholders or pre-processing mistakes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaceholderCheck {
+    /// Outputs are not scanned.
+    #[default]
+    Off,
+    /// Leftovers are reported to the `RenderObserver::leftover_placeholder()`.
+    Warn,
+    /// Render fails with the `TerariumError::LeftoverPlaceholder`.
+    Reject,
+}
+
+
+/// Template syntax found in a rendered output.
+#[derive(Debug)]
+pub struct LeftoverPlaceholder<'a> {
+    /// Key of the template.
+    pub template_key: &'a str,
+    /// Language of the content.
+    pub language: &'a str,
+    /// Line of the output (starting by 1).
+    pub line: usize,
+    /// The rest of the line starting by the leftover.
+    pub snippet: &'a str,
+}
+
+
+/// Return line and the rest of the line of the first leftover in the `output`.
+pub(crate) fn find_leftover(output: &str) -> Option<(usize, &str)> {
+    output.lines().enumerate().find_map(|(index, line)| {
+        PLACEHOLDER_SEQUENCES
+            .iter()
+            .filter_map(|sequence| line.find(sequence))
+            .min()
+            .map(|start| (index + 1, &line[start..]))
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find() {
+        assert_eq!(find_leftover("Hello {name}\n{ {x} }"), None);
+        assert_eq!(find_leftover("Hello\nDear {{ name }}, {% if %}"), Some((2, "{{ name }}, {% if %}")));
+        assert_eq!(find_leftover("{# note #}"), Some((1, "{# note #}")));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use crate::GroupHook;
+
+
+/// Default maximal length of the preheader in characters.
+const DEFAULT_MAX_LENGTH: usize = 100;
+
+
+/// Group hook providing the preheader of emails (the text shown after the subject in inbox lists).
+/// The preheader member rendered from a template is kept, otherwise it is derived from the source member (plain text
+/// body by default). Whitespaces are collapsed and the preheader is truncated at word boundary to the maximal length.
+/// Groups without both members are left untouched.
+/// Register it by the `TerariumBuilder::add_group_hook()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preheader {
+    member_key: String,
+    source_member_key: String,
+    min_length: usize,
+    max_length: usize,
+}
+
+
+impl Default for Preheader {
+    fn default() -> Self {
+        Self {
+            member_key: "preheader".to_owned(),
+            source_member_key: "text".to_owned(),
+            min_length: 0,
+            max_length: DEFAULT_MAX_LENGTH,
+        }
+    }
+}
+
+
+impl Preheader {
+    /// Create hook deriving `preheader` member from `text` member.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set key of the preheader member.
+    pub fn member(mut self, member_key: String) -> Self {
+        self.member_key = member_key;
+        self
+    }
+
+    /// Set key of the member the preheader is derived from.
+    pub fn source(mut self, source_member_key: String) -> Self {
+        self.source_member_key = source_member_key;
+        self
+    }
+
+    /// Reject groups with shorter preheader (in characters).
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Set maximal length of the preheader in characters, the ellipsis included.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Collapse whitespaces of the `text` and truncate it to the maximal length.
+    pub fn shorten(&self, text: &str) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() <= self.max_length {
+            return text;
+        }
+        let limit = self.max_length.saturating_sub(1);
+        // the character following the limit is searched too, so a word ending at the limit is kept
+        let (end, next) = text
+            .char_indices()
+            .nth(limit)
+            .map(|(i, c)| (i, i + c.len_utf8()))
+            .unwrap_or((text.len(), text.len()));
+        let cut = match text[..next].rfind(' ') {
+            Some(space) if space > 0 => &text[..space],
+            _ => &text[..end],
+        };
+        format!("{}…", cut.trim_end())
+    }
+}
+
+
+impl GroupHook for Preheader {
+    fn after_render(&self, _group_key: &str, members: &mut HashMap<String, String>) -> Result<(), String> {
+        let preheader = match members.get(&self.member_key).or_else(|| members.get(&self.source_member_key)) {
+            Some(text) => self.shorten(text),
+            None => return Ok(()),
+        };
+        if preheader.chars().count() < self.min_length {
+            return Err(format!("Preheader is shorter than {} characters", self.min_length));
+        }
+        members.insert(self.member_key.clone(), preheader);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorten() {
+        let preheader = Preheader::new().max_length(12);
+        assert_eq!(preheader.shorten("  Your\norder   shipped "), "Your order…");
+        assert_eq!(preheader.shorten("Short text"), "Short text");
+        assert_eq!(preheader.shorten("Supercalifragilistic"), "Supercalifr…");
+        assert_eq!(preheader.shorten("Žluťoučký kůň úpěl"), "Žluťoučký…");
+        assert_eq!(Preheader::new().max_length(5).shorten("abcdéfgh"), "abcd…");
+    }
+
+    #[test]
+    fn derive() {
+        let preheader = Preheader::new().min_length(5);
+        let mut members = HashMap::from([("text".to_owned(), "Hello  world".to_owned())]);
+        preheader.after_render("mail", &mut members).unwrap();
+        assert_eq!(members["preheader"], "Hello world");
+
+        members.insert("preheader".to_owned(), "Hi".to_owned());
+        assert!(preheader.after_render("mail", &mut members).is_err());
+
+        let mut members = HashMap::from([("html".to_owned(), "<p>x</p>".to_owned())]);
+        preheader.after_render("mail", &mut members).unwrap();
+        assert!(!members.contains_key("preheader"));
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{format_content, translate_dialect, translate_partials, SourceDialect, Template, UntranslatedConstruct};
+
+
+/// Translations of content sources applied before the sources are parsed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Preprocessing {
+    /// Syntax of sources translated into Tera syntax.
+    pub(crate) dialect: SourceDialect,
+    /// Handlebars partial calls are translated into Tera includes.
+    pub(crate) handlebars_partials: bool,
+    /// Sources are normalized by the `format_content()` after translations.
+    pub(crate) format: bool,
+}
+
+
+impl Preprocessing {
+    /// Translate sources of all contents of the template and return constructs which cannot be translated.
+    pub(crate) fn apply(&self, template: &mut Template) -> Vec<UntranslatedConstruct> {
+        let mut untranslated = Vec::new();
+        if self.dialect != SourceDialect::Tera {
+            template.update_contents(|content| {
+                let (source, constructs) = translate_dialect(&content.content, self.dialect);
+                content.content = source;
+                untranslated.extend(constructs);
+            });
+        }
+        if self.handlebars_partials {
+            template.update_contents(|content| content.content = translate_partials(&content.content));
+        }
+        if self.format {
+            template.update_contents(|content| content.content = format_content(&content.content));
+        }
+        untranslated
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{MissingVariables, PlaceholderCheck};
+
+
+/// Environment the instance runs in, toggling groups of render behaviors by one switch.
+/// The profile is set by the `TerariumBuilder::set_profile()`, behaviors set explicitly after it take precedence.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Missing variables render as markers, leftover template syntax is reported and caching is disabled.
+    Development,
+    /// Missing variables and leftover template syntax fail the render.
+    Staging,
+    /// Missing variables fail the render as usual in `Tera`.
+    #[default]
+    Production,
+}
+
+
+impl Profile {
+    /// Return name of the profile.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Staging => "staging",
+            Self::Production => "production",
+        }
+    }
+
+    /// Return `true` for the production profile.
+    pub fn is_production(&self) -> bool {
+        *self == Self::Production
+    }
+
+    /// Return rendering of missing variables used in the profile.
+    pub fn missing_variables(&self) -> MissingVariables {
+        match self {
+            Self::Development => MissingVariables::Marker,
+            Self::Staging | Self::Production => MissingVariables::Error,
+        }
+    }
+
+    /// Return handling of leftover template syntax used in the profile.
+    pub fn placeholder_check(&self) -> PlaceholderCheck {
+        match self {
+            Self::Development => PlaceholderCheck::Warn,
+            Self::Staging => PlaceholderCheck::Reject,
+            Self::Production => PlaceholderCheck::Off,
+        }
+    }
+
+    /// Return `true` when outputs of templates with cache policy are cached in the profile.
+    pub fn caching(&self) -> bool {
+        *self != Self::Development
+    }
+}
+
+
+/// Insert the `banner` after the opening `<body>` tag of the `html` or at its beginning when there is no body.
+pub(crate) fn inject_watermark(html: &str, banner: &str) -> String {
+    let body_end = html
+        .to_ascii_lowercase()
+        .find("<body")
+        .and_then(|start| html[start..].find('>').map(|end| start + end + 1))
+        .unwrap_or(0);
+    let mut result = String::with_capacity(html.len() + banner.len());
+    result.push_str(&html[..body_end]);
+    result.push_str(banner);
+    result.push_str(&html[body_end..]);
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watermark() {
+        let html = "<html><BODY class=\"x\"><p>Hi</p></BODY></html>";
+        let expected = "<html><BODY class=\"x\"><!-- TEST --><p>Hi</p></BODY></html>";
+        assert_eq!(inject_watermark(html, "<!-- TEST -->"), expected);
+        assert_eq!(inject_watermark("<p>Hi</p>", "<b>TEST</b>"), "<b>TEST</b><p>Hi</p>");
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::RenderCost;
+
+
+/// Decides whether a namespace (e.g. tenant) may render.
+/// The policy is consulted by the `Terarium::render()` before each render and informed about the cost of each
+/// successful render. Requests without namespace use the empty namespace.
+pub trait QuotaPolicy: Send + Sync {
+    /// Return description of the exceeded limit when the namespace must not render now.
+    fn check(&self, namespace: &str) -> Result<(), String>;
+
+    /// Record cost of the successful render of the namespace.
+    fn record(&self, _namespace: &str, _cost: &RenderCost) {}
+}
+
+
+/// Quota limiting number of renders per minute and size of outputs per day of each namespace.
+/// Usage is counted in fixed windows starting by the first render in the window.
+/// Only successful renders count, so renders checked concurrently before any of them is recorded may exceed the limit.
+#[derive(Debug, Default)]
+pub struct RateQuota {
+    renders_per_minute: Option<u64>,
+    bytes_per_day: Option<u64>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+
+#[derive(Debug, Default)]
+struct Usage {
+    renders: Window,
+    bytes: Window,
+}
+
+
+#[derive(Debug, Default)]
+struct Window {
+    start: Option<Instant>,
+    used: u64,
+}
+
+
+impl Window {
+    /// Return usage in the current window of the `length`, the window is reset when it has passed.
+    fn current(&mut self, length: Duration) -> &mut u64 {
+        let now = Instant::now();
+        if self.start.map_or(true, |start| now.duration_since(start) >= length) {
+            self.start = Some(now);
+            self.used = 0;
+        }
+        &mut self.used
+    }
+}
+
+
+impl RateQuota {
+    /// Create quota without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit number of renders per minute.
+    pub fn renders_per_minute(mut self, limit: u64) -> Self {
+        self.renders_per_minute = Some(limit);
+        self
+    }
+
+    /// Limit total size of outputs in bytes per day.
+    pub fn bytes_per_day(mut self, limit: u64) -> Self {
+        self.bytes_per_day = Some(limit);
+        self
+    }
+}
+
+
+impl QuotaPolicy for RateQuota {
+    fn check(&self, namespace: &str) -> Result<(), String> {
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let usage = usage.entry(namespace.to_owned()).or_default();
+        if let Some(limit) = self.bytes_per_day {
+            if *usage.bytes.current(Duration::from_secs(86400)) >= limit {
+                return Err(format!("{} bytes per day", limit));
+            }
+        }
+        if let Some(limit) = self.renders_per_minute {
+            if *usage.renders.current(Duration::from_secs(60)) >= limit {
+                return Err(format!("{} renders per minute", limit));
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, namespace: &str, cost: &RenderCost) {
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let usage = usage.entry(namespace.to_owned()).or_default();
+        *usage.renders.current(Duration::from_secs(60)) += 1;
+        *usage.bytes.current(Duration::from_secs(86400)) += cost.output_bytes as u64;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_per_minute() {
+        let quota = RateQuota::new().renders_per_minute(2);
+        for _ in 0..2 {
+            assert!(quota.check("a").is_ok());
+            quota.record("a", &RenderCost::default());
+        }
+        assert_eq!(quota.check("a").unwrap_err(), "2 renders per minute");
+        assert!(quota.check("b").is_ok());
+    }
+
+    #[test]
+    fn failed_renders_are_not_counted() {
+        let quota = RateQuota::new().renders_per_minute(1);
+        assert!(quota.check("a").is_ok());
+        assert!(quota.check("a").is_ok());
+        quota.record("a", &RenderCost::default());
+        assert!(quota.check("a").is_err());
+    }
+
+    #[test]
+    fn poisoned_usage() {
+        let quota = RateQuota::new().renders_per_minute(1);
+        quota.record("a", &RenderCost::default());
+        let _ = std::thread::scope(|scope| scope.spawn(|| {
+            let _usage = quota.usage.lock().unwrap();
+            panic!("poison");
+        }).join());
+        assert!(quota.usage.is_poisoned());
+        assert!(quota.check("a").is_err());
+    }
+
+    #[test]
+    fn bytes_per_day() {
+        let quota = RateQuota::new().bytes_per_day(10);
+        assert!(quota.check("a").is_ok());
+        quota.record("a", &RenderCost { output_bytes: 10, ..RenderCost::default() });
+        assert_eq!(quota.check("a").unwrap_err(), "10 bytes per day");
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hasher;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use serde::{Serialize, Serializer};
+use tera::{Context, Value};
+
+use crate::fingerprint::Fingerprint;
+
+
+/// Text used instead of masked values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Maximal number of sensitive values remembered by the process, the oldest ones are forgotten first.
+const MAX_SENSITIVE_VALUES: usize = 65_536;
+
+
+/// Wrapper marking a context value as sensitive.
+/// The value is rendered as usual but it is masked everywhere the crate prints or exports it.
+/// The wrapper serializes into the plain value and remembers its fingerprint, so values inserted by the
+/// `Context::insert()` or nested in other values are masked too. Any context value equal to a sensitive value is
+/// masked. Only the last 65 536 distinct sensitive values are remembered by the process.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sensitive<T>(pub T);
+
+
+impl<T> Sensitive<T> {
+    /// Return the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+
+impl<T> Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sensitive({})", REDACTED)
+    }
+}
+
+
+impl<T> Display for Sensitive<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+
+impl<T: Serialize> Serialize for Sensitive<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Ok(value) = serde_json::to_value(&self.0) {
+            registry().lock().unwrap_or_else(PoisonError::into_inner).record(&value);
+        }
+        self.0.serialize(serializer)
+    }
+}
+
+
+/// Extension of the `Context` for working with sensitive values.
+pub trait SensitiveContext {
+    /// Insert the sensitive value into the context.
+    /// The value is available in templates under the `key` but it is masked in errors, logs and exports.
+    /// It is the same as the `Context::insert()` of the `Sensitive` value.
+    fn insert_sensitive<T: Serialize, S: Into<String>>(&mut self, key: S, value: &Sensitive<T>);
+
+    /// Return `true` if the value stored under the `key` is sensitive.
+    fn is_sensitive(&self, key: &str) -> bool;
+}
+
+
+impl SensitiveContext for Context {
+    fn insert_sensitive<T: Serialize, S: Into<String>>(&mut self, key: S, value: &Sensitive<T>) {
+        self.insert(key, value);
+    }
+
+    fn is_sensitive(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|value| registry().lock().unwrap_or_else(PoisonError::into_inner).contains(value))
+    }
+}
+
+
+/// Return copy of the context data with all sensitive values masked.
+/// Use it whenever context is written to logs, errors or exported outside of the process.
+pub fn redact_context(context: &Context) -> Value {
+    let mut value = context.clone().into_json();
+    let registry = registry().lock().unwrap_or_else(PoisonError::into_inner);
+    if !registry.is_empty() {
+        mask(&mut value, &registry);
+    }
+    value
+}
+
+
+/// Return copy of the context with all sensitive values replaced by the `REDACTED` text.
+pub(crate) fn mask_sensitive(context: &Context) -> Context {
+    Context::from_value(redact_context(context)).unwrap_or_default()
+}
+
+
+/// Return sensitive values of the context as they are rendered into text.
+pub(crate) fn sensitive_values(context: &Context) -> Vec<String> {
+    fn collect(value: &Value, registry: &Registry, values: &mut Vec<String>) {
+        if registry.contains(value) {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            if !text.is_empty() {
+                values.push(text);
+            }
+            return;
+        }
+        match value {
+            Value::Array(items) => items.iter().for_each(|item| collect(item, registry, values)),
+            Value::Object(map) => map.values().for_each(|item| collect(item, registry, values)),
+            _ => {}
+        }
+    }
+
+    let registry = registry().lock().unwrap_or_else(PoisonError::into_inner);
+    let mut values = Vec::new();
+    if !registry.is_empty() {
+        collect(&context.clone().into_json(), &registry, &mut values);
+    }
+    values
+}
+
+
+/// Replace occurrences of the sensitive `values` in the text by the `REDACTED` text.
+/// Only whole tokens are replaced, so short values do not mask parts of unrelated words or numbers.
+pub(crate) fn redact_text(text: &str, values: &[String]) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    values.iter().fold(text.to_owned(), |text, value| {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, _) in text.match_indices(value.as_str()) {
+            let end = start + value.len();
+            if start < last || is_word(text[..start].chars().next_back()) || is_word(text[end..].chars().next()) {
+                continue;
+            }
+            redacted.push_str(&text[last..start]);
+            redacted.push_str(REDACTED);
+            last = end;
+        }
+        redacted.push_str(&text[last..]);
+        redacted
+    })
+}
+
+
+/// Replace all sensitive values in the value by the `REDACTED` text.
+fn mask(value: &mut Value, registry: &Registry) {
+    if registry.contains(value) {
+        *value = Value::String(REDACTED.to_owned());
+        return;
+    }
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| mask(item, registry)),
+        Value::Object(map) => map.values_mut().for_each(|item| mask(item, registry)),
+        _ => {}
+    }
+}
+
+
+/// Fingerprints of serialized sensitive values, the values themselves are not kept.
+#[derive(Default)]
+struct Registry {
+    fingerprints: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+
+impl Registry {
+    fn record(&mut self, value: &Value) {
+        let fingerprint = fingerprint(value);
+        if !self.fingerprints.insert(fingerprint) {
+            return;
+        }
+        self.order.push_back(fingerprint);
+        if self.order.len() > MAX_SENSITIVE_VALUES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.fingerprints.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, value: &Value) -> bool {
+        !self.fingerprints.is_empty() && self.fingerprints.contains(&fingerprint(value))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fingerprints.is_empty()
+    }
+}
+
+
+fn fingerprint(value: &Value) -> u64 {
+    let mut hasher = Fingerprint::default();
+    hasher.write_str(&value.to_string());
+    hasher.finish()
+}
+
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Mutex::default)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tera::{Context, Tera};
+
+    use super::*;
+
+    #[test]
+    fn sensitive_value_is_rendered() {
+        let mut ctx = Context::new();
+        ctx.insert_sensitive("token", &Sensitive("secret-rendered"));
+        let result = Tera::one_off("token: {{token}}", &ctx, false).unwrap();
+        assert_eq!(result, "token: secret-rendered");
+    }
+
+    #[test]
+    fn sensitive_value_is_masked() {
+        let mut ctx = Context::new();
+        ctx.insert("name", "john");
+        ctx.insert_sensitive("token", &Sensitive("secret-masked"));
+
+        assert!(ctx.is_sensitive("token"));
+        assert!(!ctx.is_sensitive("name"));
+
+        let redacted = redact_context(&ctx);
+        assert_eq!(redacted["token"], REDACTED);
+        assert_eq!(redacted["name"], "john");
+        assert_eq!(redacted.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn inserted_and_nested_values_are_masked() {
+        let mut ctx = Context::new();
+        ctx.insert("password", &Sensitive("secret-inserted"));
+        ctx.insert("user", &HashMap::from([("name", Sensitive("secret-nested"))]));
+
+        assert!(ctx.is_sensitive("password"));
+        let redacted = redact_context(&ctx);
+        assert_eq!(redacted["password"], REDACTED);
+        assert_eq!(redacted["user"]["name"], REDACTED);
+        assert_eq!(mask_sensitive(&ctx).get("password").unwrap(), REDACTED);
+    }
+
+    #[test]
+    fn sensitive_values_are_masked_in_text() {
+        let mut ctx = Context::new();
+        ctx.insert("name", "john");
+        ctx.insert_sensitive("token", &Sensitive("secret-text"));
+        ctx.insert_sensitive("pin", &Sensitive(987_654));
+
+        let mut values = sensitive_values(&ctx);
+        values.sort();
+        assert_eq!(values, vec!["987654", "secret-text"]);
+        assert_eq!(
+            redact_text("token secret-text, pin 987654, 19876543, john", &values),
+            "token [REDACTED], pin [REDACTED], 19876543, john",
+        );
+        assert_eq!(redact_text("a1 1 (1) 10", &["1".to_owned()]), "a1 [REDACTED] ([REDACTED]) 10");
+    }
+
+    #[test]
+    fn sensitive_wrapper_is_not_printed() {
+        let value = Sensitive("secret");
+        assert_eq!(format!("{:?}", value), "Sensitive([REDACTED])");
+        assert_eq!(format!("{}", value), REDACTED);
+    }
+
+    #[test]
+    fn registry_is_bounded() {
+        let mut registry = Registry::default();
+        for value in 0..=MAX_SENSITIVE_VALUES {
+            registry.record(&Value::from(value));
+        }
+        assert_eq!(registry.fingerprints.len(), MAX_SENSITIVE_VALUES);
+        assert!(!registry.contains(&Value::from(0)));
+        assert!(registry.contains(&Value::from(MAX_SENSITIVE_VALUES)));
+    }
+}
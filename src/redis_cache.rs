@@ -0,0 +1,195 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use redis::{Client, Commands, Connection, ConnectionLike, RedisResult};
+
+use crate::{CachedOutput, RenderCache};
+
+
+/// Default timeout of connecting to the server and of its responses.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Maximal number of idle connections kept for reuse.
+const MAX_IDLE_CONNECTIONS: usize = 8;
+/// Add the key (`ARGV[1]`) into the tag set (`KEYS[1]`) and keep the set as long as the output stored for `ARGV[2]`
+/// seconds (`0` for ever), so sets of expired outputs expire as well.
+const TAG_SCRIPT: &str = "\
+local remaining = redis.call('TTL', KEYS[1])
+redis.call('SADD', KEYS[1], ARGV[1])
+local ttl = tonumber(ARGV[2])
+if ttl == 0 then
+    redis.call('PERSIST', KEYS[1])
+elseif remaining == -2 or (remaining >= 0 and remaining < ttl) then
+    redis.call('EXPIRE', KEYS[1], ttl)
+end";
+
+
+/// Cache storing rendered outputs in Redis, so they are shared by all processes using the same server.
+/// Outputs are stored as strings prefixed by the store time (milliseconds since the epoch and a new line) under
+/// their keys, tags are sets of keys stored under `<prefix>tag:<tag>` expiring with the last of their outputs.
+///
+/// Connections are reused by all clones of the cache, a new one is opened only when all idle ones are in use.
+/// Connecting and waiting for responses time out (see `with_timeout()`), so an unavailable server makes renders
+/// miss the cache instead of blocking them.
+#[derive(Clone)]
+pub struct RedisRenderCache {
+    client: Client,
+    prefix: String,
+    timeout: Duration,
+    idle: Arc<Mutex<Vec<Connection>>>,
+}
+
+
+impl RedisRenderCache {
+    /// Create cache connecting by the `client`.
+    pub fn new(client: Client) -> Self {
+        Self { client, prefix: "terarium:".to_owned(), timeout: DEFAULT_TIMEOUT, idle: Arc::default() }
+    }
+
+    /// Create cache connecting to the server by the URL (e.g. `redis://127.0.0.1/`).
+    pub fn open(url: &str) -> RedisResult<Self> {
+        Client::open(url).map(Self::new)
+    }
+
+    /// Set prefix of all keys stored by the cache, so more catalogs can share the same database.
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set timeout of connecting to the server and of its responses (1 second by default).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the `command` on an idle connection or a new one when there is none.
+    /// The connection is kept for reuse unless it failed on I/O.
+    fn with_connection<T>(&self, command: impl FnOnce(&mut Connection) -> RedisResult<T>) -> RedisResult<T> {
+        let idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner).pop();
+        let mut connection = match idle {
+            Some(connection) => connection,
+            None => self.connect()?,
+        };
+        let result = command(&mut connection);
+        if connection.is_open() && !result.as_ref().is_err_and(|error| error.is_io_error()) {
+            let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(connection);
+            }
+        }
+        result
+    }
+
+    fn connect(&self) -> RedisResult<Connection> {
+        let connection = self.client.get_connection_with_timeout(self.timeout)?;
+        connection.set_read_timeout(Some(self.timeout))?;
+        connection.set_write_timeout(Some(self.timeout))?;
+        Ok(connection)
+    }
+
+    fn output_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn tag_key(&self, tag: &str) -> String {
+        format!("{}tag:{}", self.prefix, tag)
+    }
+}
+
+
+impl Debug for RedisRenderCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisRenderCache")
+            .field("client", &self.client)
+            .field("prefix", &self.prefix)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+
+impl RenderCache for RedisRenderCache {
+    fn get(&self, key: &str) -> Option<CachedOutput> {
+        let value: Option<String> = self.with_connection(|c| c.get(self.output_key(key))).ok()?;
+        let value = value?;
+        let (stored_at, output) = value.split_once('\n')?;
+        let stored_at = UNIX_EPOCH + Duration::from_millis(stored_at.parse().ok()?);
+        Some(CachedOutput { output: output.to_owned(), stored_at })
+    }
+
+    fn put(&self, key: &str, output: &str, tags: &[String], ttl: Option<Duration>) {
+        let output_key = self.output_key(key);
+        let stored_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let value = format!("{}\n{}", stored_at, output);
+        let ttl = ttl.map(|ttl| ttl.as_secs().max(1));
+        let mut pipe = redis::pipe();
+        match ttl {
+            Some(ttl) => pipe.set_ex(&output_key, value, ttl),
+            None => pipe.set(&output_key, value),
+        };
+        for tag in tags {
+            pipe.cmd("EVAL").arg(TAG_SCRIPT).arg(1).arg(self.tag_key(tag)).arg(key).arg(ttl.unwrap_or_default());
+        }
+        let _: RedisResult<()> = self.with_connection(|c| pipe.query(c));
+    }
+
+    fn invalidate_tag(&self, tag: &str) -> usize {
+        let tag_key = self.tag_key(tag);
+        let result = self.with_connection(|connection| {
+            let keys: Vec<String> = connection.smembers(&tag_key)?;
+            connection.del::<_, ()>(&tag_key)?;
+            if keys.is_empty() {
+                return Ok(0);
+            }
+            let output_keys: Vec<String> = keys.iter().map(|key| self.output_key(key)).collect();
+            connection.del(&output_keys)
+        });
+        result.unwrap_or_default()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        let cache = RedisRenderCache::open("redis://127.0.0.1/").unwrap().with_prefix("app:".to_owned());
+        assert_eq!(cache.output_key("terarium:abc"), "app:terarium:abc");
+        assert_eq!(cache.tag_key("product:1"), "app:tag:product:1");
+    }
+
+    /// Run against the server at `TERARIUM_REDIS_URL` (e.g. `redis://127.0.0.1/`), skipped when it is not set.
+    #[test]
+    fn store_and_invalidate() {
+        let Ok(url) = std::env::var("TERARIUM_REDIS_URL") else { return };
+        let cache = RedisRenderCache::open(&url).unwrap().with_prefix(format!("test:{}:", std::process::id()));
+        let tags = ["product:1".to_owned(), "products".to_owned()];
+        cache.put("a", "A", &tags, Some(Duration::from_secs(60)));
+        cache.put("b", "B\nmore", &tags[1..], None);
+        assert_eq!(cache.get("a").unwrap().output, "A");
+        assert_eq!(cache.get("b").unwrap().output, "B\nmore");
+
+        let ttl = |tag: &str| cache.with_connection(|c| c.ttl::<_, i64>(cache.tag_key(tag))).unwrap();
+        assert!((1..=60).contains(&ttl("product:1")));
+        assert_eq!(ttl("products"), -1);
+        assert_eq!(cache.idle.lock().unwrap().len(), 1);
+
+        assert_eq!(cache.invalidate_tag("product:1"), 1);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.invalidate_tag("products"), 1);
+        assert!(cache.get("b").is_none());
+        assert_eq!(ttl("products"), -2);
+    }
+
+    #[test]
+    fn unavailable_server() {
+        let cache = RedisRenderCache::open("redis://127.0.0.1:1/").unwrap().with_timeout(Duration::from_millis(100));
+        cache.put("a", "A", &["products".to_owned()], None);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.invalidate_tag("products"), 0);
+        assert!(cache.idle.lock().unwrap().is_empty());
+    }
+}
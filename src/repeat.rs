@@ -0,0 +1,31 @@
+/// Options of the `Terarium::render_repeated()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepeatOptions {
+    /// Text inserted between rendered items.
+    pub separator: String,
+    /// Key of the template rendered before the first item.
+    pub header: Option<String>,
+    /// Key of the template rendered after the last item.
+    pub footer: Option<String>,
+}
+
+
+impl RepeatOptions {
+    /// Set text inserted between rendered items.
+    pub fn separator(mut self, separator: String) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Set key of the template rendered before the first item.
+    pub fn header(mut self, template_key: String) -> Self {
+        self.header = Some(template_key);
+        self
+    }
+
+    /// Set key of the template rendered after the last item.
+    pub fn footer(mut self, template_key: String) -> Self {
+        self.footer = Some(template_key);
+        self
+    }
+}
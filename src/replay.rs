@@ -0,0 +1,34 @@
+use crate::{RenderOutcome, RenderRequest, TerariumError};
+
+
+/// Captured failed render which can be replayed by the `Terarium::replay()`.
+/// Created by the `Terarium::snapshot()`.
+/// Sensitive values of the context (see `Sensitive`) are masked in the request and the error, so snapshots can be
+/// stored outside of the process. They are replayed with the `REDACTED` text instead of the original values.
+#[derive(Clone, Debug)]
+pub struct RenderSnapshot {
+    /// The failed request.
+    pub request: RenderRequest,
+    /// Description of the original error.
+    pub error: String,
+    /// Fingerprint of the catalog which failed to render the request.
+    pub catalog_fingerprint: u64,
+}
+
+
+/// Result of the `Terarium::replay()`.
+#[derive(Debug)]
+pub struct ReplayReport {
+    /// Result of the render against the current catalog.
+    pub result: Result<RenderOutcome, TerariumError>,
+    /// `true` when the catalog changed since the snapshot was captured.
+    pub catalog_changed: bool,
+}
+
+
+impl ReplayReport {
+    /// Return `true` when the request is rendered successfully now.
+    pub fn succeeded(&self) -> bool {
+        self.result.is_ok()
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::time::{Instant, SystemTime};
+
+use tera::Context;
+
+use crate::redaction::mask_sensitive;
+use crate::{redact_context, Attachment, MemberEncoding, MissingVariables, RenderCost, RenderIntegrity, RenderTrace};
+
+
+/// What should be rendered by the `RenderRequest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenderTarget {
+    /// Single template defined by its key.
+    Template(String),
+    /// Template group defined by its key.
+    Group(String),
+}
+
+
+/// All inputs of one render call.
+/// Pass the request to the `Terarium::render()` method.
+#[derive(Clone)]
+pub struct RenderRequest {
+    target: RenderTarget,
+    languages: Vec<String>,
+    variants: Vec<String>,
+    brand: Option<String>,
+    timezone: Option<String>,
+    locale: Option<String>,
+    namespace: Option<String>,
+    missing_variables: Option<MissingVariables>,
+    time: Option<SystemTime>,
+    random_seed: Option<u64>,
+    expected_identity: Option<String>,
+    deadline: Option<Instant>,
+    output_limit: Option<usize>,
+    template_overrides: HashMap<String, String>,
+    bypass_cache: bool,
+    integrity: bool,
+    cost: bool,
+    trace: bool,
+    context: Context,
+}
+
+
+impl RenderRequest {
+    /// Create request for rendering single template.
+    pub fn template(template_key: String, context: Context) -> Self {
+        Self::new(RenderTarget::Template(template_key), context)
+    }
+
+    /// Create request for rendering template group.
+    pub fn group(group_key: String, context: Context) -> Self {
+        Self::new(RenderTarget::Group(group_key), context)
+    }
+
+    /// Create request for the render target.
+    pub fn new(target: RenderTarget, context: Context) -> Self {
+        Self {
+            target,
+            languages: Vec::new(),
+            variants: Vec::new(),
+            brand: None,
+            timezone: None,
+            locale: None,
+            namespace: None,
+            missing_variables: None,
+            time: None,
+            random_seed: None,
+            expected_identity: None,
+            deadline: None,
+            output_limit: None,
+            template_overrides: HashMap::new(),
+            bypass_cache: false,
+            integrity: false,
+            cost: false,
+            trace: false,
+            context,
+        }
+    }
+
+    /// Append language to the language chain.
+    /// Languages are tried in order they were added, so the first one is primary and others are fallbacks.
+    pub fn with_language(mut self, language: String) -> Self {
+        self.languages.push(language);
+        self
+    }
+
+    /// Append content variant to the variant chain.
+    /// Variants are tried in order they were added for each language, content without variant is used last.
+    pub fn with_variant(mut self, variant: String) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// Render for the brand.
+    /// Globals of the brand are merged into the context (see `BrandResolver`) and the brand id is appended
+    /// to the variant chain, so contents with variant equal to the brand id override default contents.
+    pub fn with_brand(mut self, brand_id: String) -> Self {
+        self.brand = Some(brand_id);
+        self
+    }
+
+    /// Set timezone of the recipient (e.g. `Europe/Prague`).
+    /// The `date` filter uses the timezone when no `timezone` argument is given and the render context gets
+    /// `now` (current UNIX timestamp) and `tz` values.
+    pub fn with_timezone(mut self, timezone: String) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Set locale of the recipient (e.g. `cs_CZ`).
+    /// The render context gets `now` and `locale` values. With the `date-locale` feature, the `date` filter uses
+    /// the locale when no `locale` argument is given.
+    pub fn with_locale(mut self, locale: String) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+
+    /// Render on behalf of the namespace (e.g. tenant), so its quota is applied (see `QuotaPolicy`).
+    pub fn with_namespace(mut self, namespace: String) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Override rendering of missing variables set by the `TerariumBuilder::set_missing_variables()`.
+    pub fn with_missing_variables(mut self, missing_variables: MissingVariables) -> Self {
+        self.missing_variables = Some(missing_variables);
+        self
+    }
+
+    /// Select contents as if rendered at the `time`, so override rules (see `OverrideRule`) apply reproducibly.
+    pub fn with_time(mut self, time: SystemTime) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Seed the `get_random()` by the `seed` instead of the `random_seed` context value.
+    /// Applies only when nondeterministic functions are replaced (see `Determinism::Replace`).
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    /// Fail the render by the `TerariumError::CatalogVersionMismatch` when the identity of the target in the primary
+    /// language differs from the `identity` cached by the client (see `Terarium::identity()`).
+    pub fn with_expected_identity(mut self, identity: String) -> Self {
+        self.expected_identity = Some(identity);
+        self
+    }
+
+    /// Fail the render by the `TerariumError::DeadlineExceeded` when it does not finish by the `deadline`.
+    /// The deadline is checked before each content is rendered, before its output is post-processed and before
+    /// each group member, so a running `Tera` render is never interrupted.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Fail the render by the `TerariumError::OutputTooLarge` when total size of rendered contents exceeds
+    /// the `bytes`.
+    pub fn with_output_limit(mut self, bytes: usize) -> Self {
+        self.output_limit = Some(bytes);
+        self
+    }
+
+    /// Render the `replacement_key` template wherever the `template_key` template is rendered, including group
+    /// members and fallbacks (e.g. previewing a draft of the template in its groups).
+    pub fn with_template_override(mut self, template_key: String, replacement_key: String) -> Self {
+        self.template_overrides.insert(template_key, replacement_key);
+        self
+    }
+
+    /// Neither read nor write the render cache (see `CachePolicy`).
+    pub fn with_cache_bypass(mut self) -> Self {
+        self.bypass_cache = true;
+        self
+    }
+
+    /// Request `RenderIntegrity` of the outputs in the `RenderOutcome`.
+    pub fn with_integrity(mut self) -> Self {
+        self.integrity = true;
+        self
+    }
+
+    /// Request `RenderCost` of the render in the `RenderOutcome`.
+    pub fn with_cost(mut self) -> Self {
+        self.cost = true;
+        self
+    }
+
+    /// Request `RenderTrace` of the render in the `RenderOutcome`.
+    pub fn with_trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    /// Get the render target.
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    /// Get the language chain.
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// Get the variant chain.
+    pub fn variants(&self) -> &[String] {
+        &self.variants
+    }
+
+    /// Get the brand id.
+    pub fn brand(&self) -> Option<&str> {
+        self.brand.as_deref()
+    }
+
+    /// Get the timezone.
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    /// Get the locale.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// Get the namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Get the rendering of missing variables when overridden.
+    pub fn missing_variables(&self) -> Option<MissingVariables> {
+        self.missing_variables
+    }
+
+    /// Get the time contents are selected at.
+    pub fn time(&self) -> Option<SystemTime> {
+        self.time
+    }
+
+    /// Get the seed of the `get_random()`.
+    pub fn random_seed(&self) -> Option<u64> {
+        self.random_seed
+    }
+
+    /// Get the identity of the target expected by the client.
+    pub fn expected_identity(&self) -> Option<&str> {
+        self.expected_identity.as_deref()
+    }
+
+    /// Get the time the render must finish by.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Get the maximal total size of rendered contents in bytes.
+    pub fn output_limit(&self) -> Option<usize> {
+        self.output_limit
+    }
+
+    /// Get keys of templates rendered instead of other templates by their keys.
+    pub fn template_overrides(&self) -> &HashMap<String, String> {
+        &self.template_overrides
+    }
+
+    /// Return `true` when the render cache is bypassed.
+    pub fn cache_bypass(&self) -> bool {
+        self.bypass_cache
+    }
+
+    /// Return `true` when integrity of the outputs is requested.
+    pub fn integrity(&self) -> bool {
+        self.integrity
+    }
+
+    /// Return `true` when cost of the render is requested.
+    pub fn cost(&self) -> bool {
+        self.cost
+    }
+
+    /// Return `true` when trace of the render is requested.
+    pub fn trace(&self) -> bool {
+        self.trace
+    }
+
+    /// Get the render context.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Return copy of the request with sensitive values of the context masked.
+    pub(crate) fn redacted(&self) -> Self {
+        Self { context: mask_sensitive(&self.context), ..self.clone() }
+    }
+}
+
+
+impl Debug for RenderRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderRequest")
+            .field("target", &self.target)
+            .field("languages", &self.languages)
+            .field("variants", &self.variants)
+            .field("brand", &self.brand)
+            .field("timezone", &self.timezone)
+            .field("locale", &self.locale)
+            .field("namespace", &self.namespace)
+            .field("missing_variables", &self.missing_variables)
+            .field("time", &self.time)
+            .field("random_seed", &self.random_seed)
+            .field("expected_identity", &self.expected_identity)
+            .field("deadline", &self.deadline)
+            .field("output_limit", &self.output_limit)
+            .field("template_overrides", &self.template_overrides)
+            .field("bypass_cache", &self.bypass_cache)
+            .field("integrity", &self.integrity)
+            .field("cost", &self.cost)
+            .field("trace", &self.trace)
+            .field("context", &redact_context(&self.context))
+            .finish()
+    }
+}
+
+
+/// Rendered data of the `RenderRequest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenderOutput {
+    /// Rendered content of single template.
+    Template(String),
+    /// Rendered contents of group members by member keys.
+    Group(HashMap<String, String>),
+}
+
+
+impl RenderOutput {
+    /// Return total size of rendered contents in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Template(output) => output.len(),
+            Self::Group(members) => members.values().map(|output| output.len()).sum(),
+        }
+    }
+
+    /// Return `true` when all rendered contents are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+
+/// Result of the successful `Terarium::render()` call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderOutcome {
+    /// Rendered data.
+    pub output: RenderOutput,
+    /// Integrity of the outputs when requested by the `RenderRequest::with_integrity()`.
+    pub integrity: Option<RenderIntegrity>,
+    /// Source revisions of the rendered templates by template keys (see `Template::set_revision()`).
+    /// Templates without revision are not listed.
+    pub revisions: HashMap<String, String>,
+    /// Cost of the render when requested by the `RenderRequest::with_cost()`.
+    pub cost: Option<RenderCost>,
+    /// Ids of override rules applied during the render in order they were applied (see `Template::add_override()`).
+    pub overrides: Vec<String>,
+    /// Trace of the render when requested by the `RenderRequest::with_trace()`.
+    pub trace: Option<RenderTrace>,
+    /// MIME types and transfer encodings of encoded group members by member keys
+    /// (see `TemplateGroupBuilder::encode_member()`).
+    pub encodings: HashMap<String, MemberEncoding>,
+    /// Attachments rendered by the group sorted by member keys (see `TemplateGroupBuilder::attach_member()`).
+    pub attachments: Vec<Attachment>,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{Sensitive, SensitiveContext};
+
+    use super::*;
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let mut ctx = Context::new();
+        ctx.insert_sensitive("password", &Sensitive("secret"));
+        let request = RenderRequest::template("tpl".to_owned(), ctx).with_language("en".to_owned());
+        let debug = format!("{:?}", request);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("[REDACTED]"));
+    }
+}
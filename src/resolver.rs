@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tera::{Error as TeraError, Function, Result as TeraResult, Value};
+
+use crate::scope::with_current;
+use crate::trace::record_resolved;
+
+
+/// Resolve values lazily during render, intended for expensive values only some templates need.
+/// Templates call `{{ resolve(key="user.display_name") }}`, the recipient is taken from the `recipient_id` context
+/// value unless given by the `recipient` argument.
+/// The resolver is set by the `TerariumBuilder::set_value_resolver()`.
+pub trait ValueResolver: Send + Sync {
+    /// Return value of the key for the recipient or description of the failure.
+    fn resolve(&self, key: &str, recipient: Option<&Value>) -> Result<Value, String>;
+}
+
+
+impl<F> ValueResolver for F where F: Fn(&str, Option<&Value>) -> Result<Value, String> + Send + Sync {
+    fn resolve(&self, key: &str, recipient: Option<&Value>) -> Result<Value, String> {
+        self(key, recipient)
+    }
+}
+
+
+/// Create the `resolve()` template function backed by the `resolver`.
+pub(crate) fn resolve_function(resolver: Arc<dyn ValueResolver>) -> impl Function {
+    move |args: &HashMap<String, Value>| -> TeraResult<Value> {
+        let key = args
+            .get("key")
+            .and_then(|key| key.as_str())
+            .ok_or_else(|| TeraError::msg("Function `resolve` requires `key` argument"))?;
+        record_resolved(key);
+        let result = match args.get("recipient") {
+            Some(recipient) => resolver.resolve(key, Some(recipient)),
+            None => with_current(|scope| resolver.resolve(key, scope.and_then(|s| s.recipient.as_ref()))),
+        };
+        result.map_err(|error| TeraError::msg(format!("Cannot resolve `{}`: {}", key, error)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let function = resolve_function(Arc::new(|key: &str, recipient: Option<&Value>| match key {
+            "user.display_name" => Ok(Value::from(format!("User {}", recipient.cloned().unwrap_or_default()))),
+            _ => Err("unknown key".to_owned()),
+        }));
+        let args = |key: &str| HashMap::from([
+            ("key".to_owned(), Value::from(key)),
+            ("recipient".to_owned(), Value::from(7)),
+        ]);
+        assert_eq!(function.call(&args("user.display_name")).unwrap(), Value::from("User 7"));
+        assert!(function.call(&args("other")).is_err());
+    }
+}
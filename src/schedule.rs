@@ -0,0 +1,64 @@
+use std::time::SystemTime;
+
+
+/// Time window when a template or a group may be rendered (e.g. seasonal campaign).
+/// Outside the window the evergreen fallback is rendered instead, or the render fails when there is no fallback.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidityWindow {
+    /// Start of the window, the window is open to the past when not set.
+    pub valid_from: Option<SystemTime>,
+    /// End of the window (exclusive), the window is open to the future when not set.
+    pub valid_until: Option<SystemTime>,
+    /// Key of the template or the group rendered outside the window.
+    pub fallback: Option<String>,
+}
+
+
+impl ValidityWindow {
+    /// Create window open in both directions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set start of the window.
+    pub fn from(mut self, valid_from: SystemTime) -> Self {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    /// Set end of the window.
+    pub fn until(mut self, valid_until: SystemTime) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    /// Set key of the evergreen template or group rendered outside the window.
+    pub fn fallback(mut self, key: String) -> Self {
+        self.fallback = Some(key);
+        self
+    }
+
+    /// Return `true` when the time is inside the window.
+    pub fn contains(&self, time: SystemTime) -> bool {
+        self.valid_from.map_or(true, |from| from <= time) && self.valid_until.map_or(true, |until| time < until)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn contains() {
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let window = ValidityWindow::new().from(time(10)).until(time(20));
+        assert!(!window.contains(time(9)));
+        assert!(window.contains(time(10)));
+        assert!(window.contains(time(19)));
+        assert!(!window.contains(time(20)));
+        assert!(ValidityWindow::new().until(time(20)).contains(time(0)));
+    }
+}
@@ -0,0 +1,84 @@
+use serde_json::{json, Map, Value};
+
+
+/// Version of the OpenAPI specification the exported schema follows.
+const OPENAPI_VERSION: &str = "3.0.3";
+
+
+/// Template or group described by the exported schema.
+#[derive(Debug, Default)]
+pub(crate) struct SchemaEntry {
+    pub(crate) key: String,
+    pub(crate) languages: Vec<String>,
+    pub(crate) required_variables: Vec<String>,
+}
+
+
+/// Return OpenAPI document with component schemas of template keys, group keys, languages and contexts of templates
+/// and groups. The document has no paths, it is meant to be merged into the specification of the render service.
+pub(crate) fn openapi_schema(version: &str, templates: &[SchemaEntry], groups: &[SchemaEntry]) -> Value {
+    let keys = |entries: &[SchemaEntry]| entries.iter().map(|entry| entry.key.clone()).collect::<Vec<_>>();
+    let mut languages: Vec<_> = templates.iter().flat_map(|entry| entry.languages.iter().cloned()).collect();
+    languages.sort();
+    languages.dedup();
+
+    let mut schemas = Map::new();
+    schemas.insert("TemplateKey".to_owned(), json!({"type": "string", "enum": keys(templates)}));
+    schemas.insert("GroupKey".to_owned(), json!({"type": "string", "enum": keys(groups)}));
+    schemas.insert("Language".to_owned(), json!({"type": "string", "enum": languages}));
+    for (prefix, entries) in [("TemplateContext", templates), ("GroupContext", groups)] {
+        for entry in entries {
+            schemas.insert(format!("{}.{}", prefix, component_name(&entry.key)), context_schema(entry));
+        }
+    }
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {"title": "Template catalog", "version": version},
+        "paths": {},
+        "components": {"schemas": schemas},
+    })
+}
+
+
+/// Return schema of the context of the template or group.
+/// Types of variables are not known, so any value is accepted.
+fn context_schema(entry: &SchemaEntry) -> Value {
+    let properties: Map<_, _> = entry.required_variables.iter().map(|v| (v.clone(), json!({}))).collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": entry.required_variables,
+        "additionalProperties": true,
+        "x-terarium-key": entry.key,
+        "x-terarium-languages": entry.languages,
+    })
+}
+
+
+/// Replace characters not allowed in names of OpenAPI components by underscores.
+fn component_name(key: &str) -> String {
+    key.chars().map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema() {
+        let templates = [SchemaEntry {
+            key: "emails/welcome".to_owned(),
+            languages: vec!["cs".to_owned(), "en".to_owned()],
+            required_variables: vec!["user".to_owned()],
+        }];
+        let schema = openapi_schema("1", &templates, &[]);
+        assert_eq!(schema["openapi"], "3.0.3");
+        let schemas = &schema["components"]["schemas"];
+        assert_eq!(schemas["Language"]["enum"], json!(["cs", "en"]));
+        assert_eq!(schemas["GroupKey"]["enum"], json!([]));
+        let context = &schemas["TemplateContext.emails_welcome"];
+        assert_eq!(context["required"], json!(["user"]));
+        assert_eq!(context["x-terarium-key"], "emails/welcome");
+    }
+}
@@ -0,0 +1,111 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use tera::Value;
+
+use crate::MissingVariables;
+
+
+/// Options of the render in progress.
+/// The scope is available to filters and functions registered by the `Terarium`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RenderScope {
+    /// Timezone used by date filters when no timezone is given.
+    pub(crate) timezone: Option<String>,
+    /// Language of the content being rendered.
+    pub(crate) language: Option<String>,
+    /// Languages of the render in order of preference, used by the `snippet()` function.
+    pub(crate) languages: Vec<String>,
+    /// Locale used by date filters when no locale is given.
+    #[cfg_attr(not(feature = "date-locale"), allow(dead_code))]
+    pub(crate) locale: Option<String>,
+    /// Value returned by the `now()` in deterministic mode.
+    pub(crate) now: Option<Value>,
+    /// Seed of the `get_random()` in deterministic mode.
+    pub(crate) random_seed: u64,
+    /// Seed of the `get_random()` requested for the render, it takes precedence over the context value.
+    pub(crate) requested_seed: Option<u64>,
+    /// Time override rules are evaluated at, the current time is used when not set.
+    pub(crate) time: Option<SystemTime>,
+    /// Number of `get_random()` calls in the scope.
+    pub(crate) random_calls: Cell<u64>,
+    /// Recipient of the render (the `recipient_id` context value).
+    pub(crate) recipient: Option<Value>,
+    /// Rendering of missing variables requested for the render.
+    pub(crate) missing_variables: Option<MissingVariables>,
+    /// Time the render must finish by.
+    pub(crate) deadline: Option<Instant>,
+    /// Keys of templates rendered instead of other templates by their keys.
+    pub(crate) template_overrides: Arc<HashMap<String, String>>,
+    /// Render cache is neither read nor written.
+    pub(crate) bypass_cache: bool,
+}
+
+
+thread_local! {
+    static CURRENT: RefCell<Option<RenderScope>> = const { RefCell::new(None) };
+}
+
+
+/// Restore the previous scope when dropped.
+struct ScopeGuard(Option<RenderScope>);
+
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+
+/// Call the `f` with the `scope` set as the current scope of the thread.
+pub(crate) fn with_scope<R>(scope: RenderScope, f: impl FnOnce() -> R) -> R {
+    let _guard = ScopeGuard(CURRENT.with(|current| current.replace(Some(scope))));
+    f()
+}
+
+
+/// Call the `f` with the copy of the current scope modified by the `update`.
+pub(crate) fn with_updated<R>(update: impl FnOnce(&mut RenderScope), f: impl FnOnce() -> R) -> R {
+    let mut scope = with_current(|scope| scope.cloned()).unwrap_or_default();
+    update(&mut scope);
+    with_scope(scope, f)
+}
+
+
+
+/// Call the `f` with the current scope.
+pub(crate) fn with_current<R>(f: impl FnOnce(Option<&RenderScope>) -> R) -> R {
+    CURRENT.with(|current| f(current.borrow().as_ref()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_scopes() {
+        let timezone = || with_current(|scope| scope.and_then(|s| s.timezone.clone()));
+        let scope = |tz: &str| RenderScope { timezone: Some(tz.to_owned()), ..RenderScope::default() };
+
+        with_scope(scope("Europe/Prague"), || {
+            with_scope(scope("UTC"), || assert_eq!(timezone().unwrap(), "UTC"));
+            assert_eq!(timezone().unwrap(), "Europe/Prague");
+        });
+        assert!(timezone().is_none());
+    }
+
+    #[test]
+    fn updated_scope() {
+        let scope = RenderScope { timezone: Some("UTC".to_owned()), ..RenderScope::default() };
+        with_scope(scope, || with_updated(|scope| scope.language = Some("cs".to_owned()), || with_current(|scope| {
+            let scope = scope.unwrap();
+            assert_eq!(scope.timezone.as_deref(), Some("UTC"));
+            assert_eq!(scope.language.as_deref(), Some("cs"));
+        })));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::RenderTarget;
+
+
+/// Query of the `Terarium::search()`.
+/// Keys, display names and metadata are matched by substring or fuzzily (characters of the query in order),
+/// contents only by substring. Matching is case-insensitive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchQuery {
+    text: String,
+    contents: bool,
+    limit: Option<usize>,
+}
+
+
+impl SearchQuery {
+    /// Create query searching keys, display names and metadata.
+    pub fn new(text: &str) -> Self {
+        Self { text: text.trim().to_lowercase(), contents: false, limit: None }
+    }
+
+    /// Search sources of template contents too. Sources are kept only when set by the
+    /// `TerariumBuilder::set_source_storage()`.
+    pub fn contents(mut self, enabled: bool) -> Self {
+        self.contents = enabled;
+        self
+    }
+
+    /// Return at most `limit` best matches.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Return `true` when sources of contents are searched.
+    pub(crate) fn searches_contents(&self) -> bool {
+        self.contents
+    }
+
+    /// Return score of the field value, `None` when it does not match.
+    pub(crate) fn score(&self, field: SearchField, value: &str) -> Option<u32> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let value = value.to_lowercase();
+        let score = if value == self.text {
+            100
+        } else if value.starts_with(&self.text) {
+            80
+        } else if value.contains(&self.text) {
+            60
+        } else if field != SearchField::Content && is_subsequence(&self.text, &value) {
+            20
+        } else {
+            return None;
+        };
+        Some(score * field.weight())
+    }
+
+    /// Sort matches from the best one and apply the limit.
+    pub(crate) fn rank(&self, mut matches: Vec<SearchMatch>) -> Vec<SearchMatch> {
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| target_key(&a.target).cmp(target_key(&b.target))));
+        if let Some(limit) = self.limit {
+            matches.truncate(limit);
+        }
+        matches
+    }
+}
+
+
+impl From<&str> for SearchQuery {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+
+/// Part of the template or group matching the query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchField {
+    /// Key of the template or group.
+    Key,
+    /// Localized name or description (see `DisplayName`).
+    DisplayName,
+    /// Owner, tags, description or metadata.
+    Metadata,
+    /// Source of a template content.
+    Content,
+}
+
+
+impl SearchField {
+    /// Return multiplier of scores, so matches of keys rank first.
+    fn weight(&self) -> u32 {
+        match self {
+            Self::Key => 3,
+            Self::DisplayName => 2,
+            Self::Metadata | Self::Content => 1,
+        }
+    }
+}
+
+
+/// Template or group found by the `Terarium::search()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Found template or group.
+    pub target: RenderTarget,
+    /// Score of the best matching field, higher is better.
+    pub score: u32,
+    /// The best matching field.
+    pub field: SearchField,
+}
+
+
+impl SearchMatch {
+    /// Return the best match of the target among scored fields, `None` when no field matches.
+    pub(crate) fn best(
+        target: RenderTarget,
+        scores: impl IntoIterator<Item=(SearchField, Option<u32>)>,
+    ) -> Option<Self> {
+        let (field, score) = scores
+            .into_iter()
+            .filter_map(|(field, score)| score.map(|score| (field, score)))
+            .max_by_key(|(_, score)| *score)?;
+        Some(Self { target, score, field })
+    }
+}
+
+
+fn target_key(target: &RenderTarget) -> &str {
+    match target {
+        RenderTarget::Template(key) | RenderTarget::Group(key) => key,
+    }
+}
+
+
+/// Return `true` when all characters of the `query` occur in the `value` in the same order.
+fn is_subsequence(query: &str, value: &str) -> bool {
+    let mut chars = value.chars();
+    query.chars().all(|c| chars.any(|v| v == c))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score() {
+        let query = SearchQuery::new(" Welcome ");
+        assert_eq!(query.score(SearchField::Key, "welcome"), Some(300));
+        assert_eq!(query.score(SearchField::Key, "welcome_email"), Some(240));
+        assert_eq!(query.score(SearchField::Metadata, "Emails/Welcome"), Some(60));
+        assert_eq!(query.score(SearchField::DisplayName, "we lc ome"), Some(40));
+        assert_eq!(query.score(SearchField::Content, "we lc ome"), None);
+        assert_eq!(query.score(SearchField::Key, "goodbye"), None);
+        assert_eq!(SearchQuery::new("").score(SearchField::Key, "welcome"), None);
+    }
+
+    #[test]
+    fn rank() {
+        let found = |key: &str, score| SearchMatch {
+            target: RenderTarget::Template(key.to_owned()),
+            score,
+            field: SearchField::Key,
+        };
+        let ranked = SearchQuery::new("a").limit(2).rank(vec![found("c", 10), found("b", 20), found("a", 20)]);
+        assert_eq!(ranked, vec![found("a", 20), found("b", 20)]);
+    }
+}
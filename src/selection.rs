@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::RenderRequest;
+use crate::lookup::LookupMap;
+
+
+/// Contents of one template in one language.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LanguageContents {
+    /// Name of the content without variant.
+    pub(crate) default: Option<String>,
+    /// Names of contents by variants.
+    pub(crate) variants: LookupMap<Arc<str>, String>,
+}
+
+
+/// Contents of one template by languages.
+pub(crate) type TemplateContents = LookupMap<Arc<str>, LanguageContents>;
+
+
+impl LanguageContents {
+    /// Iterate over all content names with their variants.
+    pub(crate) fn iter(&self) -> impl Iterator<Item=(Option<&Arc<str>>, &String)> {
+        self.default.iter().map(|name| (None, name)).chain(self.variants.iter().map(|(v, name)| (Some(v), name)))
+    }
+}
+
+
+/// Preferences used for selecting content of a template.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Selection<'a> {
+    /// Languages in order of preference.
+    pub(crate) languages: Vec<&'a str>,
+    /// Variants in order of preference. Content without variant is used when none of them matches.
+    pub(crate) variants: Vec<&'a str>,
+}
+
+
+impl<'a> Selection<'a> {
+    /// Create selection of the language with optional fallback.
+    pub(crate) fn new(language: &'a str, fallback_language: Option<&'a str>) -> Self {
+        Self {
+            languages: Some(language).into_iter().chain(fallback_language).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Create selection described by the request.
+    pub(crate) fn from_request(request: &'a RenderRequest) -> Self {
+        Self {
+            languages: request.languages().iter().map(|l| l.as_str()).collect(),
+            variants: request.variants().iter().map(|v| v.as_str()).chain(request.brand()).collect(),
+        }
+    }
+
+    /// Return the selection with aliased languages replaced by their canonical languages.
+    pub(crate) fn aliased<'b>(&self, aliases: &'b HashMap<String, String>) -> Selection<'b> where 'a: 'b {
+        Selection {
+            languages: self.languages
+                .iter()
+                .map(|language| aliases.get(*language).map_or(*language, |canonical| canonical.as_str()))
+                .collect(),
+            variants: self.variants.clone(),
+        }
+    }
+
+    /// Find the best matching content and return its language and name.
+    pub(crate) fn find<'m>(&self, contents: &'m TemplateContents) -> Option<(&'m str, &'m String)> {
+        self.languages.iter().filter_map(|language| contents.get_key_value(*language)).find_map(|(language, language_contents)| {
+            self.variants
+                .iter()
+                .find_map(|variant| language_contents.variants.get(*variant))
+                .or(language_contents.default.as_ref())
+                .map(|name| (language.as_ref(), name))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find() {
+        let contents: TemplateContents = [
+            ("cs".into(), LanguageContents {
+                default: None,
+                variants: [("dark".into(), "cs_dark".to_owned())].into_iter().collect(),
+            }),
+            ("en".into(), LanguageContents {
+                default: Some("en".to_owned()),
+                variants: [("dark".into(), "en_dark".to_owned())].into_iter().collect(),
+            }),
+        ].into_iter().collect();
+        let mut selection = Selection::new("cs", Some("en"));
+        assert_eq!(selection.find(&contents).unwrap().1, "en");
+
+        selection.variants = vec!["light", "dark"];
+        assert_eq!(selection.find(&contents).unwrap().1, "cs_dark");
+
+        selection.languages = vec!["de", "en"];
+        assert_eq!(selection.find(&contents).unwrap(), ("en", &"en_dark".to_owned()));
+
+        selection.languages = vec!["de"];
+        assert!(selection.find(&contents).is_none());
+
+        let aliases = HashMap::from([("de".to_owned(), "cs".to_owned())]);
+        assert_eq!(selection.aliased(&aliases).find(&contents).unwrap().1, "cs_dark");
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tera::{Error as TeraError, Function, Result as TeraResult, Value};
+
+use crate::scope::with_current;
+
+
+/// Catalog of short localized strings (e.g. button labels) shared by templates.
+/// Templates call `{{ snippet(key="cta.view_order") }}`, the text is looked up in the language of the rendered
+/// content first and then in fallback languages of the render, like contents of templates.
+/// The `language` argument selects the language explicitly.
+/// Snippets are added by the `TerariumBuilder::add_snippet()`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SnippetCatalog {
+    snippets: HashMap<String, HashMap<String, String>>,
+    aliases: HashMap<String, String>,
+}
+
+
+impl SnippetCatalog {
+    /// Add text of the snippet in the language, replacing the previous one.
+    pub(crate) fn insert(&mut self, key: String, language: String, text: String) {
+        self.snippets.entry(key).or_default().insert(language, text);
+    }
+
+    /// Return `true` when there is no snippet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    /// Set aliases of languages applied by the `find()`.
+    pub(crate) fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+    }
+
+    /// Return text of the snippet in the first of the languages it exists in.
+    pub(crate) fn find<'a>(&self, key: &str, languages: impl IntoIterator<Item=&'a str>) -> Option<&str> {
+        let texts = self.snippets.get(key)?;
+        languages
+            .into_iter()
+            .map(|language| self.aliases.get(language).map_or(language, |canonical| canonical.as_str()))
+            .find_map(|language| texts.get(language))
+            .map(|text| text.as_str())
+    }
+}
+
+
+/// Create the `snippet()` template function backed by the `catalog`.
+pub(crate) fn snippet_function(catalog: Arc<SnippetCatalog>) -> impl Function {
+    move |args: &HashMap<String, Value>| -> TeraResult<Value> {
+        let key = args
+            .get("key")
+            .and_then(|key| key.as_str())
+            .ok_or_else(|| TeraError::msg("Function `snippet` requires `key` argument"))?;
+        let text = match args.get("language").and_then(|language| language.as_str()) {
+            Some(language) => catalog.find(key, [language]),
+            None => with_current(|scope| {
+                let scope = scope.map(|s| (s.language.as_deref(), s.languages.as_slice())).unwrap_or_default();
+                catalog.find(key, scope.0.into_iter().chain(scope.1.iter().map(|l| l.as_str())))
+            }),
+        };
+        text.map(Value::from).ok_or_else(|| TeraError::msg(format!("Snippet `{}` not found", key)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::{RenderScope, with_scope};
+
+    #[test]
+    fn snippet() {
+        let mut catalog = SnippetCatalog::default();
+        catalog.insert("cta".to_owned(), "en".to_owned(), "View order".to_owned());
+        catalog.insert("cta".to_owned(), "cs".to_owned(), "Zobrazit objednávku".to_owned());
+        catalog.set_aliases(HashMap::from([("cs-CZ".to_owned(), "cs".to_owned())]));
+        let function = snippet_function(Arc::new(catalog));
+        let call = |args: &[(&str, &str)]| {
+            function.call(&args.iter().map(|(k, v)| (k.to_string(), Value::from(*v))).collect())
+        };
+        let scope = |language: &str| RenderScope {
+            language: Some(language.to_owned()),
+            languages: vec!["de".to_owned(), "en".to_owned()],
+            ..RenderScope::default()
+        };
+        with_scope(scope("cs"), || assert_eq!(call(&[("key", "cta")]).unwrap(), "Zobrazit objednávku"));
+        with_scope(scope("sk"), || assert_eq!(call(&[("key", "cta")]).unwrap(), "View order"));
+        assert_eq!(call(&[("key", "cta"), ("language", "cs-CZ")]).unwrap(), "Zobrazit objednávku");
+        assert!(call(&[("key", "cta")]).is_err());
+        assert!(call(&[("key", "unknown"), ("language", "cs")]).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+
+/// Number of templates listed in the `CatalogStats::largest_templates`.
+pub(crate) const LARGEST_TEMPLATES: usize = 10;
+
+
+/// Size of the catalog returned by the `Terarium::stats()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatalogStats {
+    /// Number of templates.
+    pub templates: usize,
+    /// Number of contents of all templates.
+    pub contents: usize,
+    /// Number of groups.
+    pub groups: usize,
+    /// Number of distinct languages.
+    pub languages: usize,
+    /// Total size of all contents in bytes.
+    pub content_bytes: usize,
+    /// Keys and sizes in bytes of the largest templates, the largest first.
+    pub largest_templates: Vec<(String, usize)>,
+    /// Time spent by the `TerariumBuilder::build()`.
+    pub build_time: Duration,
+}
+
+
+impl Display for CatalogStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} templates ({} contents in {} languages, {} bytes), {} groups",
+            self.templates, self.contents, self.languages, self.content_bytes, self.groups,
+        )
+    }
+}
+
+
+/// Renders of a template in a fallback language returned by the `Terarium::fallback_stats()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FallbackUsage {
+    /// Key of the rendered template.
+    pub template_key: String,
+    /// The most preferred language of renders, the template has no content in it.
+    pub requested_language: String,
+    /// Number of renders since the instance was built.
+    pub hits: u64,
+}
+
+
+/// Estimated memory footprint of the catalog returned by the `Terarium::memory_report()`.
+/// Sizes are in bytes and count keys, names, sources and lookup entries. Parsed contents are approximated by sizes of
+/// their sources and top level nodes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Bytes used by each template including its contents, by template key.
+    pub templates: HashMap<String, usize>,
+    /// Bytes used by each content, by name of the content in the `Tera` instance.
+    pub contents: HashMap<String, usize>,
+    /// Bytes used by all groups.
+    pub groups: usize,
+}
+
+
+impl MemoryReport {
+    /// Return bytes used by the whole catalog.
+    pub fn total(&self) -> usize {
+        self.templates.values().sum::<usize>() + self.groups
+    }
+}
@@ -0,0 +1,99 @@
+use std::io;
+
+
+/// Compression level used for sources kept by the `SourceStorage::Compressed`.
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: i32 = 3;
+
+
+/// How the built `Terarium` keeps sources of contents besides their parsed form.
+/// Kept sources are returned by the `Terarium::content_source()`. Parsed contents are always kept, so kept sources
+/// add memory on top of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SourceStorage {
+    /// Sources are dropped once contents are parsed.
+    #[default]
+    Dropped,
+    /// Sources are kept as they are.
+    Plain,
+    /// Sources are kept compressed by zstd and decompressed on demand,
+    /// so they add only a fraction of the `Plain` sources for catalogs dominated by big HTML templates.
+    #[cfg(feature = "compression")]
+    Compressed,
+}
+
+
+/// Source of a content kept by the `Terarium`.
+#[derive(Clone, Debug)]
+pub(crate) enum StoredSource {
+    Plain(String),
+    #[cfg(feature = "compression")]
+    Compressed(Vec<u8>),
+}
+
+
+impl SourceStorage {
+    /// Return the source stored the way described by the storage.
+    pub(crate) fn store(&self, source: &str) -> Option<StoredSource> {
+        match self {
+            Self::Dropped => None,
+            Self::Plain => Some(StoredSource::Plain(source.to_owned())),
+            #[cfg(feature = "compression")]
+            Self::Compressed => match zstd::bulk::compress(source.as_bytes(), COMPRESSION_LEVEL) {
+                Ok(compressed) => Some(StoredSource::Compressed(compressed)),
+                Err(_) => Some(StoredSource::Plain(source.to_owned())),
+            },
+        }
+    }
+}
+
+
+impl StoredSource {
+    /// Return the original source, fail when the compressed source cannot be decompressed.
+    pub(crate) fn load(&self) -> io::Result<String> {
+        match self {
+            Self::Plain(source) => Ok(source.clone()),
+            #[cfg(feature = "compression")]
+            Self::Compressed(compressed) => String::from_utf8(zstd::stream::decode_all(compressed.as_slice())?)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+        }
+    }
+
+    /// Return number of bytes used by the stored source.
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Self::Plain(source) => source.len(),
+            #[cfg(feature = "compression")]
+            Self::Compressed(compressed) => compressed.len(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store() {
+        assert!(SourceStorage::Dropped.store("abc").is_none());
+        assert_eq!(SourceStorage::Plain.store("abc").unwrap().load().unwrap(), "abc");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compress() {
+        let source = "<tr><td>{{ item }}</td></tr>\n".repeat(100);
+        let stored = SourceStorage::Compressed.store(&source).unwrap();
+        assert!(stored.size() < source.len() / 10);
+        assert_eq!(stored.load().unwrap(), source);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn corrupted() {
+        assert!(StoredSource::Compressed(b"not zstd".to_vec()).load().is_err());
+        let invalid = zstd::bulk::compress(&[0xff, 0xfe], COMPRESSION_LEVEL).unwrap();
+        assert_eq!(StoredSource::Compressed(invalid).load().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,7 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
 
 use thiserror::Error;
 
+use crate::{CachePolicy, DisplayName, OverrideRule, TranslatorNote, ValidityWindow};
+use crate::fingerprint::Fingerprint;
+
 
 /// Contains data for `Tera` template with language mutations.
 #[derive(Clone, Default)]
@@ -9,11 +13,35 @@ pub struct Template {
     /// List of available contents for the template in different languages and dialects
     contents: Vec<Content>,
 
-    /// Helper list of used languages
-    used_languages: HashSet<String>,
+    /// Helper list of used languages with variants
+    used_languages: HashSet<(String, Option<String>)>,
 
     /// Helper list of used names
     used_names: HashSet<String>,
+
+    /// Set when the template should not be used anymore.
+    deprecation: Option<Deprecation>,
+
+    /// Team or person responsible for the template.
+    owner: Option<String>,
+
+    /// Revision of the template in its source (e.g. ETag, row version or commit hash).
+    revision: Option<String>,
+
+    /// Caching of rendered outputs.
+    cache_policy: Option<CachePolicy>,
+
+    /// Time window when the template may be rendered.
+    validity: Option<ValidityWindow>,
+
+    /// Rules replacing contents by variants.
+    overrides: Vec<OverrideRule>,
+
+    /// Free-form labels of the template (e.g. `marketing`).
+    tags: HashSet<String>,
+
+    /// Names presented to users by languages.
+    display_names: HashMap<String, DisplayName>,
 }
 
 
@@ -34,14 +62,15 @@ impl Template {
     /// Add new content into template.
     /// Return handle of the content.
     pub fn add_content(&mut self, content: Content) -> Result<(), TemplateError> {
-        let mut languages_to_add = Vec::<String>::new();
+        let mut languages_to_add = Vec::<(String, Option<String>)>::new();
         let mut names_to_add = Vec::<String>::new();
 
         for lang in content.languages.iter() {
-            if self.used_languages.contains(lang) {
+            let language_variant = (lang.to_owned(), content.variant.clone());
+            if self.used_languages.contains(&language_variant) {
                 return Err(TemplateError::DuplicatedContentLanguages(lang.to_owned()));
             }
-            languages_to_add.push(lang.to_owned());
+            languages_to_add.push(language_variant);
         }
         if let Some(name) = content.name.clone() {
             if self.used_names.contains(&name) {
@@ -56,27 +85,198 @@ impl Template {
         Ok(())
     }
 
+    /// Return all contents of the template.
+    pub fn contents(&self) -> &[Content] {
+        &self.contents
+    }
+
+    /// Update all contents of the template.
+    /// The update must not change languages and variants of contents.
+    pub(crate) fn update_contents(&mut self, update: impl FnMut(&mut Content)) {
+        self.contents.iter_mut().for_each(update);
+        self.used_names = self.contents.iter().filter_map(|c| c.name.clone()).collect();
+    }
+
+    /// Mark the template as deprecated.
+    /// Deprecated template can be rendered but each usage is reported.
+    pub fn set_deprecated(&mut self, deprecation: Deprecation) {
+        self.deprecation = Some(deprecation);
+    }
+
+    /// Return deprecation info when the template is deprecated.
+    pub fn deprecation(&self) -> Option<&Deprecation> {
+        self.deprecation.as_ref()
+    }
+
+    /// Set team or person responsible for the template.
+    /// The owner is reported with render failures, so alerts can be routed to them.
+    pub fn set_owner(&mut self, owner: String) {
+        self.owner = Some(owner);
+    }
+
+    /// Return owner of the template.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// Set revision of the template in its source (e.g. ETag, row version or commit hash).
+    /// The revision is reported in the `RenderOutcome`, so it is known which revision was used.
+    pub fn set_revision(&mut self, revision: String) {
+        self.revision = Some(revision);
+    }
+
+    /// Return revision of the template in its source.
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// Cache rendered outputs of the template by the `policy`.
+    pub fn set_cache_policy(&mut self, policy: CachePolicy) {
+        self.cache_policy = Some(policy);
+    }
+
+    /// Return caching of rendered outputs when the template is cached.
+    pub fn cache_policy(&self) -> Option<&CachePolicy> {
+        self.cache_policy.as_ref()
+    }
+
+    /// Allow rendering of the template only inside the `window`.
+    pub fn set_validity(&mut self, window: ValidityWindow) {
+        self.validity = Some(window);
+    }
+
+    /// Return time window when the template may be rendered.
+    pub fn validity(&self) -> Option<&ValidityWindow> {
+        self.validity.as_ref()
+    }
+
+    /// Add rule replacing content by its variant, rules are evaluated in order they were added.
+    pub fn add_override(&mut self, rule: OverrideRule) {
+        self.overrides.push(rule);
+    }
+
+    /// Return rules replacing contents by variants.
+    pub fn overrides(&self) -> &[OverrideRule] {
+        &self.overrides
+    }
+
+    /// Label the template with the tag.
+    pub fn add_tag(&mut self, tag: String) {
+        self.tags.insert(tag);
+    }
+
+    /// Return labels of the template.
+    pub fn tags(&self) -> &HashSet<String> {
+        &self.tags
+    }
+
+    /// Set name of the template presented to users speaking the `language` (see `Terarium::display_name()`).
+    pub fn set_display_name(&mut self, language: String, display_name: DisplayName) {
+        self.display_names.insert(language, display_name);
+    }
+
+    /// Return names of the template presented to users by languages.
+    pub fn display_names(&self) -> &HashMap<String, DisplayName> {
+        &self.display_names
+    }
+
+    /// Return notes for translators written in sources of contents as `{# translator: ... #}` comments.
+    pub fn translator_notes(&self) -> Vec<TranslatorNote> {
+        self.contents.iter().flat_map(TranslatorNote::extract).collect()
+    }
+
+    /// Return stable fingerprint of the template contents.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fingerprint::default();
+        for content in self.contents.iter() {
+            hasher.write_str(content.name.as_deref().unwrap_or_default());
+            content.languages.iter().for_each(|l| hasher.write_str(l));
+            if let Some(variant) = content.variant.as_deref() {
+                hasher.write_str(variant);
+            }
+            if let Some(autoescape) = content.autoescape {
+                hasher.write_u8(autoescape as u8);
+            }
+            // the default kind is not hashed, so fingerprints of text contents are the same as before kinds
+            if content.kind != ContentKind::Text {
+                hasher.write_str(&format!("{:?}", content.kind));
+            }
+            hasher.write_str(&content.content);
+        }
+        hasher.finish()
+    }
+
     /// Collect template content settings as Vec
     /// When content has no language, this content is dropped
     pub fn collect_contents(self) -> Vec<Content> {
-        self.contents.into_iter().filter(|c| c.languages.len() > 0).collect()
+        self.contents.into_iter().filter(|c| !c.languages.is_empty()).collect()
+    }
+}
+
+
+impl TryFrom<Vec<Content>> for Template {
+    type Error = TemplateError;
+
+    fn try_from(contents: Vec<Content>) -> Result<Self, Self::Error> {
+        Self::new(contents)
+    }
+}
+
+
+/// Deprecation info of a template or a group.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Deprecation {
+    /// Message describing why the item is deprecated.
+    pub message: String,
+    /// Key of the item which should be used instead.
+    pub replacement: Option<String>,
+}
+
+
+impl Deprecation {
+    /// Create new instance without replacement.
+    pub fn new(message: String) -> Self {
+        Self {
+            message,
+            ..Self::default()
+        }
+    }
+
+    /// Create new instance with replacement key set.
+    pub fn with_replacement(message: String, replacement: String) -> Self {
+        Self {
+            message,
+            replacement: Some(replacement),
+        }
     }
 }
 
 
 /// Errors returned by template operations.
 #[derive(Debug, Error, PartialEq)]
+#[non_exhaustive]
 pub enum TemplateError {
     /// Two contents in the template has same name.
     #[error("Name {0} is used by other template")]
     DuplicatedContentName(String),
 
-    /// Two contents in the template has assigned same language.
+    /// Two contents in the template has assigned same language and variant.
     #[error("Language {0} is used by other template")]
     DuplicatedContentLanguages(String),
 }
 
 
+impl TemplateError {
+    /// Return stable code of the error for machine handling.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DuplicatedContentName(_) => "DUPLICATED_CONTENT_NAME",
+            Self::DuplicatedContentLanguages(_) => "DUPLICATED_CONTENT_LANGUAGES",
+        }
+    }
+}
+
+
 /// Represent content of template
 #[derive(Clone, Default, Debug)]
 pub struct Content {
@@ -87,6 +287,14 @@ pub struct Content {
     /// Name of the content.
     /// The name can be used for referenced for example by {% include %} statement.
     pub name: Option<String>,
+    /// Kind of the content. Some kinds are post-processed after render.
+    pub kind: ContentKind,
+    /// Variant of the content (e.g. `dark` theme or brand name).
+    /// Content without variant is used when no requested variant is available.
+    pub variant: Option<String>,
+    /// Escape values inserted into the content.
+    /// When not set, `Tera` decides by the content name (names ending with `.html`, `.htm` or `.xml` are escaped).
+    pub autoescape: Option<bool>,
 }
 
 
@@ -106,8 +314,79 @@ impl Content {
             content,
             languages,
             name: Some(name),
+            ..Self::default()
         }
     }
+
+    /// Set kind of the content.
+    pub fn with_kind(mut self, kind: ContentKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Enable or disable escaping of values inserted into the content.
+    pub fn with_autoescape(mut self, autoescape: bool) -> Self {
+        self.autoescape = Some(autoescape);
+        self
+    }
+
+    /// Set variant of the content.
+    pub fn with_variant(mut self, variant: String) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+}
+
+
+/// Kind of the content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    /// Plain text.
+    #[default]
+    Text,
+    /// Comma separated values.
+    /// Rendered output is validated to have the same number of fields in each record.
+    Csv,
+    /// HTML document or fragment.
+    Html,
+    /// iCalendar data (RFC 5545).
+    /// Rendered output is folded to 75 octets per line, terminated by CRLF and validated.
+    ICalendar,
+    /// JSON document.
+    Json,
+    /// Markdown document.
+    Markdown,
+    /// Tab separated values.
+    /// Rendered output is validated to have the same number of fields in each record.
+    Tsv,
+    /// vCard data (RFC 6350).
+    VCard,
+    /// XML document.
+    Xml,
+}
+
+
+impl ContentKind {
+    /// Return kind of the content stored in file with the extension (e.g. `html` or `json`).
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "txt" | "text" => Some(Self::Text),
+            "csv" => Some(Self::Csv),
+            "html" | "htm" => Some(Self::Html),
+            "ics" => Some(Self::ICalendar),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            "tsv" => Some(Self::Tsv),
+            "vcf" | "vcard" => Some(Self::VCard),
+            "xml" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    /// Return `true` when values inserted into contents of the kind should be escaped.
+    pub fn escapes_values(&self) -> bool {
+        matches!(self, Self::Html | Self::Xml)
+    }
 }
 
 
@@ -116,7 +395,7 @@ mod tests {
     mod template {
         use std::collections::HashMap;
 
-        use crate::{Content, Template, TemplateError};
+        use crate::{Content, ContentKind, Template, TemplateError};
 
         #[test]
         fn add_content() {
@@ -155,6 +434,16 @@ mod tests {
             assert_eq!(err, TemplateError::DuplicatedContentLanguages("cs".to_owned()));
         }
 
+        #[test]
+        fn add_language_variants() {
+            let mut tpl = empty_template();
+            tpl.add_content(Content::new("foo".to_owned(), vec!["cs".to_owned()])).unwrap();
+            tpl.add_content(Content::new("foo".to_owned(), vec!["cs".to_owned()]).with_variant("dark".to_owned())).unwrap();
+            let result = tpl.add_content(Content::new("foo".to_owned(), vec!["cs".to_owned()]).with_variant("dark".to_owned()));
+
+            assert_eq!(result.err().unwrap(), TemplateError::DuplicatedContentLanguages("cs".to_owned()));
+        }
+
         #[test]
         fn collect_contents() {
             let mut template = empty_template();
@@ -174,6 +463,28 @@ mod tests {
             assert_eq!(languages_by_content["bar bar"], vec!["3".to_owned()]);
         }
 
+        #[test]
+        fn try_from_contents() {
+            let contents = vec![Content::new("a".to_owned(), vec!["en".to_owned()])];
+            assert_eq!(Template::try_from(contents).unwrap().contents().len(), 1);
+            let contents = vec![
+                Content::new("a".to_owned(), vec!["en".to_owned()]),
+                Content::new("b".to_owned(), vec!["en".to_owned()]),
+            ];
+            assert!(matches!(Template::try_from(contents), Err(TemplateError::DuplicatedContentLanguages(_))));
+        }
+
+        #[test]
+        fn fingerprint_depends_on_kind() {
+            let template = |kind: ContentKind| {
+                Template::new(vec![Content::new("a".to_owned(), vec!["en".to_owned()]).with_kind(kind)]).unwrap()
+            };
+            let text = template(ContentKind::Text).fingerprint();
+            assert_eq!(text, template(ContentKind::Text).fingerprint());
+            assert_ne!(text, template(ContentKind::ICalendar).fingerprint());
+            assert_ne!(template(ContentKind::Html).fingerprint(), template(ContentKind::Xml).fingerprint());
+        }
+
         fn empty_template() -> Template {
             Template::default()
         }
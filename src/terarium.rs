@@ -1,12 +1,68 @@
-use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hasher;
+use std::io::Write;
+use std::iter::once;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-use tera::{Context, Error as TeraError};
+use tera::{Context, Error as TeraError, Value};
 use tera::Tera;
 use thiserror::Error;
 
-use crate::Template;
+use crate::{
+    Attachment, AuditAction, AuditEntry, BrandResolver, BufferPool, BuildLimits, CachePolicy, CatalogStats, ChaosMode,
+    ComplianceRule, ContentKind, CsvValidator, DanglingMemberPolicy, DeprecatedKey, Deprecation, Determinism,
+    DirectoryLoader, DisplayName, DocsFormat, EmptyGroupPolicy, FallbackUsage, FlagProvider, GroupHook,
+    HandlebarsPartials, KeyMigration, KeyNormalization, KeyPattern, LeftoverPlaceholder, LinkRewriter, LoaderError,
+    LocalizationEnforcement, LocalizationPolicy, MemberEncoding, MemoryRenderCache, MemoryReport, MissingVariables,
+    OutputIntegrity, OutputNormalization, OutputValidator, OverrideRule, PlaceholderCheck, Profile, QuotaPolicy,
+    RECIPIENT_VARIABLE, RenderCache, RenderCost, RenderFailure, RenderIntegrity, RenderObserver, RenderOutcome,
+    RenderOutput, RenderRequest, RenderSnapshot, RenderTarget, RepeatOptions, ReplayReport, RequiredLanguages,
+    SearchField, SearchMatch, SearchQuery, SourceDialect, SourceStorage, Template, TemplateGroup, TemplateHandle,
+    TextDirection, TraceStep, TranslatorNote, UntranslatedConstruct, ValidityWindow, ValueResolver,
+    resolve_inheritance, to_mime_multipart,
+};
+#[cfg(feature = "async")]
+use crate::Cancellation;
+#[cfg(feature = "pdf")]
+use crate::PdfBackend;
+use crate::analysis::{find_cycle, include_sequence, referenced_variables, required_variables};
+use crate::brand::brand_context;
+use crate::buffers::render_pooled;
+use crate::cache::{Freshness, cache_key};
+use crate::cost::{measure, timed};
+use crate::determinism::register_functions;
+use crate::docs::{TemplateDoc, write_docs};
+use crate::filters::register_filters;
+use crate::flags::feature_function;
+use crate::fingerprint::Fingerprint;
+use crate::ics::finalize_icalendar;
+use crate::overrides::{collect_overrides, record_override};
+use crate::placeholders::find_leftover;
+use crate::preprocess::Preprocessing;
+use crate::profile::inject_watermark;
+use crate::redaction::{redact_text, sensitive_values};
+use crate::resolver::resolve_function;
+use crate::keys::KeyIndex;
+use crate::links::rewrite_links;
+use crate::memo::{memoized, with_memo, without_memo};
+use crate::missing::render_with_markers;
+use crate::scope::{RenderScope, with_current, with_scope, with_updated};
+use crate::schema::{SchemaEntry, openapi_schema};
+use crate::intern::Interner;
+use crate::lookup::LookupMap;
+use crate::selection::{LanguageContents, Selection, TemplateContents};
+use crate::snippets::{SnippetCatalog, snippet_function};
+use crate::storage::StoredSource;
+use crate::stats::LARGEST_TEMPLATES;
+use crate::trace::{collect_trace, is_tracing, start_step, traced};
+
+/// Default maximal number of members of a group rendered concurrently.
+#[cfg(feature = "async")]
+const DEFAULT_GROUP_PARALLELISM: usize = 4;
 
 /// Wrapper over the `Tera` templating engine with capability of template bulk rendering.
 /// Each template can exists in more than one version (support for multi-language templates).
@@ -15,16 +71,133 @@ use crate::Template;
 pub struct Terarium {
     /// Internal Tera template
     tera: Tera,
-    /// Template by template key lookup.
-    template_map: HashMap<String, HashMap<String, String>>,
+    /// Template by template key lookup. Contents of each template are stored by languages.
+    template_map: LookupMap<Arc<str>, Arc<TemplateContents>>,
+    /// Shared language and variant names of contents.
+    interner: Interner,
     /// Group by group key lookup.
-    groups: HashMap<String, HashMap<String, String>>,
+    groups: HashMap<String, TemplateGroup>,
+    /// Deprecation info by template key.
+    template_deprecations: LookupMap<String, Deprecation>,
+    /// Owners by template key.
+    template_owners: HashMap<String, String>,
+    /// Source revisions by template key.
+    template_revisions: HashMap<String, String>,
+    /// Validity windows by template key.
+    template_validities: LookupMap<String, ValidityWindow>,
+    /// Override rules by template key.
+    template_overrides: LookupMap<String, Vec<OverrideRule>>,
+    /// Tags by template key.
+    template_tags: LookupMap<String, HashSet<String>>,
+    /// Cache policies by template key.
+    cache_policies: LookupMap<String, CachePolicy>,
+    /// Notes for translators by template key.
+    translator_notes: HashMap<String, Vec<TranslatorNote>>,
+    /// Names presented to users by template key and language.
+    template_display_names: HashMap<String, HashMap<String, DisplayName>>,
+    /// Values of placeholders in display names.
+    display_variables: HashMap<String, String>,
+    /// Short localized strings used by the `snippet()` template function.
+    snippets: Arc<SnippetCatalog>,
+    /// Cache policies of templates without own policy by tags, the first matching tag wins.
+    tag_cache_policies: Vec<(String, CachePolicy)>,
+    /// Cache of outputs of templates with cache policy.
+    render_cache: Option<Arc<dyn RenderCache>>,
+    /// Quota consulted before renders of requests.
+    quota_policy: Option<Arc<dyn QuotaPolicy>>,
+    /// Tasks re-rendering outputs in background by cache keys.
+    #[cfg(feature = "async")]
+    revalidating: Arc<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    /// The instance itself when it was built by the `TerariumBuilder::build_shared()`.
+    #[cfg(feature = "async")]
+    shared: SharedInstance,
+    /// Observer notified about rendering events.
+    observer: Option<Arc<dyn RenderObserver>>,
+    /// Deprecated templates and groups rendered since the instance was built.
+    deprecated_usage: Arc<Mutex<HashSet<DeprecatedKey>>>,
+    /// Numbers of renders in fallback languages by template key and requested language.
+    fallback_usage: Arc<Mutex<HashMap<(String, String), u64>>>,
+    /// Fingerprints of templates by template key.
+    fingerprints: LookupMap<String, u64>,
+    /// Fingerprint of all templates. Cache keys use it, because outputs depend on included and extended templates.
+    templates_fingerprint: u64,
+    /// Total sizes of contents in bytes by template key.
+    template_sizes: HashMap<String, usize>,
+    /// Sequence used for naming of unnamed contents in the `Tera` instance.
+    next_content_id: u32,
+    /// Record of all runtime mutations of the catalog.
+    audit_log: Vec<AuditEntry>,
+    /// Lookup of template keys by their normalized forms.
+    template_keys: KeyIndex,
+    /// Lookup of group keys by their normalized forms.
+    group_keys: KeyIndex,
+    /// How sources of contents are kept.
+    source_storage: SourceStorage,
+    /// Kept sources by names of contents in the `Tera` instance.
+    sources: HashMap<String, StoredSource>,
+    /// Content kinds by names of contents in the `Tera` instance.
+    content_kinds: LookupMap<String, ContentKind>,
+    /// Validators of rendered outputs by content kinds.
+    validators: HashMap<ContentKind, Vec<Arc<dyn OutputValidator>>>,
+    /// Resolver of brands used by requests with brand id.
+    brand_resolver: Option<Arc<dyn BrandResolver>>,
+    /// Normalization applied to rendered outputs.
+    output_normalization: Option<OutputNormalization>,
+    /// Handling of nondeterministic template functions.
+    determinism: Determinism,
+    /// Each content is rendered twice and outputs are compared when set.
+    verify_determinism: bool,
+    /// Handling of template syntax left in outputs.
+    placeholder_check: PlaceholderCheck,
+    /// Rendering of variables missing in contexts.
+    missing_variables: MissingVariables,
+    /// Environment the instance runs in.
+    profile: Profile,
+    /// Outputs of templates with cache policy are not cached when set.
+    caching_disabled: bool,
+    /// Banner injected into HTML outputs in non-production profiles.
+    watermark: Option<String>,
+    /// Synthetic failures and delays of renders in non-production profiles.
+    chaos: Option<ChaosMode>,
+    /// Limits checked when templates are added.
+    limits: BuildLimits,
+    /// Languages templates must have content in, checked when templates are added.
+    localization: LocalizationPolicy,
+    /// Translations of sources of added templates.
+    preprocessing: Preprocessing,
+    /// Constructs of sources which cannot be translated by the preprocessing by template keys.
+    untranslated: BTreeMap<String, Vec<UntranslatedConstruct>>,
+    /// Sizing of the pool of output buffers.
+    buffer_pool: BufferPool,
+    /// Languages written right to left.
+    rtl_languages: HashSet<String>,
+    /// Canonical languages of the catalog by their aliases.
+    language_aliases: HashMap<String, String>,
+    /// Time spent by the build of the instance.
+    build_time: Duration,
+    /// Hooks invoked with rendered members of groups.
+    group_hooks: Vec<Arc<dyn GroupHook>>,
+    /// Outputs of templates are memoized during group renders when set.
+    deduplicate_members: bool,
+    /// Handling of group members referencing removed templates.
+    dangling_member_policy: DanglingMemberPolicy,
+    /// Handling of groups left without members by removed templates.
+    empty_group_policy: EmptyGroupPolicy,
+    /// Contexts of groups are checked for variables required by all members before rendering when set.
+    validate_group_context: bool,
+    /// Maximal number of members of a group rendered concurrently by the `render_group_async()`.
+    #[cfg(feature = "async")]
+    group_parallelism: usize,
+    /// Rewriter of links in HTML outputs.
+    link_rewriter: Option<Arc<dyn LinkRewriter>>,
+    /// Rules checked on outputs of tagged templates.
+    compliance_rules: Vec<ComplianceRule>,
 }
 
 impl Terarium {
     /// Render single template identified by its key.
     /// The `Tera` context is accepted for rendering.
-    pub fn render_template<K: ?Sized, LK: ?Sized>(
+    pub fn render_template<K, LK>(
         &self,
         context: &Context,
         template_key: &K,
@@ -32,357 +205,5007 @@ impl Terarium {
         fallback_language: Option<&LK>,
     ) -> Result<String, TerariumError>
         where
-            String: Borrow<K>,
-            String: Borrow<LK>,
-            K: Hash + Eq,
-            LK: Hash + Eq,
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
     {
-        let template = self
-            .template_map.get(template_key).ok_or_else(|| TerariumError::TemplateNotFound)?;
-        let content_key = template
-            .get(language)
-            .or_else(|| {
-                fallback_language.map(|k| template.get(k)).flatten()
-            })
-            .ok_or_else(|| TerariumError::LanguageNotFound)?;
-        Ok(self.tera.render(content_key.as_str(), context)?)
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        self.render_template_with_selection(context, template_key.as_ref(), &selection)
+    }
+
+    /// Render single template and write the output into the `writer`.
+    /// The output buffer is returned to the per-thread pool (see `TerariumBuilder::set_buffer_pool()`) and reused by
+    /// next renders on the same thread.
+    pub fn render_template_to<K, LK, W>(
+        &self,
+        context: &Context,
+        template_key: &K,
+        language: &LK,
+        fallback_language: Option<&LK>,
+        mut writer: W,
+    ) -> Result<(), TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+            W: Write,
+    {
+        let output = self.render_template(context, template_key, language, fallback_language)?;
+        writer.write_all(output.as_bytes()).map_err(TerariumError::WriteFailed)?;
+        self.buffer_pool.recycle(output.into_bytes());
+        Ok(())
+    }
+
+    /// Return handle of the template for repeated rendering by the `render_template_by_handle()`.
+    pub fn template_handle(&self, template_key: &str) -> Option<TemplateHandle> {
+        let (key, contents) = self.template_map.get_key_value(self.template_keys.resolve(template_key))?;
+        Some(TemplateHandle { key: key.clone(), contents: contents.clone() })
+    }
+
+    /// Render single template identified by its handle (see `template_handle()`).
+    pub fn render_template_by_handle<LK>(
+        &self,
+        context: &Context,
+        handle: &TemplateHandle,
+        language: &LK,
+        fallback_language: Option<&LK>,
+    ) -> Result<String, TerariumError>
+        where
+            LK: AsRef<str> + ?Sized,
+    {
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        self.render_resolved_template(context, &handle.key, &handle.contents, &selection)
+    }
+
+    /// Render single template in the content variant (e.g. `dark` theme).
+    /// Content without variant is used when the template has no content of the `variant` in the language.
+    /// The fallback language is used only when the template has no content in the language at all, so the content
+    /// without variant in the language wins over the `variant` in the fallback language.
+    pub fn render_template_variant<K, LK>(
+        &self,
+        context: &Context,
+        template_key: &K,
+        language: &LK,
+        variant: &str,
+        fallback_language: Option<&LK>,
+    ) -> Result<String, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+    {
+        let mut selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        selection.variants.push(variant);
+        self.render_template_with_selection(context, template_key.as_ref(), &selection)
     }
 
     /// Render template group.
     /// Result is HashMap where keys are member names and values are rendered templates.
-    pub fn render_group<K: ?Sized, LK: ?Sized>(
+    pub fn render_group<K, LK>(
+        &self,
+        context: &Context,
+        group_key: &K,
+        language: &LK,
+        fallback_language: Option<&LK>,
+    ) -> Result<HashMap<String, String>, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+    {
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        self.render_group_with_selection(context, group_key.as_ref(), &selection)
+    }
+
+    /// Render template group in the content variant.
+    /// Variant is selected for each member the same way as in the `render_template_variant()`.
+    pub fn render_group_variant<K, LK>(
         &self,
         context: &Context,
         group_key: &K,
         language: &LK,
+        variant: &str,
         fallback_language: Option<&LK>,
     ) -> Result<HashMap<String, String>, TerariumError>
         where
-            String: Borrow<K>,
-            String: Borrow<LK>,
-            K: Hash + Eq,
-            LK: Hash + Eq,
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+    {
+        let mut selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        selection.variants.push(variant);
+        self.render_group_with_selection(context, group_key.as_ref(), &selection)
+    }
+
+    /// Render single template in each of the `languages` the template has content in.
+    /// Languages without content are skipped, no fallback language is used.
+    /// The `override_context` is called with the language and a copy of the `context` before each render,
+    /// so language specific values (e.g. formatted prices) can be set.
+    /// Result is HashMap where keys are languages and values are rendered templates.
+    pub fn render_template_multilingual<K, LK, F>(
+        &self,
+        context: &Context,
+        template_key: &K,
+        languages: &[&LK],
+        mut override_context: F,
+    ) -> Result<HashMap<String, String>, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+            F: FnMut(&str, &mut Context),
     {
-        let group = self.groups.get(group_key).ok_or_else(|| TerariumError::GroupNotFound)?;
+        let template_key = self.template_keys.resolve(template_key.as_ref());
+        let template = self.template_map.get(template_key).ok_or_else(|| TerariumError::TemplateNotFound)?;
         let mut result = HashMap::<String, String>::new();
 
-        for (member_key, template_key) in group.iter() {
-            let content = self.render_template(context, template_key, language, fallback_language)?;
-            result.insert(member_key.clone(), content);
+        for language in languages.iter().map(|l| l.as_ref()).filter(|l| template.contains_key(*l)) {
+            let mut language_context = context.clone();
+            override_context(language, &mut language_context);
+            let selection = Selection::new(language, None);
+            let content = self.render_template_with_selection(&language_context, template_key, &selection)?;
+            result.insert(language.to_owned(), content);
         }
 
         Ok(result)
     }
-}
 
+    /// Render all templates with keys matching the `pattern`.
+    /// The pattern is a key prefix or a glob (see `KeyPattern`).
+    /// Result is HashMap where keys are template keys and values are rendered templates.
+    pub fn render_matching<LK>(
+        &self,
+        context: &Context,
+        pattern: impl Into<KeyPattern>,
+        language: &LK,
+        fallback_language: Option<&LK>,
+    ) -> Result<HashMap<String, String>, TerariumError>
+        where
+            LK: AsRef<str> + ?Sized,
+    {
+        let pattern = pattern.into();
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        let mut result = HashMap::<String, String>::new();
 
-/// Errors returned by `Terarium` operations.
-#[derive(Debug, Error)]
-pub enum TerariumError {
-    /// Requested template was not found.
-    #[error("There is no template")]
-    TemplateNotFound,
-    /// Request language was not found for template.
-    #[error("Language not found")]
-    LanguageNotFound,
-    /// Requested group was not found.
-    #[error("There is no group")]
-    GroupNotFound,
+        for template_key in self.template_map.keys().filter(|k| pattern.matches(k)) {
+            let content = self.render_template_with_selection(context, template_key, &selection)?;
+            result.insert(template_key.to_string(), content);
+        }
 
-    /// Error propagated from underlying `Tera` instance.
-    #[error("Error when rendering template")]
-    RenderingFailed(TeraError),
-}
+        Ok(result)
+    }
+
+    /// Render the template once for each item and join results by the separator.
+    /// Each item context is merged into the shared `context`. Header and footer are rendered with the shared context.
+    pub fn render_repeated<K, LK, I>(
+        &self,
+        context: &Context,
+        template_key: &K,
+        items: I,
+        language: &LK,
+        fallback_language: Option<&LK>,
+        options: &RepeatOptions,
+    ) -> Result<String, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+            I: IntoIterator<Item=Context>,
+    {
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        let mut parts = Vec::<String>::new();
 
+        for item in items {
+            let mut item_context = context.clone();
+            item_context.extend(item);
+            parts.push(self.render_template_with_selection(&item_context, template_key.as_ref(), &selection)?);
+        }
 
-impl From<TeraError> for TerariumError {
-    fn from(value: TeraError) -> Self {
-        Self::RenderingFailed(value)
+        let mut result = String::new();
+        if let Some(header) = &options.header {
+            result.push_str(&self.render_template_with_selection(context, header, &selection)?);
+        }
+        result.push_str(&parts.join(&options.separator));
+        if let Some(footer) = &options.footer {
+            result.push_str(&self.render_template_with_selection(context, footer, &selection)?);
+        }
+        Ok(result)
     }
-}
-
 
-/// Build the `Terarium` instance.
-#[derive(Default)]
-pub struct TerariumBuilder {
-    templates: HashMap<String, Template>,
-    groups: HashMap<String, HashMap<String, String>>,
-}
+    /// Render group members in the `order` and concatenate them into one string joined by the `separator`.
+    /// Members missing in the `order` are not rendered. Group hooks, header encoding and transfer encodings of members
+    /// are not applied.
+    pub fn render_group_concat<K, LK>(
+        &self,
+        context: &Context,
+        group_key: &K,
+        order: &[&str],
+        separator: &str,
+        language: &LK,
+        fallback_language: Option<&LK>,
+    ) -> Result<String, TerariumError>
+        where
+            K: AsRef<str> + ?Sized,
+            LK: AsRef<str> + ?Sized,
+    {
+        let selection = Selection::new(language.as_ref(), fallback_language.map(|l| l.as_ref()));
+        let group = self.find_group(group_key.as_ref())?;
+        let mut parts = Vec::<String>::with_capacity(order.len());
 
+        let members = self.group_members(group);
+        for member_key in order {
+            let template_key = members
+                .get(*member_key)
+                .ok_or_else(|| TerariumError::MemberNotFound(member_key.to_string()))?;
+            parts.push(self.render_template_with_selection(context, template_key, &selection)?);
+        }
 
-impl TerariumBuilder {
-    /// Add new template to the new instance.
-    /// If template exist, it will be replaced
-    pub fn add_template(&mut self, key: String, template: Template) -> Result<(), TerariumBuilderError> {
-        self.templates.insert(key.clone(), template);
-        Ok(())
+        Ok(parts.join(separator))
     }
 
-    /// Add new group into new instance
-    /// If group with same name exists, it is replaced.
-    pub fn add_group(&mut self, key: String, group: HashMap<String, String>) -> Result<(), TerariumBuilderError> {
-        // Check templates exist
-        for (_, tpl_name) in group.iter() {
-            if !self.templates.contains_key(tpl_name) {
-                return Err(TerariumBuilderError::TemplateNotFound(tpl_name.to_owned()));
+    /// Render template or group described by the request.
+    /// The quota of the request namespace is checked before the render (see `TerariumBuilder::set_quota_policy()`).
+    pub fn render(&self, request: &RenderRequest) -> Result<RenderOutcome, TerariumError> {
+        let namespace = request.namespace().unwrap_or_default();
+        if let Some(quota) = &self.quota_policy {
+            quota.check(namespace).map_err(|limit| TerariumError::QuotaExceeded(namespace.to_owned(), limit))?;
+        }
+        if let Some(expected) = request.expected_identity() {
+            let language = request.languages().first().map(|l| l.as_str()).unwrap_or_default();
+            let identity = self.identity(request.target(), language).unwrap_or_default();
+            if identity != expected {
+                return Err(TerariumError::CatalogVersionMismatch(expected.to_owned(), identity));
+            }
+        }
+        let selection = Selection::from_request(request);
+        let context = self.request_context(request);
+        let scope = RenderScope {
+            timezone: request.timezone().map(|tz| tz.to_owned()),
+            locale: request.locale().map(|locale| locale.to_owned()),
+            missing_variables: request.missing_variables(),
+            requested_seed: request.random_seed(),
+            time: request.time(),
+            deadline: request.deadline(),
+            template_overrides: Arc::new(request.template_overrides().clone()),
+            bypass_cache: request.cache_bypass(),
+            ..RenderScope::default()
+        };
+        let render = || -> Result<_, TerariumError> {
+            Ok(match request.target() {
+                RenderTarget::Template(key) => RenderOutput::Template(
+                    self.render_template_with_selection(&context, key, &selection)?
+                ),
+                RenderTarget::Group(key) => RenderOutput::Group(
+                    self.render_group_with_selection(&context, key, &selection)?
+                ),
+            })
+        };
+        let render = || match request.trace() {
+            true => {
+                let (output, mut trace) = collect_trace(render);
+                trace.redact(&context);
+                (output, Some(trace))
             }
+            false => (render(), None),
+        };
+        let (((output, trace), overrides), cost) = measure(|| collect_overrides(|| with_scope(scope, render)));
+        let output = output?;
+        if let Some(limit) = request.output_limit().filter(|limit| output.len() > *limit) {
+            return Err(TerariumError::OutputTooLarge(output.len(), limit));
         }
+        let cost = RenderCost { output_bytes: output.len(), ..cost };
+        if let Some(quota) = &self.quota_policy {
+            quota.record(namespace, &cost);
+        }
+        let cost = request.cost().then_some(cost);
+        let (output, attachments) = self.split_attachments(request.target(), &context, output)?;
+        let integrity = match request.integrity() {
+            true => Some(self.render_integrity(request.target(), &output)),
+            false => None,
+        };
+        let revisions = self.render_revisions(request.target());
+        let encodings = self.render_encodings(request.target(), &output);
+        Ok(RenderOutcome { output, integrity, revisions, cost, overrides, trace, encodings, attachments })
+    }
 
-        // Add group to lookup
-        self.groups.insert(key, group);
-        Ok(())
+    /// Capture the failed render, so it can be replayed later by the `replay()`.
+    /// Sensitive values of the context are masked in the snapshot.
+    pub fn snapshot(&self, request: &RenderRequest, error: &TerariumError) -> RenderSnapshot {
+        RenderSnapshot {
+            request: request.redacted(),
+            error: redact_text(&error_chain(error), &sensitive_values(request.context())),
+            catalog_fingerprint: self.fingerprint(),
+        }
     }
 
-    /// Build new `Terarium` instance based on stored templates and groups.
-    pub fn build(self) -> Result<Terarium, TerariumBuilderError> {
-        let mut instance = Terarium::default();
-        let mut tera_template_id: u32 = 1;
-
-        // build templates
-        self.templates.into_iter().try_for_each(|(template_key, template)| {
-            template.collect_contents().into_iter().try_for_each(|content| {
-                let template_name = content.name.unwrap_or_else(|| format!("template#{}", tera_template_id));
-                tera_template_id += 1;
-                instance.tera.add_raw_template(&template_name, &content.content)?;
-
-                content.languages.into_iter().for_each(|language_key| {
-                    instance
-                        .template_map
-                        .entry(template_key.clone())
-                        .or_default()
-                        .insert(language_key.clone(), template_name.clone());
-                });
+    /// Render the captured request against the current catalog.
+    /// Intended for verifying template hotfixes against real failure data.
+    pub fn replay(&self, snapshot: &RenderSnapshot) -> ReplayReport {
+        ReplayReport {
+            result: self.render(&snapshot.request),
+            catalog_changed: snapshot.catalog_fingerprint != self.fingerprint(),
+        }
+    }
 
-                Ok::<_, TerariumBuilderError>(())
-            })?;
-            Ok::<_, TerariumBuilderError>(())
-        })?;
+    /// Render group described by the request and convert its HTML member into PDF document.
+    /// Return rendered members together with the PDF bytes.
+    #[cfg(feature = "pdf")]
+    pub fn render_group_pdf(
+        &self,
+        request: &RenderRequest,
+        html_member: &str,
+        backend: &dyn PdfBackend,
+    ) -> Result<(HashMap<String, String>, Vec<u8>), TerariumError> {
+        let RenderOutput::Group(members) = self.render(request)?.output else {
+            return Err(TerariumError::NotAGroup);
+        };
+        let html = members.get(html_member).ok_or_else(|| TerariumError::MemberNotFound(html_member.to_owned()))?;
+        let pdf = backend.convert(html)?;
+        Ok((members, pdf))
+    }
 
-        instance.groups = self.groups;
-        Ok(instance)
+    /// Render group described by the request and assemble its `subject`, `text` and `html` members into a raw
+    /// multipart e-mail message (see `to_mime_multipart()`).
+    pub fn render_group_mime(&self, request: &RenderRequest) -> Result<String, TerariumError> {
+        match self.render(request)?.output {
+            RenderOutput::Group(members) => to_mime_multipart(&members),
+            RenderOutput::Template(_) => Err(TerariumError::NotAGroup),
+        }
     }
-}
 
+    /// Get group defined by the `key`.
+    /// Return `None` if no group defined by the `key` is found.
+    pub fn group(&self, key: &str) -> Option<&TemplateGroup> {
+        self.groups.get(self.group_keys.resolve(key))
+    }
 
-/// Simplify building template groups.
-#[derive(Clone, Default)]
-pub struct TemplateGroupBuilder {
-    group: HashMap<String, String>,
-}
+    /// Return keys of deprecated templates and groups rendered since the instance was built.
+    pub fn deprecated_usage(&self) -> Vec<DeprecatedKey> {
+        let mut usage: Vec<_> = self.deprecated_usage
+            .lock()
+            .map(|usage| usage.iter().cloned().collect())
+            .unwrap_or_default();
+        usage.sort();
+        usage
+    }
 
-impl TemplateGroupBuilder {
-    /// Add new member to group.
-    pub fn add_member(mut self, member_key: String, template_key: String) -> Self {
-        self.group.insert(member_key, template_key);
-        self
+    /// Return numbers of renders in fallback languages since the instance was built, the most frequent first.
+    /// Each entry is a template missing content in the requested language, so missing translations can be
+    /// prioritized by real traffic.
+    pub fn fallback_stats(&self) -> Vec<FallbackUsage> {
+        let mut stats: Vec<_> = self.fallback_usage
+            .lock()
+            .map(|usage| {
+                usage
+                    .iter()
+                    .map(|((template_key, requested_language), hits)| FallbackUsage {
+                        template_key: template_key.clone(),
+                        requested_language: requested_language.clone(),
+                        hits: *hits,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        stats.sort_by(|a, b| {
+            b.hits.cmp(&a.hits)
+                .then_with(|| a.template_key.cmp(&b.template_key))
+                .then_with(|| a.requested_language.cmp(&b.requested_language))
+        });
+        stats
     }
 
-    /// Build the group spec.
-    pub fn build(self) -> HashMap<String, String> {
-        self.group
+    /// Compute integrity of the rendered output.
+    fn render_integrity(&self, target: &RenderTarget, output: &RenderOutput) -> RenderIntegrity {
+        let integrity = |template_key: &str, output: &str| {
+            let template_key = self.template_keys.resolve(template_key);
+            let fingerprint = self.fingerprints.get(template_key).copied().unwrap_or_default();
+            OutputIntegrity::new(template_key.to_owned(), fingerprint, output)
+        };
+        let outputs = match (target, output) {
+            (RenderTarget::Template(key), RenderOutput::Template(output)) => {
+                HashMap::from([(key.clone(), integrity(key, output))])
+            }
+            (RenderTarget::Group(key), RenderOutput::Group(members)) => {
+                let group_members = self.group(key).map(|group| self.group_members(group)).unwrap_or_default();
+                members
+                    .iter()
+                    .filter_map(|(member_key, output)| {
+                        let template_key = group_members.get(member_key.as_str())?;
+                        Some((member_key.clone(), integrity(template_key, output)))
+                    })
+                    .collect()
+            }
+            _ => HashMap::new(),
+        };
+        RenderIntegrity { catalog_fingerprint: self.fingerprint(), outputs }
     }
-}
 
+    /// Return source revisions of templates rendered for the target.
+    fn render_revisions(&self, target: &RenderTarget) -> HashMap<String, String> {
+        let template_keys: Vec<&str> = match target {
+            RenderTarget::Template(key) => vec![self.template_keys.resolve(key)],
+            RenderTarget::Group(key) => self.group(self.group_keys.resolve(key))
+                .map(|group| self.group_members(group).into_values().collect())
+                .unwrap_or_default(),
+        };
+        template_keys
+            .into_iter()
+            .filter_map(|key| self.template_revisions.get_key_value(key))
+            .map(|(key, revision)| (key.clone(), revision.clone()))
+            .collect()
+    }
 
-/// Errors returned by `TerariumBuilder` struct.
-#[derive(Debug, Error)]
-pub enum TerariumBuilderError {
-    /// Cannot build template in underlying `Tera` instance.
-    #[error("Unable to build template")]
-    TemplateBuildingError(TeraError),
-    /// Template was not found (when building group).
-    #[error("Cannot build template groups - some templates are missing")]
-    TemplateNotFound(String),
-}
+    /// Return encodings of group members present in the output.
+    fn render_encodings(&self, target: &RenderTarget, output: &RenderOutput) -> HashMap<String, MemberEncoding> {
+        let (RenderTarget::Group(key), RenderOutput::Group(members)) = (target, output) else {
+            return HashMap::new();
+        };
+        self.group(key)
+            .map(|group| group.member_encodings.iter()
+                .filter(|(member_key, _)| members.contains_key(*member_key))
+                .map(|(member_key, encoding)| (member_key.clone(), encoding.clone()))
+                .collect())
+            .unwrap_or_default()
+    }
 
+    /// Move outputs of attachment members from the group output to attachments with rendered file names.
+    fn split_attachments(
+        &self,
+        target: &RenderTarget,
+        context: &Context,
+        mut output: RenderOutput,
+    ) -> Result<(RenderOutput, Vec<Attachment>), TerariumError> {
+        let (RenderTarget::Group(key), RenderOutput::Group(members)) = (target, &mut output) else {
+            return Ok((output, Vec::new()));
+        };
+        let mut attachments = Vec::new();
+        for (member_key, attachment) in self.group(key).into_iter().flat_map(|group| group.attachments.iter()) {
+            let Some(data) = members.remove(member_key) else { continue };
+            let filename = Tera::one_off(&attachment.filename, context, false)
+                .map_err(|error| TerariumError::RenderingFailed(member_key.clone(), error))?;
+            attachments.push(Attachment {
+                member_key: member_key.clone(),
+                filename,
+                mime_type: attachment.mime_type.clone(),
+                data: data.into_bytes(),
+            });
+        }
+        attachments.sort_by(|a, b| a.member_key.cmp(&b.member_key));
+        Ok((output, attachments))
+    }
 
-impl From<TeraError> for TerariumBuilderError {
-    fn from(value: TeraError) -> Self {
-        Self::TemplateBuildingError(value)
+    /// Build render context of the request.
+    /// Brand globals are merged and `now`, `tz` and `locale` values are injected when the request has timezone
+    /// or locale. Values already present in the context are not overridden.
+    fn request_context<'a>(&self, request: &'a RenderRequest) -> Cow<'a, Context> {
+        let mut context = match (request.brand(), &self.brand_resolver) {
+            (Some(brand_id), Some(resolver)) => Cow::Owned(brand_context(resolver.as_ref(), brand_id, request.context())),
+            _ => Cow::Borrowed(request.context()),
+        };
+        if request.timezone().is_none() && request.locale().is_none() {
+            return context;
+        }
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let values = [
+            ("now", Some(Value::from(now))),
+            ("tz", request.timezone().map(Value::from)),
+            ("locale", request.locale().map(Value::from)),
+        ];
+        for (key, value) in values {
+            if let Some(value) = value.filter(|_| !context.contains_key(key)) {
+                context.to_mut().insert(key, &value);
+            }
+        }
+        context
     }
-}
 
+    /// Render single template with the best content matching the `selection`.
+    fn render_template_with_selection(
+        &self,
+        context: &Context,
+        template_key: &str,
+        selection: &Selection,
+    ) -> Result<String, TerariumError> {
+        let replacement = with_current(|scope| scope.and_then(|s| s.template_overrides.get(template_key).cloned()));
+        let template_key = self.template_keys.resolve(replacement.as_deref().unwrap_or(template_key));
+        let template = self
+            .template_map.get(template_key).ok_or_else(|| TerariumError::TemplateNotFound)?;
+        self.render_resolved_template(context, template_key, template, selection)
+    }
 
-/// Additional methods for testing
-#[cfg(test)]
-impl TerariumBuilder {
-    /// Get template defined by its `key`.
-    /// If no template defined by given `key` exist, return `None`.
-    pub fn get_template(&mut self, key: &String) -> Option<&mut Template> {
-        self.templates.get_mut(key)
+    /// Render the template with resolved key and contents with the best content matching the `selection`.
+    fn render_resolved_template(
+        &self,
+        context: &Context,
+        template_key: &str,
+        template: &TemplateContents,
+        selection: &Selection,
+    ) -> Result<String, TerariumError> {
+        if let Some((key, deprecation)) = self.template_deprecations.get_key_value(template_key) {
+            self.report_deprecated_usage(DeprecatedKey::Template(key.clone()), deprecation);
+        }
+        if let Some(chaos) = &self.chaos {
+            chaos.apply(template_key)?;
+        }
+        if let Some(window) = self.template_validities.get(template_key).filter(|w| !w.contains(SystemTime::now())) {
+            return match &window.fallback {
+                Some(fallback) if fallback != template_key => {
+                    self.render_template_with_selection(context, fallback, selection)
+                }
+                _ => Err(TerariumError::OutsideValidity(template_key.to_owned())),
+            };
+        }
+        let aliased;
+        let selection = match self.language_aliases.is_empty() {
+            true => selection,
+            false => {
+                aliased = selection.aliased(&self.language_aliases);
+                &aliased
+            }
+        };
+        let (language, content_key) = selection.find(template).ok_or_else(|| TerariumError::LanguageNotFound)?;
+        if let Some(requested) = selection.languages.first().filter(|requested| **requested != language) {
+            self.report_fallback_usage(template_key, requested);
+        }
+        let content_key = self.override_content(template_key, &template[language], language, content_key);
+        let bypass_cache = self.caching_disabled || with_current(|scope| scope.is_some_and(|s| s.bypass_cache));
+        let render = || match self.cache_policy(template_key).filter(|_| !bypass_cache) {
+            Some(policy) => self.render_cached(policy, context, template_key, language, content_key),
+            None => self.render_content(context, template_key, language, content_key),
+        };
+        let output = memoized(template_key, language, || match self.snippets.is_empty() {
+            true => render(),
+            false => with_updated(
+                |scope| scope.languages = selection.languages.iter().map(|l| l.to_string()).collect(),
+                render,
+            ),
+        });
+        let output = output.and_then(|output| {
+            self.check_compliance(context, template_key, content_key, &output)?;
+            Ok(output)
+        });
+        output.map_err(|error| {
+                self.report_render_failure(context, template_key, language, &error);
+                error
+            })
     }
 
-    /// Remove template defined by the `key` from the builder and return it.
-    /// Returns `None` if no template with given `key` is defined.
-    pub fn remove_template(&mut self, key: &String) -> Option<Template> {
-        self.templates.remove(key)
+    /// Return cached output of the content or render it and store it in the cache.
+    fn render_cached(
+        &self,
+        policy: &CachePolicy,
+        context: &Context,
+        template_key: &str,
+        language: &str,
+        content_key: &str,
+    ) -> Result<String, TerariumError> {
+        let Some(cache) = &self.render_cache else {
+            return self.render_content(context, template_key, language, content_key);
+        };
+        let key = cache_key(self.templates_fingerprint, content_key, language, context);
+        if let Some(cached) = cache.get(&key) {
+            match policy.freshness(cached.stored_at) {
+                Freshness::Fresh => return Ok(cached.output),
+                Freshness::Stale if self.revalidate(policy, context, template_key, language, content_key, &key) => {
+                    return Ok(cached.output);
+                }
+                _ => {}
+            }
+        }
+        self.render_to_cache(cache.as_ref(), policy, context, template_key, language, content_key, &key)
     }
 
-    /// Get group defined by the `key`.
-    /// Return `None` if no group defined by the `key` is found.
-    pub fn get_group(&mut self, key: &String) -> Option<&mut HashMap<String, String>> {
-        self.groups.get_mut(key)
+    /// Render the content and store the output in the cache under the key.
+    #[allow(clippy::too_many_arguments)]
+    fn render_to_cache(
+        &self,
+        cache: &dyn RenderCache,
+        policy: &CachePolicy,
+        context: &Context,
+        template_key: &str,
+        language: &str,
+        content_key: &str,
+        key: &str,
+    ) -> Result<String, TerariumError> {
+        let output = self.render_content(context, template_key, language, content_key)?;
+        let tags = policy.tags()
+            .iter()
+            .map(|tag| Tera::one_off(tag, context, false))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| TerariumError::RenderingFailed(template_key.to_owned(), error))?;
+        cache.put(key, &output, &tags, policy.storage_ttl());
+        Ok(output)
     }
 
-    /// Remove group defined by the `key` from the builder and return it.
-    /// Returns `None` if no group with given `key` is defined.
-    pub fn remove_group(&mut self, key: &String) -> Option<HashMap<String, String>> {
-        self.groups.remove(key)
+    /// Re-render the stale output in background of the current Tokio runtime.
+    /// Return `false` when there is no runtime or the instance was not built by the `TerariumBuilder::build_shared()`,
+    /// so the output must be re-rendered immediately.
+    #[cfg(feature = "async")]
+    fn revalidate(
+        &self,
+        policy: &CachePolicy,
+        context: &Context,
+        template_key: &str,
+        language: &str,
+        content_key: &str,
+        key: &str,
+    ) -> bool {
+        let (Ok(runtime), Some(cache)) = (tokio::runtime::Handle::try_current(), self.render_cache.clone()) else {
+            return false;
+        };
+        let Some(instance) = self.shared.0.upgrade() else {
+            return false;
+        };
+        // the task removes itself after it is added
+        let mut revalidating = self.revalidating.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        // output already being re-rendered is served stale
+        if revalidating.contains_key(key) {
+            return true;
+        }
+        let scope = crate::scope::with_current(|scope| scope.cloned()).unwrap_or_default();
+        let (policy, context) = (policy.clone(), context.clone());
+        let (template_key, language) = (template_key.to_owned(), language.to_owned());
+        let (content_key, task_key) = (content_key.to_owned(), key.to_owned());
+        let task = runtime.spawn_blocking(move || {
+            let _ = with_scope(scope, || instance.render_to_cache(
+                cache.as_ref(), &policy, &context, &template_key, &language, &content_key, &task_key,
+            ));
+            instance.revalidating.lock().unwrap_or_else(std::sync::PoisonError::into_inner).remove(&task_key);
+        });
+        revalidating.insert(key.to_owned(), task);
+        true
     }
-}
 
+    /// Stale outputs are always re-rendered immediately without the `async` feature.
+    #[cfg(not(feature = "async"))]
+    fn revalidate(&self, _: &CachePolicy, _: &Context, _: &str, _: &str, _: &str, _: &str) -> bool {
+        false
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Render the content of the template in the language, check determinism and post-process the output.
+    /// Direction of the language is available as `dir` and `is_rtl` unless the context sets them.
+    fn render_content(
+        &self,
+        context: &Context,
+        template_key: &str,
+        language: &str,
+        content_key: &str,
+    ) -> Result<String, TerariumError> {
+        check_deadline()?;
+        if is_tracing() {
+            start_step(TraceStep {
+                template_key: template_key.to_owned(),
+                language: language.to_owned(),
+                includes: include_sequence(&self.tera, content_key),
+                variables: referenced_variables(&self.tera, content_key),
+                ..TraceStep::default()
+            });
+        }
+        let direction = self.language_direction(language);
+        let mut context = Cow::Borrowed(context);
+        if !context.contains_key("dir") {
+            context.to_mut().insert("dir", direction.as_str());
+        }
+        if !context.contains_key("is_rtl") {
+            context.to_mut().insert("is_rtl", &(direction == TextDirection::Rtl));
+        }
+        let context = context.as_ref();
+        let missing_variables = with_current(|scope| scope.and_then(|s| s.missing_variables))
+            .unwrap_or(self.missing_variables);
+        let render_tera = || match missing_variables {
+            MissingVariables::Error => render_pooled(&self.buffer_pool, &self.tera, content_key, context),
+            MissingVariables::Marker => render_with_markers(&self.tera, content_key, context),
+        };
+        let render = || with_updated(
+            |scope| {
+                scope.language = Some(language.to_owned());
+                scope.recipient = context.get(RECIPIENT_VARIABLE).cloned();
+                if self.determinism == Determinism::Replace {
+                    scope.now = context.get("now").cloned();
+                    scope.random_seed = scope.requested_seed
+                        .or_else(|| context.get("random_seed").and_then(|s| s.as_u64()))
+                        .unwrap_or_default();
+                }
+            },
+            || timed(|cost, time| cost.tera_time += time, || traced(|step, time| step.tera_time += time, render_tera)),
+        );
+        let failed = |error| TerariumError::RenderingFailed(template_key.to_owned(), error);
+        let output = render().map_err(failed)?;
+        if self.verify_determinism && render().map_err(failed)? != output {
+            return Err(TerariumError::NondeterministicOutput(template_key.to_owned()));
+        }
+        check_deadline()?;
+        let output = timed(
+            |cost, time| cost.postprocess_time += time,
+            || traced(|step, time| step.postprocess_time += time, || self.post_process(content_key, output)),
+        )?;
+        self.check_placeholders(template_key, language, &output)?;
+        Ok(output)
+    }
 
-    mod terarium_builder {
-        use crate::Content;
+    /// Scan the output for template syntax by the placeholder check.
+    fn check_placeholders(&self, template_key: &str, language: &str, output: &str) -> Result<(), TerariumError> {
+        if self.placeholder_check == PlaceholderCheck::Off {
+            return Ok(());
+        }
+        let Some((line, snippet)) = find_leftover(output) else { return Ok(()) };
+        match self.placeholder_check {
+            PlaceholderCheck::Reject => return Err(TerariumError::LeftoverPlaceholder(template_key.to_owned(), line)),
+            _ => if let Some(observer) = &self.observer {
+                observer.leftover_placeholder(&LeftoverPlaceholder { template_key, language, line, snippet });
+            },
+        }
+        Ok(())
+    }
 
-        use super::*;
+    /// Apply post-processing and validation based on the content kind.
+    fn post_process(&self, content_key: &str, output: String) -> Result<String, TerariumError> {
+        let kind = self.content_kinds.get(content_key).copied().unwrap_or_default();
+        let output = match kind {
+            ContentKind::ICalendar => finalize_icalendar(&output).map_err(TerariumError::InvalidOutput)?,
+            ContentKind::Csv => {
+                CsvValidator::csv().validate(&output).map_err(TerariumError::InvalidOutput)?;
+                output
+            }
+            ContentKind::Tsv => {
+                CsvValidator::tsv().validate(&output).map_err(TerariumError::InvalidOutput)?;
+                output
+            }
+            ContentKind::Html => {
+                let output = match &self.link_rewriter {
+                    Some(rewriter) => rewrite_links(&output, rewriter.as_ref()),
+                    None => output,
+                };
+                match &self.watermark {
+                    Some(banner) => inject_watermark(&output, banner),
+                    None => output,
+                }
+            }
+            _ => output,
+        };
+        let output = match &self.output_normalization {
+            Some(normalization) => normalization.normalize(output),
+            None => output,
+        };
+        for validator in self.validators.get(&kind).into_iter().flatten() {
+            validator.validate(&output).map_err(TerariumError::InvalidOutput)?;
+        }
+        Ok(output)
+    }
 
-        #[test]
-        fn add_template() {
-            let mut instance = make_instance();
+    /// Render all members of the group with the best contents matching the `selection`.
+    fn render_group_with_selection(
+        &self,
+        context: &Context,
+        group_key: &str,
+        selection: &Selection,
+    ) -> Result<HashMap<String, String>, TerariumError> {
+        let (group_key, group) = self.find_valid_group(group_key)?;
+        let members = self.group_members(group);
+        let order = group.render_order(&members).map_err(TerariumError::CyclicMembers)?;
+        self.check_broken_members(group_key, group)?;
+        self.check_group_context(context, group_key, group, &members, selection)?;
+        let render_members = || -> Result<_, TerariumError> {
+            let mut result = HashMap::<String, String>::new();
+            for member_key in order {
+                check_deadline()?;
+                let Some(inputs) = group.member_outputs.get(member_key) else {
+                    let content = self.render_template_with_selection(context, members[member_key], selection)?;
+                    result.insert(member_key.to_owned(), content);
+                    continue;
+                };
+                let mut member_context = context.clone();
+                for (variable, source) in inputs {
+                    if let Some(output) = result.get(source) {
+                        member_context.insert(variable, output);
+                    }
+                }
+                let content = without_memo(|| {
+                    self.render_template_with_selection(&member_context, members[member_key], selection)
+                })?;
+                result.insert(member_key.to_owned(), content);
+            }
+            Ok(result)
+        };
+        let result = match self.deduplicate_members {
+            true => with_memo(render_members)?,
+            false => render_members()?,
+        };
+        self.finish_group(group_key, group, result)
+    }
 
-            let mut tpl = Template::default();
-            tpl.add_content(Content::new("foo".to_string(), vec!["1".to_owned(), "2".to_owned()])).unwrap();
+    /// Render all members of the group on the current Tokio runtime.
+    /// Members are rendered concurrently on blocking threads, bounded by the parallelism set by the
+    /// `TerariumBuilder::set_group_parallelism()`. Members using outputs of other members wait for them.
+    #[cfg(feature = "async")]
+    pub async fn render_group_async(
+        self: &Arc<Self>,
+        context: &Context,
+        group_key: &str,
+        language: &str,
+        fallback_language: Option<&str>,
+    ) -> Result<HashMap<String, String>, TerariumError> {
+        self.render_group_async_with(context, group_key, language, fallback_language, None).await
+    }
 
-            instance.add_template(
-                "1".to_owned(),
-                tpl,
-            ).unwrap();
+    /// Render all members of the group on the current Tokio runtime same way as the `render_group_async()`, but abort
+    /// with the `TerariumError::Cancelled` once the `cancellation` is signalled. The cancellation is checked before
+    /// each member and before outputs are post-processed by group hooks, members being rendered are finished.
+    #[cfg(feature = "async")]
+    pub async fn render_group_async_cancellable<C: Cancellation + 'static>(
+        self: &Arc<Self>,
+        context: &Context,
+        group_key: &str,
+        language: &str,
+        fallback_language: Option<&str>,
+        cancellation: C,
+    ) -> Result<HashMap<String, String>, TerariumError> {
+        let cancellation: Arc<dyn Cancellation> = Arc::new(cancellation);
+        self.render_group_async_with(context, group_key, language, fallback_language, Some(cancellation)).await
+    }
 
-            assert_eq!(instance.templates.len(), 1);
-            let template = instance.templates["1"].clone();
-            let contents = template.collect_contents();
-            assert_eq!(contents.len(), 1);
-        }
+    #[cfg(feature = "async")]
+    async fn render_group_async_with(
+        self: &Arc<Self>,
+        context: &Context,
+        group_key: &str,
+        language: &str,
+        fallback_language: Option<&str>,
+        cancellation: Option<Arc<dyn Cancellation>>,
+    ) -> Result<HashMap<String, String>, TerariumError> {
+        let check_cancellation = |cancellation: &Option<Arc<dyn Cancellation>>| match cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(TerariumError::Cancelled),
+            _ => Ok(()),
+        };
+        let (group_key, group) = self.find_valid_group(group_key)?;
+        let members = self.group_members(group);
+        let mut pending = group.render_order(&members).map_err(TerariumError::CyclicMembers)?;
+        let selection = Selection::new(language, fallback_language);
+        self.check_broken_members(group_key, group)?;
+        self.check_group_context(context, group_key, group, &members, &selection)?;
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(self.group_parallelism.max(1)));
+        // blocking tasks and the rest of the render may run on other threads than the caller
+        let scope = with_current(|scope| scope.cloned()).unwrap_or_default();
+        let mut result = HashMap::<String, String>::new();
+        // outputs by template keys shared by deduplicated members like the memo of the `render_group_with_selection()`
+        let mut outputs = HashMap::<String, String>::new();
 
-        #[test]
-        fn group_manipulation() {
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<&str>, Vec<&str>) = pending.into_iter().partition(|member_key| {
+                group.member_outputs.get(*member_key).into_iter().flatten().all(|(_, source)| {
+                    result.contains_key(source) || !members.contains_key(source.as_str())
+                })
+            });
+            let mut tasks = Vec::with_capacity(ready.len());
+            let mut duplicates = Vec::new();
+            let mut spawned = HashSet::new();
+            for member_key in ready {
+                check_cancellation(&cancellation)?;
+                let template_key = members[member_key];
+                let rendered_key = match self.deduplicate_members && !group.member_outputs.contains_key(member_key) {
+                    true => Some(self.rendered_template_key(template_key)),
+                    false => None,
+                };
+                if let Some(rendered_key) = &rendered_key {
+                    if outputs.contains_key(rendered_key) || !spawned.insert(rendered_key.clone()) {
+                        duplicates.push((member_key, rendered_key.clone()));
+                        continue;
+                    }
+                }
+                let mut member_context = context.clone();
+                for (variable, source) in group.member_outputs.get(member_key).into_iter().flatten() {
+                    if let Some(output) = result.get(source) {
+                        member_context.insert(variable, output);
+                    }
+                }
+                let permit = semaphore.clone().acquire_owned().await.expect("semaphore is never closed");
+                let instance = self.clone();
+                let owned_template_key = template_key.to_owned();
+                let (language, fallback_language) = (language.to_owned(), fallback_language.map(|l| l.to_owned()));
+                let cancellation = cancellation.clone();
+                let scope = scope.clone();
+                let task = tokio::task::spawn_blocking(move || with_scope(scope, || {
+                    let _permit = permit;
+                    check_cancellation(&cancellation)?;
+                    let selection = Selection::new(&language, fallback_language.as_deref());
+                    instance.render_template_with_selection(&member_context, &owned_template_key, &selection)
+                }));
+                tasks.push((member_key, rendered_key, task));
+            }
+            for (member_key, rendered_key, task) in tasks {
+                let content = task.await.unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))?;
+                if let Some(rendered_key) = rendered_key {
+                    outputs.insert(rendered_key, content.clone());
+                }
+                result.insert(member_key.to_owned(), content);
+            }
+            for (member_key, rendered_key) in duplicates {
+                result.insert(member_key.to_owned(), outputs[&rendered_key].clone());
+            }
+            pending = rest;
+        }
+        check_cancellation(&cancellation)?;
+        with_scope(scope, || self.finish_group(group_key, group, result))
+    }
+
+    /// Return key of the template actually rendered for the template key, after template overrides of the current
+    /// scope and fallbacks of templates outside their validity windows.
+    #[cfg(feature = "async")]
+    fn rendered_template_key(&self, template_key: &str) -> String {
+        let mut template_key = template_key.to_owned();
+        for _ in 0..=self.template_validities.len() {
+            let replacement = with_current(|s| s.and_then(|s| s.template_overrides.get(&template_key).cloned()));
+            let resolved = self.template_keys.resolve(replacement.as_deref().unwrap_or(&template_key)).to_owned();
+            let window = self.template_validities.get(&resolved).filter(|w| !w.contains(SystemTime::now()));
+            match window.and_then(|window| window.fallback.as_ref()) {
+                Some(fallback) if *fallback != resolved => template_key = fallback.clone(),
+                _ => return resolved,
+            }
+        }
+        template_key
+    }
+
+    /// Find the group and follow fallbacks of groups outside their validity windows.
+    /// Return key of the group actually rendered with the group.
+    fn find_valid_group<'a>(&'a self, group_key: &'a str) -> Result<(&'a str, &'a TemplateGroup), TerariumError> {
+        let mut group_key = group_key;
+        loop {
+            let group = self.find_group(group_key)?;
+            match group.validity.as_ref().filter(|w| !w.contains(SystemTime::now())) {
+                None => return Ok((group_key, group)),
+                Some(window) => match &window.fallback {
+                    Some(fallback) if fallback != group_key => group_key = fallback,
+                    _ => return Err(TerariumError::OutsideValidity(group_key.to_owned())),
+                },
+            }
+        }
+    }
+
+    /// Apply group hooks and output normalization to rendered members of the group.
+    fn finish_group(
+        &self,
+        group_key: &str,
+        group: &TemplateGroup,
+        mut result: HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, TerariumError> {
+        check_deadline()?;
+        for hook in self.group_hooks.iter() {
+            hook.after_render(group_key, &mut result)
+                .map_err(|reason| TerariumError::GroupRejected(group_key.to_owned(), reason))?;
+        }
+        if let Some(normalization) = &self.output_normalization {
+            result = result.into_iter().map(|(key, content)| {
+                let content = normalization.encode(&key, content);
+                (key, content)
+            }).collect();
+        }
+        for (member_key, encoding) in group.member_encodings.iter() {
+            if let Some(output) = result.get_mut(member_key) {
+                *output = encoding.transfer_encoding.encode(output);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Render every template in every language once and return all failures.
+    /// Contexts are taken from `sample_contexts` by template key, the empty context is used for templates
+    /// without sample. Intended for health checks, so broken templates are found before real traffic comes.
+    pub fn warm_up(&self, sample_contexts: &HashMap<String, Context>) -> Vec<WarmUpFailure> {
+        self.render_for_testing(sample_contexts, |_| {})
+    }
+
+    /// Render every template in every language and variant once, pass each output to the `callback` and return all
+    /// failures. Contexts are taken from `sample_contexts` same way as in the `warm_up()`. Templates are rendered in
+    /// order of `template_languages()`, content without variant goes first. Intended as hook for visual regression
+    /// and snapshot tools.
+    pub fn render_for_testing<F>(&self, sample_contexts: &HashMap<String, Context>, mut callback: F) -> Vec<WarmUpFailure>
+        where
+            F: FnMut(&TestRender),
+    {
+        let empty_context = Context::new();
+        let mut failures = Vec::new();
+
+        for (template_key, language) in self.template_languages() {
+            let context = sample_contexts.get(&template_key).unwrap_or(&empty_context);
+            let mut contents: Vec<_> = self.template_map[template_key.as_str()][language.as_str()].iter().collect();
+            contents.sort();
+            for (variant, content_key) in contents {
+                let result = self.render_content(context, &template_key, &language, content_key);
+                match result {
+                    Ok(output) => callback(&TestRender {
+                        template_key: &template_key,
+                        language: &language,
+                        variant: variant.map(|v| v.as_ref()),
+                        kind: self.content_kinds.get(content_key).copied().unwrap_or_default(),
+                        fingerprint: self.fingerprints.get(&template_key).copied().unwrap_or_default(),
+                        output: &output,
+                    }),
+                    Err(error) => failures.push(WarmUpFailure {
+                        template_key: template_key.clone(),
+                        language: language.clone(),
+                        variant: variant.map(|v| v.to_string()),
+                        error,
+                    }),
+                }
+            }
+        }
+        failures
+    }
+
+    /// Return constructs of sources which cannot be translated from the dialect set by the
+    /// `TerariumBuilder::set_source_dialect()` by template keys.
+    pub fn untranslated_constructs(&self) -> &BTreeMap<String, Vec<UntranslatedConstruct>> {
+        &self.untranslated
+    }
+
+    /// Return name of the template or group presented to users speaking the language with interpolated placeholders.
+    /// Language aliases are applied, return `None` when the target has no display name in the language.
+    pub fn display_name(&self, target: &RenderTarget, language: &str) -> Option<DisplayName> {
+        let language = self.language_aliases.get(language).map_or(language, |canonical| canonical.as_str());
+        let (key, display_names) = match target {
+            RenderTarget::Template(key) => {
+                let key = self.template_keys.resolve(key);
+                (key, self.template_display_names.get(key)?)
+            }
+            RenderTarget::Group(key) => {
+                let key = self.group_keys.resolve(key);
+                (key, &self.groups.get(key)?.display_names)
+            }
+        };
+        let display_name = display_names.get(language)?;
+        let mut variables: HashMap<&str, &str> = self.display_variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        variables.insert("key", key);
+        variables.insert("language", language);
+        Some(display_name.interpolate(&variables))
+    }
+
+    /// Return text of the snippet in the language, language aliases are applied.
+    pub fn snippet(&self, key: &str, language: &str) -> Option<&str> {
+        self.snippets.find(key, [language])
+    }
+
+    /// Return notes for translators written in sources of the template (see `Template::translator_notes()`).
+    pub fn translator_notes(&self, template_key: &str) -> &[TranslatorNote] {
+        self.translator_notes.get(self.template_keys.resolve(template_key)).map_or(&[], |notes| notes.as_slice())
+    }
+
+    /// Generate documentation of every template (languages, required variables, metadata, groups and sample render)
+    /// in the format. Samples are rendered in the first language (alphabetically) with contexts taken from
+    /// `sample_contexts` by template key, templates without sample context are documented without sample.
+    pub fn generate_docs(&self, format: DocsFormat, sample_contexts: &HashMap<String, Context>) -> String {
+        let mut template_keys: Vec<_> = self.fingerprints.keys().collect();
+        template_keys.sort();
+        let docs: Vec<_> = template_keys.into_iter().map(|template_key| {
+            let contents = self.template_map.get(template_key.as_str());
+            let mut languages: Vec<_> = contents.into_iter().flat_map(|c| c.keys()).map(|l| l.to_string()).collect();
+            languages.sort();
+
+            let mut metadata = Vec::new();
+            if let Some(owner) = self.template_owners.get(template_key) {
+                metadata.push(("Owner", owner.clone()));
+            }
+            if let Some(revision) = self.template_revisions.get(template_key) {
+                metadata.push(("Revision", revision.clone()));
+            }
+            if let Some(tags) = self.template_tags.get(template_key) {
+                let mut tags: Vec<_> = tags.iter().map(|t| t.as_str()).collect();
+                tags.sort();
+                metadata.push(("Tags", tags.join(", ")));
+            }
+            if let Some(deprecation) = self.template_deprecations.get(template_key) {
+                metadata.push(("Deprecated", deprecation.message.clone()));
+            }
+            for note in self.translator_notes(template_key) {
+                metadata.push(("Translator note", note.note.clone()));
+            }
+
+            let mut groups: Vec<_> = self.groups
+                .iter()
+                .filter(|(_, group)| {
+                    group.resolve_members(self.fingerprints.keys())
+                        .values()
+                        .any(|key| self.template_keys.resolve(key) == template_key)
+                })
+                .map(|(group_key, _)| group_key.clone())
+                .collect();
+            groups.sort();
+
+            let sample = sample_contexts.get(template_key).zip(contents).and_then(|(context, contents)| {
+                let (language, content_key) = Selection::new(languages.first()?, None).find(contents)?;
+                let output = self.render_content(context, template_key, language, content_key);
+                Some(output.map_err(|error| error.to_string()))
+            });
+            TemplateDoc {
+                key: template_key.clone(),
+                languages,
+                required_variables: self.template_variables(template_key).into_iter().collect(),
+                metadata,
+                groups,
+                sample,
+            }
+        }).collect();
+        write_docs(&docs, format)
+    }
+
+    /// Export OpenAPI document describing template keys, group keys, languages and variables required in contexts of
+    /// templates and groups, so clients of a render service can be generated against the catalog.
+    /// The document has only component schemas, its version is the fingerprint of the catalog.
+    pub fn export_schema(&self) -> serde_json::Value {
+        let mut template_keys: Vec<_> = self.fingerprints.keys().collect();
+        template_keys.sort();
+        let templates: Vec<_> = template_keys.into_iter().map(|template_key| {
+            let mut languages: Vec<_> = self.template_map
+                .get(template_key.as_str())
+                .into_iter()
+                .flat_map(|contents| contents.keys().map(|language| language.to_string()))
+                .collect();
+            languages.sort();
+            SchemaEntry {
+                key: template_key.clone(),
+                languages,
+                required_variables: self.template_variables(template_key).into_iter().collect(),
+            }
+        }).collect();
+
+        let mut group_keys: Vec<_> = self.groups.keys().collect();
+        group_keys.sort();
+        let groups: Vec<_> = group_keys.into_iter().map(|group_key| {
+            let group = &self.groups[group_key];
+            let mut languages = BTreeSet::new();
+            let mut variables = BTreeSet::new();
+            for (member_key, template_key) in self.group_members(group) {
+                let template_key = self.template_keys.resolve(template_key);
+                if let Ok(entry) = templates.binary_search_by(|e| e.key.as_str().cmp(template_key)) {
+                    languages.extend(templates[entry].languages.iter().cloned());
+                }
+                let inputs = group.member_outputs.get(member_key);
+                variables.extend(self.template_variables(template_key).into_iter().filter(|variable| {
+                    !inputs.is_some_and(|inputs| inputs.contains_key(variable))
+                }));
+            }
+            SchemaEntry {
+                key: group_key.clone(),
+                languages: languages.into_iter().collect(),
+                required_variables: variables.into_iter().collect(),
+            }
+        }).collect();
+        openapi_schema(&format!("{:016x}", self.fingerprint()), &templates, &groups)
+    }
+
+    /// Return variables required by any content of the template.
+    fn template_variables(&self, template_key: &str) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        let contents = self.template_map.get(template_key).into_iter().flat_map(|contents| contents.values());
+        for (_, content_key) in contents.flat_map(|contents| contents.iter()) {
+            variables.extend(required_variables(&self.tera, content_key));
+        }
+        variables
+    }
+
+    /// Return all pairs of template key and language available for rendering, sorted.
+    pub fn template_languages(&self) -> Vec<(String, String)> {
+        let mut pairs: Vec<_> = self.template_map
+            .iter()
+            .flat_map(|(key, languages)| languages.keys().map(|language| (key.to_string(), language.to_string())))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Add new template or replace existing one in the already built instance.
+    /// The `actor` identifies who made the change in the audit log.
+    pub fn add_template(
+        &mut self,
+        key: String,
+        mut template: Template,
+        actor: &str,
+    ) -> Result<(), TerariumBuilderError> {
+        let untranslated = self.preprocessing.apply(&mut template);
+        let before = self.fingerprints.get(&key).copied();
+        self.limits.check_template_count(self.fingerprints.len() + usize::from(before.is_none()))?;
+        self.limits.check_template(&key, &template)?;
+        self.localization.check(&key, &template, self.observer.as_deref())?;
+        let template_keys = KeyIndex::new(
+            self.template_keys.normalization(),
+            self.fingerprints.keys().filter(|k| **k != key).chain(once(&key)),
+        ).map_err(|(a, b)| TerariumBuilderError::KeyConflict(a, b))?;
+        // Only contents of this template are re-registered, previous ones are put back when the new ones fail.
+        let previous_names: Vec<_> = self.template_map
+            .get(key.as_str())
+            .into_iter()
+            .flat_map(|languages| languages.values())
+            .flat_map(|contents| contents.iter())
+            .map(|(_, name)| name.clone())
+            .collect();
+        let previous: Vec<_> = previous_names
+            .iter()
+            .filter_map(|name| self.tera.templates.remove(name).map(|template| (name.clone(), template)))
+            .collect();
+        let contents = self.raw_contents(&template);
+        let names: Vec<_> = contents.iter().map(|content| content.name.clone()).collect();
+        let registered = add_contents(&mut self.tera, contents)
+            .map_err(TerariumBuilderError::from)
+            .and_then(|_| self.check_dependencies(&self.tera));
+        if let Err(error) = registered {
+            names.iter().for_each(|name| { self.tera.templates.remove(name); });
+            self.tera.templates.extend(previous);
+            self.tera.build_inheritance_chains()?;
+            return Err(error);
+        }
+        for name in &previous_names {
+            self.content_kinds.remove(name);
+            self.sources.remove(name);
+        }
+        self.insert_template(key.clone(), template);
+        self.interner.prune();
+        self.update_templates_fingerprint();
+        self.template_keys = template_keys;
+        match untranslated.is_empty() {
+            true => self.untranslated.remove(&key),
+            false => self.untranslated.insert(key.clone(), untranslated),
+        };
+
+        let after = self.fingerprints.get(&key).copied();
+        let action = if before.is_some() { AuditAction::TemplateUpdated(key) } else { AuditAction::TemplateAdded(key) };
+        self.record_audit(actor, action, before, after);
+        Ok(())
+    }
+
+    /// Remove template from the already built instance.
+    /// Group members referencing the template are handled by the `DanglingMemberPolicy`.
+    /// The `actor` identifies who made the change in the audit log.
+    pub fn remove_template(&mut self, key: &str, actor: &str) -> Result<(), TerariumBuilderError> {
+        let key = self.template_keys.resolve(key).to_owned();
+        let key = key.as_str();
+        let before = self.fingerprints.get(key).copied()
+            .ok_or_else(|| TerariumBuilderError::TemplateNotFound(key.to_owned()))?;
+        let mut dangling: Vec<_> = self.groups
+            .iter()
+            .flat_map(|(group_key, group)| group.members.iter().map(move |member| (group_key, member)))
+            .filter(|(_, (_, template_key))| self.template_keys.resolve(template_key) == key)
+            .map(|(group_key, (member_key, _))| (group_key.clone(), member_key.clone()))
+            .collect();
+        dangling.sort();
+        if let Some((group_key, member_key)) = dangling.first() {
+            if self.dangling_member_policy == DanglingMemberPolicy::Reject {
+                return Err(TerariumBuilderError::TemplateInUse(key.to_owned(), group_key.clone(), member_key.clone()));
+            }
+        }
+        // groups are changed on copies and contents are put back, so the instance is kept as is when the removal fails
+        let mut groups = BTreeMap::new();
+        if self.dangling_member_policy == DanglingMemberPolicy::RemoveMember {
+            for (group_key, member_key) in dangling {
+                let group = groups.entry(group_key).or_insert_with_key(|group_key| self.groups[group_key].clone());
+                group.remove_member(&member_key);
+            }
+            let template_keys: Vec<_> = self.fingerprints.keys().filter(|k| *k != key).cloned().collect();
+            let languages = |template_key: &str| self.content_languages(template_key);
+            for (group_key, group) in groups.iter() {
+                check_group(group_key, group, &template_keys, languages, self.empty_group_policy)?;
+            }
+        }
+        let names = self.remove_template_contents(key)?;
+        for name in names.iter() {
+            self.content_kinds.remove(name);
+            self.sources.remove(name);
+        }
+        self.groups.extend(groups);
+        self.template_map.remove(key);
+        self.template_deprecations.remove(key);
+        self.template_owners.remove(key);
+        self.template_revisions.remove(key);
+        self.template_validities.remove(key);
+        self.template_overrides.remove(key);
+        self.template_tags.remove(key);
+        self.cache_policies.remove(key);
+        self.translator_notes.remove(key);
+        self.template_display_names.remove(key);
+        self.untranslated.remove(key);
+        self.fingerprints.remove(key);
+        self.template_sizes.remove(key);
+        self.template_keys.remove(key);
+        self.interner.prune();
+        self.update_templates_fingerprint();
+
+        self.record_audit(actor, AuditAction::TemplateRemoved(key.to_owned()), Some(before), None);
+        Ok(())
+    }
+
+    /// Replace whole catalog (templates and groups) by the catalog of the `other` instance.
+    /// Groups of the `other` instance are validated by the group policies of this instance first. Members whose
+    /// templates were removed from the `other` instance are handled by the `DanglingMemberPolicy` of this instance.
+    /// Templates are not validated again, they were validated when the `other` instance was built or mutated.
+    /// The instance is kept as is when the validation fails.
+    /// Observer, audit log and usage statistics of this instance are kept.
+    /// The `actor` identifies who made the change in the audit log.
+    pub fn swap(&mut self, mut other: Terarium, actor: &str) -> Result<(), TerariumBuilderError> {
+        let mut group_keys: Vec<_> = other.groups.keys().cloned().collect();
+        group_keys.sort();
+        for group_key in group_keys.iter() {
+            let mut dangling: Vec<_> = other.groups[group_key].members
+                .iter()
+                .filter(|(_, template_key)| !other.fingerprints.contains_key(other.template_keys.resolve(template_key)))
+                .map(|(member_key, template_key)| (member_key.clone(), template_key.clone()))
+                .collect();
+            dangling.sort();
+            match self.dangling_member_policy {
+                DanglingMemberPolicy::Reject => if let Some((_, template_key)) = dangling.first() {
+                    return Err(TerariumBuilderError::TemplateNotFound(template_key.clone()));
+                },
+                DanglingMemberPolicy::RemoveMember => if let Some(group) = other.groups.get_mut(group_key) {
+                    dangling.iter().for_each(|(member_key, _)| group.remove_member(member_key));
+                },
+                _ => {}
+            }
+        }
+        let languages = |template_key: &str| other.content_languages(template_key);
+        for group_key in group_keys.iter() {
+            let group = &other.groups[group_key];
+            check_group(group_key, group, other.fingerprints.keys(), languages, self.empty_group_policy)?;
+        }
+
+        let before = self.fingerprint();
+        self.tera = other.tera;
+        self.template_map = other.template_map;
+        self.interner = other.interner;
+        self.groups = other.groups;
+        self.template_deprecations = other.template_deprecations;
+        self.template_owners = other.template_owners;
+        self.template_revisions = other.template_revisions;
+        self.template_validities = other.template_validities;
+        self.template_overrides = other.template_overrides;
+        self.template_tags = other.template_tags;
+        self.cache_policies = other.cache_policies;
+        self.translator_notes = other.translator_notes;
+        self.template_display_names = other.template_display_names;
+        self.untranslated = other.untranslated;
+        self.fingerprints = other.fingerprints;
+        self.templates_fingerprint = other.templates_fingerprint;
+        self.template_sizes = other.template_sizes;
+        self.next_content_id = other.next_content_id;
+        self.content_kinds = other.content_kinds;
+        self.source_storage = other.source_storage;
+        self.sources = other.sources;
+        self.template_keys = other.template_keys;
+        self.group_keys = other.group_keys;
+        let after = self.fingerprint();
+        self.record_audit(actor, AuditAction::CatalogSwapped, Some(before), Some(after));
+        Ok(())
+    }
+
+    /// Return all runtime mutations of the catalog in order they were made.
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        &self.audit_log
+    }
+
+    /// Return owner of the template.
+    pub fn template_owner(&self, template_key: &str) -> Option<&str> {
+        self.template_owners.get(self.template_keys.resolve(template_key)).map(|o| o.as_str())
+    }
+
+    /// Return environment the instance runs in.
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Return sorted keys of templates with the tag (see `Template::add_tag()`).
+    pub fn templates_with_tag(&self, tag: &str) -> Vec<String> {
+        let mut keys: Vec<_> = self.template_tags
+            .iter()
+            .filter(|(_, tags)| tags.contains(tag))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Return sorted keys of groups with the tag (see `TemplateGroupBuilder::tag()`).
+    pub fn groups_with_tag(&self, tag: &str) -> Vec<String> {
+        let mut keys: Vec<_> = self.groups
+            .iter()
+            .filter(|(_, group)| group.tags.contains(tag))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Return direction of the text in the language.
+    pub fn language_direction(&self, language: &str) -> TextDirection {
+        TextDirection::of(&self.rtl_languages, language)
+    }
+
+    /// Remove cached outputs with the tag (see `CachePolicy::tag()`) and return their number.
+    pub fn invalidate_tag(&self, tag: &str) -> usize {
+        self.render_cache.as_ref().map(|cache| cache.invalidate_tag(tag)).unwrap_or_default()
+    }
+
+    /// Return size of the catalog for logging and capacity planning.
+    pub fn stats(&self) -> CatalogStats {
+        let languages: HashSet<_> = self.template_map.values().flat_map(|languages| languages.keys()).collect();
+        let mut largest_templates: Vec<_> = self.template_sizes.iter().map(|(k, size)| (k.clone(), *size)).collect();
+        largest_templates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        largest_templates.truncate(LARGEST_TEMPLATES);
+        CatalogStats {
+            templates: self.fingerprints.len(),
+            contents: self.content_kinds.len(),
+            groups: self.groups.len(),
+            languages: languages.len(),
+            content_bytes: self.template_sizes.values().sum(),
+            largest_templates,
+            build_time: self.build_time,
+        }
+    }
+
+    /// Search templates and groups matching the query and return them ranked from the best match
+    /// (see `SearchQuery`). Intended for template pickers of admin UIs.
+    pub fn search(&self, query: impl Into<SearchQuery>) -> Vec<SearchMatch> {
+        let query = query.into();
+        let display_names = |names: Option<&HashMap<String, DisplayName>>| -> Vec<(SearchField, Option<u32>)> {
+            names.into_iter()
+                .flat_map(|names| names.values())
+                .flat_map(|name| once(&name.name).chain(name.description.as_ref()))
+                .map(|text| (SearchField::DisplayName, query.score(SearchField::DisplayName, text)))
+                .collect()
+        };
+        let mut matches = Vec::new();
+        for (key, languages) in self.template_map.iter() {
+            let mut scores = vec![(SearchField::Key, query.score(SearchField::Key, key))];
+            scores.extend(display_names(self.template_display_names.get(key.as_ref())));
+            let metadata = self.template_owners.get(key.as_ref()).into_iter()
+                .chain(self.template_tags.get(key.as_ref()).into_iter().flatten());
+            scores.extend(metadata.map(|text| (SearchField::Metadata, query.score(SearchField::Metadata, text))));
+            if query.searches_contents() {
+                let names = languages
+                    .values()
+                    .flat_map(|contents| contents.default.iter().chain(contents.variants.values()));
+                // sources which cannot be loaded are not searched
+                let sources = names.filter_map(|name| self.sources.get(name)?.load().ok());
+                scores.extend(sources.map(|source| (SearchField::Content, query.score(SearchField::Content, &source))));
+            }
+            matches.extend(SearchMatch::best(RenderTarget::Template(key.to_string()), scores));
+        }
+        for (key, group) in self.groups.iter() {
+            let mut scores = vec![(SearchField::Key, query.score(SearchField::Key, key))];
+            scores.extend(display_names(Some(&group.display_names)));
+            let metadata = group.description.iter().chain(group.metadata.values()).chain(group.tags.iter());
+            scores.extend(metadata.map(|text| (SearchField::Metadata, query.score(SearchField::Metadata, text))));
+            matches.extend(SearchMatch::best(RenderTarget::Group(key.clone()), scores));
+        }
+        query.rank(matches)
+    }
+
+    /// Return source of the template content in the language and variant or `None` when there is no such content.
+    /// Sources are kept only when set by the `TerariumBuilder::set_source_storage()`.
+    /// Return the `TerariumError::SourceUnavailable` when the kept source cannot be decompressed.
+    pub fn content_source(
+        &self,
+        template_key: &str,
+        language: &str,
+        variant: Option<&str>,
+    ) -> Result<Option<String>, TerariumError> {
+        let contents = self.template_map.get(self.template_keys.resolve(template_key)).and_then(|t| t.get(language));
+        let name = contents.and_then(|contents| match variant {
+            Some(variant) => contents.variants.get(variant),
+            None => contents.default.as_ref(),
+        });
+        let Some((name, source)) = name.and_then(|name| self.sources.get_key_value(name)) else {
+            return Ok(None);
+        };
+        source.load().map(Some).map_err(|error| TerariumError::SourceUnavailable(name.clone(), error))
+    }
+
+    /// Return estimated memory footprint of templates, their contents and groups.
+    pub fn memory_report(&self) -> MemoryReport {
+        let contents: HashMap<String, usize> = self.tera.templates
+            .iter()
+            .filter(|(name, _)| self.content_kinds.contains_key(*name))
+            .map(|(name, template)| {
+                let ast = template.ast.capacity() * size_of::<tera::ast::Node>();
+                let source = self.sources.get(name).map_or(0, |s| s.size());
+                (name.clone(), size_of::<tera::Template>() + name.len() * 3 + ast + source)
+            })
+            .collect();
+
+        let templates = self.fingerprints.keys().map(|key| {
+            let languages = self.template_map.get(key.as_str());
+            let names: HashSet<&String> = languages
+                .iter()
+                .flat_map(|languages| languages.values().flat_map(|c| c.iter().map(|(_, name)| name)))
+                .collect();
+            let lookup: usize = languages.iter().flat_map(|languages| languages.values()).map(|c| {
+                size_of::<(Arc<str>, LanguageContents)>() + c.variants.len() * size_of::<(Arc<str>, String)>()
+            }).sum();
+            let metadata = self.template_owners.get(key).map_or(0, |o| o.len())
+                + self.template_revisions.get(key).map_or(0, |r| r.len());
+            let size = key.len() * 3
+                + self.template_sizes.get(key).copied().unwrap_or_default()
+                + names.iter().filter_map(|name| contents.get(*name)).sum::<usize>()
+                + lookup
+                + metadata;
+            (key.clone(), size)
+        }).collect();
+
+        let groups = self.groups.iter().map(|(key, group)| {
+            let members: usize = group.members.iter().map(|(m, t)| m.len() + t.len()).sum();
+            key.len() + size_of::<TemplateGroup>() + members
+        }).sum();
+
+        MemoryReport { templates, contents, groups }
+    }
+
+    /// Return fingerprint of the whole catalog.
+    /// The fingerprint changes whenever any template or group changes.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fingerprint::default();
+        self.hash_templates(&mut hasher);
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        groups.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, group) in groups {
+            hasher.write_str(key);
+            let mut members: Vec<_> = group.members.iter().collect();
+            members.sort();
+            for (member_key, template_key) in members {
+                hasher.write_str(member_key);
+                hasher.write_str(template_key);
+            }
+            for pattern in group.member_patterns.iter() {
+                hasher.write_str(&pattern.to_string());
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Return stable identity of the template or group in the language, or `None` when the target does not exist.
+    /// The identity changes whenever the template (or any member template of the group) changes, so clients can
+    /// cache it and send it back by the `RenderRequest::with_expected_identity()` to detect skew between them and
+    /// the catalog.
+    pub fn identity(&self, target: &RenderTarget, language: &str) -> Option<String> {
+        let language = self.language_aliases.get(language).map_or(language, |canonical| canonical.as_str());
+        let mut hasher = Fingerprint::default();
+        match target {
+            RenderTarget::Template(key) => {
+                let (key, fingerprint) = self.fingerprints.get_key_value(self.template_keys.resolve(key))?;
+                hasher.write_str(key);
+                hasher.write_str(language);
+                hasher.write_u64(*fingerprint);
+            }
+            RenderTarget::Group(key) => {
+                let (key, group) = self.groups.get_key_value(self.group_keys.resolve(key))?;
+                hasher.write_str(key);
+                hasher.write_str(language);
+                let mut members: Vec<_> = self.group_members(group).into_iter().collect();
+                members.sort();
+                for (member_key, template_key) in members {
+                    hasher.write_str(member_key);
+                    hasher.write_str(template_key);
+                    let template_key = self.template_keys.resolve(template_key);
+                    hasher.write_u64(self.fingerprints.get(template_key).copied().unwrap_or_default());
+                }
+            }
+        }
+        Some(format!("{:016x}", hasher.finish()))
+    }
+
+    /// Write keys and fingerprints of all templates into the hasher, sorted.
+    fn hash_templates(&self, hasher: &mut Fingerprint) {
+        let mut templates: Vec<_> = self.fingerprints.iter().collect();
+        templates.sort();
+        for (key, fingerprint) in templates {
+            hasher.write_str(key);
+            hasher.write_u64(*fingerprint);
+        }
+    }
+
+    /// Recompute the fingerprint of all templates after they changed.
+    fn update_templates_fingerprint(&mut self) {
+        let mut hasher = Fingerprint::default();
+        self.hash_templates(&mut hasher);
+        self.templates_fingerprint = hasher.finish();
+    }
+
+    /// Return contents of the template named the same way the `insert_template()` names them.
+    fn raw_contents(&self, template: &Template) -> Vec<RawContent> {
+        template.contents()
+            .iter()
+            .filter(|content| !content.languages.is_empty())
+            .zip(self.next_content_id..)
+            .map(|(content, id)| RawContent {
+                name: content.name.clone().unwrap_or_else(|| format!("template#{}", id)),
+                content: content.content.clone(),
+                autoescape: content.autoescape,
+            })
+            .collect()
+    }
+
+    /// Update lookups by the template and return its contents to be added into the `Tera` by the `add_contents()`.
+    fn insert_template(&mut self, key: String, template: Template) -> Vec<RawContent> {
+        let deprecation = template.deprecation().cloned();
+        let owner = template.owner().map(|o| o.to_owned());
+        let revision = template.revision().map(|r| r.to_owned());
+        let cache_policy = template.cache_policy().cloned();
+        let validity = template.validity().cloned();
+        let overrides = template.overrides().to_vec();
+        let tags = template.tags().clone();
+        let notes = template.translator_notes();
+        let display_names = template.display_names().clone();
+        let fingerprint = template.fingerprint();
+        let size = template.contents().iter().map(|c| c.content.len()).sum();
+        let mut languages = TemplateContents::default();
+
+        let mut kinds = HashMap::<String, ContentKind>::new();
+        let mut contents = Vec::new();
+
+        for content in template.collect_contents() {
+            let template_name = content.name.unwrap_or_else(|| format!("template#{}", self.next_content_id));
+            self.next_content_id += 1;
+            kinds.insert(template_name.clone(), content.kind);
+            if let Some(source) = self.source_storage.store(&content.content) {
+                self.sources.insert(template_name.clone(), source);
+            }
+            for language_key in content.languages {
+                let language_contents = languages.entry(self.interner.intern(&language_key)).or_default();
+                match &content.variant {
+                    Some(variant) => {
+                        language_contents.variants.insert(self.interner.intern(variant), template_name.clone())
+                    }
+                    None => language_contents.default.replace(template_name.clone()),
+                };
+            }
+            contents.push(RawContent { name: template_name, content: content.content, autoescape: content.autoescape });
+        }
+        self.content_kinds.extend(kinds);
+
+        self.template_map.remove(key.as_str());
+        if !languages.is_empty() {
+            self.template_map.insert(Arc::from(key.as_str()), Arc::new(languages));
+        }
+        match deprecation {
+            Some(deprecation) => self.template_deprecations.insert(key.clone(), deprecation),
+            None => self.template_deprecations.remove(&key),
+        };
+        match owner {
+            Some(owner) => self.template_owners.insert(key.clone(), owner),
+            None => self.template_owners.remove(&key),
+        };
+        match revision {
+            Some(revision) => self.template_revisions.insert(key.clone(), revision),
+            None => self.template_revisions.remove(&key),
+        };
+        match validity {
+            Some(window) => self.template_validities.insert(key.clone(), window),
+            None => self.template_validities.remove(&key),
+        };
+        match overrides.is_empty() {
+            false => self.template_overrides.insert(key.clone(), overrides),
+            true => self.template_overrides.remove(&key),
+        };
+        match tags.is_empty() {
+            false => self.template_tags.insert(key.clone(), tags),
+            true => self.template_tags.remove(&key),
+        };
+        match cache_policy {
+            Some(policy) => self.cache_policies.insert(key.clone(), policy),
+            None => self.cache_policies.remove(&key),
+        };
+        match notes.is_empty() {
+            false => self.translator_notes.insert(key.clone(), notes),
+            true => self.translator_notes.remove(&key),
+        };
+        match display_names.is_empty() {
+            false => self.template_display_names.insert(key.clone(), display_names),
+            true => self.template_display_names.remove(&key),
+        };
+        self.template_sizes.insert(key.clone(), size);
+        self.fingerprints.insert(key, fingerprint);
+        contents
+    }
+
+    /// Release excess capacity of lookups and parsed contents.
+    fn shrink_to_fit(&mut self) {
+        for languages in self.template_map.values_mut() {
+            if let Some(languages) = Arc::get_mut(languages) {
+                languages.values_mut().for_each(|contents| contents.variants.shrink_to_fit());
+                languages.shrink_to_fit();
+            }
+        }
+        self.template_map.shrink_to_fit();
+        self.template_deprecations.shrink_to_fit();
+        self.template_owners.shrink_to_fit();
+        self.template_revisions.shrink_to_fit();
+        self.template_validities.shrink_to_fit();
+        self.template_overrides.shrink_to_fit();
+        self.template_tags.shrink_to_fit();
+        self.cache_policies.shrink_to_fit();
+        self.translator_notes.shrink_to_fit();
+        self.template_display_names.shrink_to_fit();
+        self.fingerprints.shrink_to_fit();
+        self.template_sizes.shrink_to_fit();
+        self.content_kinds.shrink_to_fit();
+        self.sources.shrink_to_fit();
+        for template in self.tera.templates.values_mut() {
+            template.ast.shrink_to_fit();
+        }
+        self.tera.templates.shrink_to_fit();
+        for group in self.groups.values_mut() {
+            group.members.shrink_to_fit();
+        }
+        self.groups.shrink_to_fit();
+    }
+
+    /// Check dependencies between contents in the `tera` (cycles and include depth).
+    fn check_dependencies(&self, tera: &Tera) -> Result<(), TerariumBuilderError> {
+        if let Some(cycle) = find_cycle(tera) {
+            return Err(TerariumBuilderError::CyclicDependency(cycle));
+        }
+        self.limits.check_include_depth(tera)
+    }
+
+    /// Remove contents of the template from the `Tera` and return their names.
+    /// Removed contents are put back when other contents depend on them.
+    fn remove_template_contents(&mut self, key: &str) -> Result<Vec<String>, TerariumBuilderError> {
+        let names: Vec<_> = self.template_map
+            .get(key)
+            .into_iter()
+            .flat_map(|languages| languages.values())
+            .flat_map(|contents| contents.iter())
+            .map(|(_, name)| name.clone())
+            .collect();
+        let removed: Vec<_> = names
+            .iter()
+            .filter_map(|name| self.tera.templates.remove(name).map(|template| (name.clone(), template)))
+            .collect();
+        if !removed.is_empty() {
+            if let Err(error) = self.tera.build_inheritance_chains() {
+                self.tera.templates.extend(removed);
+                self.tera.build_inheritance_chains()?;
+                return Err(error.into());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Return languages the template has content in.
+    fn content_languages(&self, template_key: &str) -> HashSet<&str> {
+        self.template_map
+            .get(template_key)
+            .map(|languages| languages.keys().map(|language| language.as_ref()).collect())
+            .unwrap_or_default()
+    }
+
+    fn record_audit(&mut self, actor: &str, action: AuditAction, fingerprint_before: Option<u64>, fingerprint_after: Option<u64>) {
+        let entry = AuditEntry {
+            actor: actor.to_owned(),
+            timestamp: SystemTime::now(),
+            action,
+            fingerprint_before,
+            fingerprint_after,
+        };
+        if let Some(observer) = &self.observer {
+            observer.catalog_mutated(&entry);
+        }
+        self.audit_log.push(entry);
+    }
+
+    /// Return template keys by member keys of the group including members matching its patterns.
+    fn group_members<'a>(&'a self, group: &'a TemplateGroup) -> HashMap<&'a str, &'a str> {
+        group.resolve_members(self.fingerprints.keys())
+    }
+
+    /// Check templates of explicit members of the group were not removed.
+    fn check_broken_members(&self, group_key: &str, group: &TemplateGroup) -> Result<(), TerariumError> {
+        let mut broken: Vec<_> = group.members
+            .iter()
+            .filter(|(_, template_key)| !self.fingerprints.contains_key(self.template_keys.resolve(template_key)))
+            .map(|(member_key, _)| member_key)
+            .collect();
+        broken.sort();
+        match broken.first() {
+            Some(member_key) => Err(TerariumError::BrokenMember(group_key.to_owned(), member_key.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the context contains variables required by contents of members selected by the `selection`.
+    fn check_group_context(
+        &self,
+        context: &Context,
+        group_key: &str,
+        group: &TemplateGroup,
+        members: &HashMap<&str, &str>,
+        selection: &Selection,
+    ) -> Result<(), TerariumError> {
+        if !self.validate_group_context {
+            return Ok(());
+        }
+        let selection = selection.aliased(&self.language_aliases);
+        let mut missing = BTreeSet::new();
+        for (member_key, template_key) in members {
+            let template_key = self.template_keys.resolve(template_key);
+            let Some((_, content_key)) = self.template_map.get(template_key).and_then(|t| selection.find(t)) else {
+                continue;
+            };
+            let inputs = group.member_outputs.get(*member_key);
+            missing.extend(required_variables(&self.tera, content_key).into_iter().filter(|variable| {
+                !context.contains_key(variable) && !inputs.is_some_and(|inputs| inputs.contains_key(variable))
+            }));
+        }
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(TerariumError::MissingContextVariables(group_key.to_owned(), missing.into_iter().collect())),
+        }
+    }
+
+    /// Find group by its key and report its usage.
+    fn find_group(&self, group_key: &str) -> Result<&TemplateGroup, TerariumError> {
+        let group_key = self.group_keys.resolve(group_key);
+        let (key, group) = self.groups.get_key_value(group_key).ok_or_else(|| TerariumError::GroupNotFound)?;
+        if let Some(deprecation) = &group.deprecation {
+            self.report_deprecated_usage(DeprecatedKey::Group(key.clone()), deprecation);
+        }
+        Ok(group)
+    }
+
+    /// Return own cache policy of the template or policy bound to its tag.
+    fn cache_policy(&self, template_key: &str) -> Option<&CachePolicy> {
+        self.cache_policies.get(template_key).or_else(|| {
+            let tags = self.template_tags.get(template_key)?;
+            self.tag_cache_policies.iter().find(|(tag, _)| tags.contains(tag)).map(|(_, policy)| policy)
+        })
+    }
+
+    /// Check the output of the tagged template satisfies all compliance rules of its tags.
+    fn check_compliance(
+        &self,
+        context: &Context,
+        template_key: &str,
+        content_key: &str,
+        output: &str,
+    ) -> Result<(), TerariumError> {
+        let Some(tags) = self.template_tags.get(template_key) else { return Ok(()) };
+        let kind = self.content_kinds.get(content_key).copied().unwrap_or_default();
+        if !matches!(kind, ContentKind::Html | ContentKind::Text) {
+            return Ok(());
+        }
+        for rule in self.compliance_rules.iter().filter(|rule| tags.contains(&rule.tag)) {
+            rule.check(output, context)
+                .map_err(|violation| TerariumError::ComplianceViolation(template_key.to_owned(), violation))?;
+        }
+        Ok(())
+    }
+
+    /// Return content of the first override rule applying to the selected default content.
+    fn override_content<'a>(
+        &self,
+        template_key: &str,
+        contents: &'a LanguageContents,
+        language: &str,
+        content_key: &'a String,
+    ) -> &'a String {
+        let Some(rules) = self.template_overrides.get(template_key) else { return content_key };
+        if contents.default.as_ref() != Some(content_key) {
+            return content_key;
+        }
+        let now = with_current(|scope| scope.and_then(|s| s.time)).unwrap_or_else(SystemTime::now);
+        let applied = rules
+            .iter()
+            .filter(|rule| rule.applies(language, now))
+            .find_map(|rule| contents.variants.get(rule.variant.as_str()).map(|name| (rule, name)));
+        match applied {
+            Some((rule, name)) => {
+                record_override(&rule.id);
+                name
+            }
+            None => content_key,
+        }
+    }
+
+    fn report_render_failure(&self, context: &Context, template_key: &str, language: &str, error: &TerariumError) {
+        if let Some(observer) = &self.observer {
+            observer.render_failed(&RenderFailure {
+                template_key,
+                language,
+                owner: self.template_owners.get(template_key).map(|o| o.as_str()),
+                error,
+                message: redact_text(&error_chain(error), &sensitive_values(context)),
+            });
+        }
+    }
+
+    fn report_deprecated_usage(&self, key: DeprecatedKey, deprecation: &Deprecation) {
+        if let Some(observer) = &self.observer {
+            observer.deprecated_usage(&key, deprecation);
+        }
+        if let Ok(mut usage) = self.deprecated_usage.lock() {
+            usage.insert(key);
+        }
+    }
+
+    fn report_fallback_usage(&self, template_key: &str, requested_language: &str) {
+        if let Ok(mut usage) = self.fallback_usage.lock() {
+            *usage.entry((template_key.to_owned(), requested_language.to_owned())).or_default() += 1;
+        }
+    }
+}
+
+
+/// Return messages of the error and all its sources separated by colons.
+fn error_chain(error: &TerariumError) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+
+/// Fail when the deadline of the current render passed.
+fn check_deadline() -> Result<(), TerariumError> {
+    match with_current(|scope| scope.and_then(|scope| scope.deadline)) {
+        Some(deadline) if Instant::now() >= deadline => Err(TerariumError::DeadlineExceeded),
+        _ => Ok(()),
+    }
+}
+
+
+/// Check the group is valid for templates with the keys (members, member outputs and requirements).
+/// The `languages` return languages templates have content in by template keys.
+fn check_group<'a, 't>(
+    group_key: &str,
+    group: &'a TemplateGroup,
+    template_keys: impl IntoIterator<Item=&'a String>,
+    languages: impl Fn(&str) -> HashSet<&'t str>,
+    empty_group_policy: EmptyGroupPolicy,
+) -> Result<(), TerariumBuilderError> {
+    let members = group.resolve_members(template_keys);
+    if members.is_empty() && empty_group_policy == EmptyGroupPolicy::Reject {
+        return Err(TerariumBuilderError::EmptyGroup(group_key.to_owned()));
+    }
+    if let Some(members) = group.missing_members().into_iter().next() {
+        return Err(TerariumBuilderError::MissingGroupMembers(group_key.to_owned(), members));
+    }
+    let member_languages = |member_key: &str| -> HashSet<&'t str> {
+        members.get(member_key).map(|template_key| languages(template_key)).unwrap_or_default()
+    };
+    for (member_key, required) in group.required_languages.iter() {
+        let available = member_languages(member_key);
+        let mut required: Vec<&str> = match required {
+            RequiredLanguages::List(list) => list.iter().map(|l| l.as_str()).collect(),
+            RequiredLanguages::OfMember(other) => member_languages(other).into_iter().collect(),
+        };
+        required.sort();
+        if let Some(language) = required.into_iter().find(|l| !available.contains(l)) {
+            return Err(TerariumBuilderError::MissingMemberLanguage(
+                group_key.to_owned(), member_key.clone(), language.to_owned(),
+            ));
+        }
+    }
+    let mut sources = group.member_outputs.values().flat_map(|inputs| inputs.values());
+    if let Some(source) = sources.find(|s| !members.contains_key(s.as_str())) {
+        return Err(TerariumBuilderError::MissingGroupMembers(group_key.to_owned(), vec![source.clone()]));
+    }
+    group.render_order(&members).map_err(TerariumBuilderError::CyclicDependency)?;
+    Ok(())
+}
+
+
+/// Weak reference of the instance to itself set by the `TerariumBuilder::build_shared()`.
+/// Clones are independent instances, so they do not inherit it.
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct SharedInstance(std::sync::Weak<Terarium>);
+
+
+#[cfg(feature = "async")]
+impl Clone for SharedInstance {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+
+/// Content waiting for the registration in the `Tera`.
+struct RawContent {
+    name: String,
+    content: String,
+    autoescape: Option<bool>,
+}
+
+
+/// Add all contents into the `tera` at once.
+fn add_contents(tera: &mut Tera, contents: Vec<RawContent>) -> Result<(), TeraError> {
+    tera.add_raw_templates(contents.iter().map(|c| (c.name.as_str(), c.content.as_str())))?;
+    for content in contents {
+        if let Some(autoescape) = content.autoescape {
+            set_autoescape(tera, &content.name, autoescape);
+        }
+    }
+    Ok(())
+}
+
+
+/// Suffix of template paths escaped by the `Tera` of every instance.
+const AUTOESCAPE_SUFFIX: &str = "#autoescape";
+
+
+/// Force escaping of the content regardless of its name.
+/// `Tera` decides by the template path when it is set, so the path is set to end (or not) with the `AUTOESCAPE_SUFFIX`
+/// registered by the `TerariumBuilder::build()`.
+fn set_autoescape(tera: &mut Tera, name: &str, autoescape: bool) {
+    let suffix = match autoescape {
+        true => AUTOESCAPE_SUFFIX,
+        false => "#raw",
+    };
+    if let Some(template) = tera.templates.get_mut(name) {
+        template.path = Some(format!("{}{}", name, suffix));
+    }
+}
+
+
+/// Successfully rendered template passed to the callback of the `Terarium::render_for_testing()`.
+#[derive(Debug)]
+pub struct TestRender<'a> {
+    /// Key of the rendered template.
+    pub template_key: &'a str,
+    /// Language of the rendered content.
+    pub language: &'a str,
+    /// Variant of the rendered content.
+    pub variant: Option<&'a str>,
+    /// Kind of the rendered content.
+    pub kind: ContentKind,
+    /// Fingerprint of the rendered template.
+    pub fingerprint: u64,
+    /// Rendered output.
+    pub output: &'a str,
+}
+
+
+/// Template which cannot be rendered by the `Terarium::warm_up()`.
+#[derive(Debug)]
+pub struct WarmUpFailure {
+    /// Key of the failed template.
+    pub template_key: String,
+    /// Language of the failed content.
+    pub language: String,
+    /// Variant of the failed content.
+    pub variant: Option<String>,
+    /// Render error.
+    pub error: TerariumError,
+}
+
+
+/// Errors returned by `Terarium` operations.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TerariumError {
+    /// Requested template was not found.
+    #[error("There is no template")]
+    TemplateNotFound,
+    /// Request language was not found for template.
+    #[error("Language not found")]
+    LanguageNotFound,
+    /// Requested group was not found.
+    #[error("There is no group")]
+    GroupNotFound,
+    /// Request renders a single template but the operation requires a group.
+    #[error("Request does not render a group")]
+    NotAGroup,
+    /// Rendered output is not valid for its content kind.
+    #[error("Rendered output is not valid: {0}")]
+    InvalidOutput(String),
+    /// Two consecutive renders of the template produced different outputs.
+    #[error("Template {0} is not deterministic")]
+    NondeterministicOutput(String),
+    /// Requested member was not found in the group.
+    #[error("There is no group member {0}")]
+    MemberNotFound(String),
+    /// The template or the group is rendered outside its validity window and has no fallback.
+    #[error("Template or group {0} is not valid at this time")]
+    OutsideValidity(String),
+    /// Rendered output contains template syntax (see `PlaceholderCheck::Reject`).
+    #[error("Output of template {0} contains template syntax at line {1}")]
+    LeftoverPlaceholder(String, usize),
+    /// Output of the tagged template violates a compliance rule.
+    #[error("Template {0} violates compliance rule: {1}")]
+    ComplianceViolation(String, String),
+    /// Group hook rejected the rendered group.
+    #[error("Group {0} was rejected: {1}")]
+    GroupRejected(String, String),
+    /// There is no catalog snapshot effective at the requested time.
+    #[error("There is no catalog at the time")]
+    CatalogNotFound,
+    /// Catalog of the namespace cannot be loaded (see `NamespacedCatalog`).
+    #[error("Catalog of namespace {0} cannot be loaded: {1}")]
+    NamespaceUnavailable(String, String),
+    /// Namespace of the request exceeded its quota.
+    #[error("Namespace {0} exceeded quota of {1}")]
+    QuotaExceeded(String, String),
+    /// Group members use outputs of each other in a cycle.
+    #[error("Group members form a cycle: {}", .0.join(" -> "))]
+    CyclicMembers(Vec<String>),
+    /// Render was aborted by the `Cancellation`.
+    #[error("Render was cancelled")]
+    Cancelled,
+    /// Render did not finish by the deadline of the request (see `RenderRequest::with_deadline()`).
+    #[error("Render exceeded its deadline")]
+    DeadlineExceeded,
+    /// Failure injected by the `ChaosMode`.
+    #[error("Render of template {0} failed by chaos mode")]
+    InjectedFailure(String),
+    /// Identity of the target differs from the identity expected by the client (see `Terarium::identity()`).
+    #[error("Client expects identity {0} but the catalog has identity {1}")]
+    CatalogVersionMismatch(String, String),
+    /// Template of the group member was removed (see `DanglingMemberPolicy::MarkBroken`).
+    #[error("Template of member {1} of group {0} was removed")]
+    BrokenMember(String, String),
+    /// Total size of rendered contents exceeds the limit of the request (see `RenderRequest::with_output_limit()`).
+    #[error("Output has {0} bytes but the limit is {1} bytes")]
+    OutputTooLarge(usize, usize),
+    /// Context misses variables required by members of the group
+    /// (see `TerariumBuilder::set_group_context_validation()`).
+    #[error("Context of group {0} misses variables: {}", .1.join(", "))]
+    MissingContextVariables(String, Vec<String>),
+
+    /// Rendered output cannot be written.
+    #[error("Cannot write rendered output")]
+    WriteFailed(#[source] std::io::Error),
+
+    /// Kept source of the content (identified by its name) cannot be loaded.
+    #[error("Cannot load source of content {0}")]
+    SourceUnavailable(String, #[source] std::io::Error),
+
+    /// Error propagated from underlying `Tera` instance when rendering the template (identified by its key).
+    #[error("Error when rendering template {0}")]
+    RenderingFailed(String, #[source] TeraError),
+
+    /// PDF document cannot be generated.
+    #[cfg(feature = "pdf")]
+    #[error("Cannot generate PDF document")]
+    PdfFailed(#[from] crate::PdfError),
+}
+
+
+impl TerariumError {
+    /// Return stable code of the error for machine handling (e.g. mapping to HTTP statuses or metrics labels).
+    /// Errors wrapping other errors of this crate return codes of the wrapped errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TemplateNotFound => "TEMPLATE_NOT_FOUND",
+            Self::LanguageNotFound => "LANGUAGE_NOT_FOUND",
+            Self::GroupNotFound => "GROUP_NOT_FOUND",
+            Self::NotAGroup => "NOT_A_GROUP",
+            Self::InvalidOutput(_) => "INVALID_OUTPUT",
+            Self::NondeterministicOutput(_) => "NONDETERMINISTIC_OUTPUT",
+            Self::MemberNotFound(_) => "MEMBER_NOT_FOUND",
+            Self::OutsideValidity(_) => "OUTSIDE_VALIDITY",
+            Self::LeftoverPlaceholder(..) => "LEFTOVER_PLACEHOLDER",
+            Self::ComplianceViolation(..) => "COMPLIANCE_VIOLATION",
+            Self::GroupRejected(..) => "GROUP_REJECTED",
+            Self::CatalogNotFound => "CATALOG_NOT_FOUND",
+            Self::NamespaceUnavailable(..) => "NAMESPACE_UNAVAILABLE",
+            Self::QuotaExceeded(..) => "QUOTA_EXCEEDED",
+            Self::CyclicMembers(_) => "CYCLIC_MEMBERS",
+            Self::Cancelled => "CANCELLED",
+            Self::DeadlineExceeded => "DEADLINE_EXCEEDED",
+            Self::InjectedFailure(_) => "INJECTED_FAILURE",
+            Self::CatalogVersionMismatch(..) => "CATALOG_VERSION_MISMATCH",
+            Self::BrokenMember(..) => "BROKEN_MEMBER",
+            Self::MissingContextVariables(..) => "MISSING_CONTEXT_VARIABLES",
+            Self::OutputTooLarge(..) => "OUTPUT_TOO_LARGE",
+            Self::WriteFailed(_) => "WRITE_FAILED",
+            Self::SourceUnavailable(..) => "SOURCE_UNAVAILABLE",
+            Self::RenderingFailed(..) => "RENDER_FAILED",
+            #[cfg(feature = "pdf")]
+            Self::PdfFailed(error) => error.code(),
+        }
+    }
+}
+
+
+/// Stable identifier of a template assigned by the `TerariumBuilder` when the template is added.
+/// Groups refer to templates by identifiers, so they keep referring to renamed templates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct TemplateId(u64);
+
+
+/// Build the `Terarium` instance.
+#[derive(Default)]
+pub struct TerariumBuilder {
+    templates: HashMap<String, Template>,
+    /// Identifiers of templates by their current and former (renamed) keys.
+    template_ids: HashMap<String, TemplateId>,
+    /// Current keys of templates by their identifiers.
+    current_keys: HashMap<TemplateId, String>,
+    next_template_id: u64,
+    groups: HashMap<String, TemplateGroup>,
+    /// Identifiers of templates of group members by group keys and member keys.
+    group_member_ids: HashMap<String, HashMap<String, TemplateId>>,
+    observer: Option<Arc<dyn RenderObserver>>,
+    validators: HashMap<ContentKind, Vec<Arc<dyn OutputValidator>>>,
+    brand_resolver: Option<Arc<dyn BrandResolver>>,
+    output_normalization: Option<OutputNormalization>,
+    determinism: Determinism,
+    verify_determinism: bool,
+    placeholder_check: PlaceholderCheck,
+    missing_variables: MissingVariables,
+    profile: Profile,
+    caching_disabled: bool,
+    watermark: Option<String>,
+    chaos: Option<ChaosMode>,
+    source_storage: SourceStorage,
+    buffer_pool: BufferPool,
+    limits: BuildLimits,
+    localization: LocalizationPolicy,
+    preprocessing: Preprocessing,
+    handlebars_partials: HandlebarsPartials,
+    empty_group_policy: EmptyGroupPolicy,
+    dangling_member_policy: DanglingMemberPolicy,
+    key_normalization: KeyNormalization,
+    rtl_languages: HashSet<String>,
+    language_aliases: HashMap<String, String>,
+    display_variables: HashMap<String, String>,
+    snippets: SnippetCatalog,
+    group_hooks: Vec<Arc<dyn GroupHook>>,
+    deduplicate_members: bool,
+    validate_group_context: bool,
+    #[cfg(feature = "async")]
+    group_parallelism: Option<usize>,
+    link_rewriter: Option<Arc<dyn LinkRewriter>>,
+    compliance_rules: Vec<ComplianceRule>,
+    flag_provider: Option<Arc<dyn FlagProvider>>,
+    value_resolver: Option<Arc<dyn ValueResolver>>,
+    tag_cache_policies: Vec<(String, CachePolicy)>,
+    render_cache: Option<Arc<dyn RenderCache>>,
+    quota_policy: Option<Arc<dyn QuotaPolicy>>,
+}
+
+
+impl TryFrom<&DirectoryLoader> for TerariumBuilder {
+    type Error = TerariumBuilderError;
+
+    /// Create builder with templates loaded by the `loader`.
+    fn try_from(loader: &DirectoryLoader) -> Result<Self, Self::Error> {
+        let mut builder = Self::default();
+        builder.load_templates(loader)?;
+        Ok(builder)
+    }
+}
+
+
+impl TerariumBuilder {
+    /// Add new template to the new instance.
+    /// If template exist, it will be replaced and keeps its identifier. A former key of a renamed template gets new
+    /// identifier, so it stops referring to the renamed template (see `rename_template()`).
+    pub fn add_template(&mut self, key: String, template: Template) -> Result<(), TerariumBuilderError> {
+        if !self.templates.contains_key(&key) {
+            let id = TemplateId(self.next_template_id);
+            self.next_template_id += 1;
+            self.template_ids.insert(key.clone(), id);
+            self.current_keys.insert(id, key.clone());
+        }
+        self.templates.insert(key, template);
+        Ok(())
+    }
+
+    /// Add all templates loaded by the `loader`.
+    /// Existing templates with the same keys are replaced.
+    pub fn load_templates(&mut self, loader: &DirectoryLoader) -> Result<(), TerariumBuilderError> {
+        for (key, template) in loader.load()? {
+            self.add_template(key, template)?;
+        }
+        Ok(())
+    }
+
+    /// Add all templates from files in the `directory` whose relative paths match the `pattern`
+    /// (e.g. `emails/*.tera`). Template keys and languages are taken from paths by the `PathConvention::KeyDirectory`,
+    /// use the `load_templates()` with the `DirectoryLoader` for other layouts. Syntax errors are reported by the
+    /// `build()` with contents named by their paths.
+    pub fn add_templates_from_dir(
+        &mut self,
+        directory: impl Into<PathBuf>,
+        pattern: &str,
+    ) -> Result<(), TerariumBuilderError> {
+        self.load_templates(&DirectoryLoader::new(directory).matching(pattern))
+    }
+
+    /// Rename template keys by the `migration` in templates and groups added so far.
+    /// Groups refer to templates by identifiers assigned when templates are added, so old keys stay valid in members
+    /// of groups added later.
+    pub fn migrate_keys(&mut self, migration: &KeyMigration) -> Result<(), TerariumBuilderError> {
+        migration.apply(&mut self.templates, &mut self.groups)?;
+        let renamed: Vec<_> = migration.renames()
+            .filter_map(|(from, to)| Some((self.template_ids.get(from).copied()?, to.clone())))
+            .collect();
+        for (id, key) in renamed {
+            self.template_ids.insert(key.clone(), id);
+            self.current_keys.insert(id, key);
+        }
+        Ok(())
+    }
+
+    /// Rename the template (see `KeyMigration`).
+    /// Groups refer to the template by its identifier, so group definitions don't have to be changed and the old key
+    /// stays valid in members of groups added later, until a template is added with the old key again.
+    pub fn rename_template(&mut self, old_key: String, new_key: String) -> Result<(), TerariumBuilderError> {
+        self.migrate_keys(&KeyMigration::default().rename(old_key, new_key))
+    }
+
+    /// Add new group into new instance
+    /// If group with same name exists, it is replaced.
+    /// Members refer to templates by identifiers, so they follow templates renamed later.
+    pub fn add_group(&mut self, key: String, group: impl Into<TemplateGroup>) -> Result<(), TerariumBuilderError> {
+        let mut group: TemplateGroup = group.into();
+        let mut member_ids = HashMap::with_capacity(group.members.len());
+        for (member_key, template_key) in group.members.iter_mut() {
+            let id = self.template_ids.get(template_key.as_str()).copied();
+            let current = id.and_then(|id| self.current_keys.get(&id)).filter(|k| self.templates.contains_key(*k));
+            let (Some(id), Some(current)) = (id, current) else {
+                return Err(TerariumBuilderError::TemplateNotFound(template_key.to_owned()));
+            };
+            *template_key = current.clone();
+            member_ids.insert(member_key.clone(), id);
+        }
+
+        // Add group to lookup
+        self.group_member_ids.insert(key.clone(), member_ids);
+        self.groups.insert(key, group);
+        Ok(())
+    }
+
+    /// Set observer notified about rendering events of the new instance.
+    pub fn set_observer<O: RenderObserver + 'static>(&mut self, observer: O) {
+        self.observer = Some(Arc::new(observer));
+    }
+
+    /// Add validator of rendered outputs of contents of the `kind`.
+    /// Render fails with the `TerariumError::InvalidOutput` when any validator rejects the output.
+    pub fn add_output_validator<V: OutputValidator + 'static>(&mut self, kind: ContentKind, validator: V) {
+        self.validators.entry(kind).or_default().push(Arc::new(validator));
+    }
+
+    /// Set resolver of brands used by requests with brand id (see `RenderRequest::with_brand()`).
+    pub fn set_brand_resolver<R: BrandResolver + 'static>(&mut self, resolver: R) {
+        self.brand_resolver = Some(Arc::new(resolver));
+    }
+
+    /// Set normalization applied to all rendered outputs (see `OutputNormalization`).
+    pub fn set_output_normalization(&mut self, normalization: OutputNormalization) {
+        self.output_normalization = Some(normalization);
+    }
+
+    /// Set handling of nondeterministic template functions (`now()` and `get_random()`).
+    pub fn set_determinism(&mut self, determinism: Determinism) {
+        self.determinism = determinism;
+    }
+
+    /// Render each content twice and fail with the `TerariumError::NondeterministicOutput` when outputs differ.
+    pub fn set_verify_determinism(&mut self, verify: bool) {
+        self.verify_determinism = verify;
+    }
+
+    /// Set handling of template syntax (`{{`, `{%` or `{#`) left in rendered outputs.
+    pub fn set_placeholder_check(&mut self, check: PlaceholderCheck) {
+        self.placeholder_check = check;
+    }
+
+    /// Set rendering of variables missing in contexts, requests may override it
+    /// (see `RenderRequest::with_missing_variables()`).
+    pub fn set_missing_variables(&mut self, missing_variables: MissingVariables) {
+        self.missing_variables = missing_variables;
+    }
+
+    /// Set environment the instance runs in and behaviors of the profile (see `Profile`).
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.missing_variables = profile.missing_variables();
+        self.placeholder_check = profile.placeholder_check();
+        self.caching_disabled = !profile.caching();
+    }
+
+    /// Enable or disable caching of outputs of templates with cache policy.
+    pub fn set_caching(&mut self, caching: bool) {
+        self.caching_disabled = !caching;
+    }
+
+    /// Set sizing of the per-thread pool of output buffers (see `BufferPool`).
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.buffer_pool = pool;
+    }
+
+    /// Set how sources of contents are kept by the built instance (see `SourceStorage`).
+    pub fn set_source_storage(&mut self, storage: SourceStorage) {
+        self.source_storage = storage;
+    }
+
+    /// Set banner (e.g. HTML comment or visible box) injected into all HTML outputs when the profile is not
+    /// production, so screenshots and test sends are unmistakably marked.
+    pub fn set_watermark(&mut self, banner: String) {
+        self.watermark = Some(banner);
+    }
+
+    /// Inject synthetic failures and delays into template renders when the profile is not production
+    /// (see `ChaosMode`), so retry and fallback logic of the application can be tested.
+    pub fn set_chaos_mode(&mut self, chaos: ChaosMode) {
+        self.chaos = Some(chaos);
+    }
+
+    /// Set limits checked when the instance is built and when templates are added at runtime.
+    pub fn set_limits(&mut self, limits: BuildLimits) {
+        self.limits = limits;
+    }
+
+    /// Set behavior for groups without members.
+    pub fn set_empty_group_policy(&mut self, policy: EmptyGroupPolicy) {
+        self.empty_group_policy = policy;
+    }
+
+    /// Set behavior for group members referencing templates removed by the `Terarium::remove_template()`.
+    pub fn set_dangling_member_policy(&mut self, policy: DanglingMemberPolicy) {
+        self.dangling_member_policy = policy;
+    }
+
+    /// Translate sources of all templates from the dialect into Tera syntax (see `SourceDialect`).
+    /// Constructs which cannot be translated are listed by the `Terarium::untranslated_constructs()`.
+    pub fn set_source_dialect(&mut self, dialect: SourceDialect) {
+        self.preprocessing.dialect = dialect;
+    }
+
+    /// Translate handlebars partial calls (`{{> name}}`) in sources of all templates into Tera includes
+    /// (see `translate_partials()`), so templates migrated from handlebars can use `HandlebarsPartials`.
+    pub fn set_handlebars_compatibility(&mut self, enabled: bool) {
+        self.preprocessing.handlebars_partials = enabled;
+    }
+
+    /// Normalize sources of all templates by the `format_content()`, so exported catalogs differ only in content.
+    /// Formatting may change whitespaces of rendered outputs.
+    pub fn set_source_formatting(&mut self, enabled: bool) {
+        self.preprocessing.format = enabled;
+    }
+
+    /// Add handlebars partials includable by all templates (see `HandlebarsPartials`).
+    /// Existing partials with the same names are replaced.
+    pub fn add_handlebars_partials(&mut self, partials: HandlebarsPartials) {
+        self.handlebars_partials.extend(partials);
+    }
+
+    /// Require every template to have content in all `languages`, the `enforcement` sets how missing languages are
+    /// handled (see `LocalizationEnforcement`).
+    pub fn set_required_languages(&mut self, languages: Vec<String>, enforcement: LocalizationEnforcement) {
+        self.localization = LocalizationPolicy { languages, enforcement };
+    }
+
+    /// Set how template and group keys are matched when rendering.
+    pub fn set_key_normalization(&mut self, normalization: KeyNormalization) {
+        self.key_normalization = normalization;
+    }
+
+    /// Set storage of outputs of templates with cache policy (see `Template::set_cache_policy()`).
+    /// The `MemoryRenderCache` is used by default.
+    pub fn set_render_cache<C: RenderCache + 'static>(&mut self, cache: C) {
+        self.render_cache = Some(Arc::new(cache));
+    }
+
+    /// Set quota consulted before renders of requests (see `RenderRequest::with_namespace()`).
+    pub fn set_quota_policy<Q: QuotaPolicy + 'static>(&mut self, quota: Q) {
+        self.quota_policy = Some(Arc::new(quota));
+    }
+
+    /// Mark the language as written right to left.
+    /// Languages with region subtag (e.g. `ar-EG`) are right to left when their primary language is marked.
+    pub fn add_rtl_language(&mut self, language: String) {
+        self.rtl_languages.insert(language);
+    }
+
+    /// Render the `alias` language (e.g. `no`) by contents of the `language` (e.g. `nb`).
+    /// Aliases are applied to requested and fallback languages before contents are looked up and they are not chained.
+    pub fn add_language_alias(&mut self, alias: String, language: String) {
+        self.language_aliases.insert(alias, language);
+    }
+
+    /// Replace the `{name}` placeholder in display names of templates and groups by the `value`
+    /// (e.g. product name differing between deployments, see `DisplayName`).
+    pub fn add_display_variable(&mut self, name: String, value: String) {
+        self.display_variables.insert(name, value);
+    }
+
+    /// Add text of the snippet in the language, called from templates as `{{ snippet(key="cta.view_order") }}`.
+    /// Snippets are short localized strings (e.g. button labels) not worth own templates. The text is taken
+    /// from the language of the rendered content or from fallback languages of the render.
+    pub fn add_snippet(&mut self, key: String, language: String, text: String) {
+        self.snippets.insert(key, language, text);
+    }
+
+    /// Add hook invoked with rendered members of every group (see `GroupHook`).
+    pub fn add_group_hook<H: GroupHook + 'static>(&mut self, hook: H) {
+        self.group_hooks.push(Arc::new(hook));
+    }
+
+    /// Render each template of a group only once in each language and reuse the output for other members, even when
+    /// members reference the template by different keys or through validity fallbacks. Outputs are kept only during
+    /// the group render. Members using outputs of other members (see `TemplateGroupBuilder::use_member_output()`)
+    /// are always rendered.
+    pub fn set_member_deduplication(&mut self, deduplicate: bool) {
+        self.deduplicate_members = deduplicate;
+    }
+
+    /// Check the context of a group render contains all variables required by its members before any member is
+    /// rendered, so no member is rendered when the context is incomplete. Variables used only in conditions, loops
+    /// or with the `default` filter are not required, nor are variables filled by outputs of other members.
+    pub fn set_group_context_validation(&mut self, enabled: bool) {
+        self.validate_group_context = enabled;
+    }
+
+    /// Set maximal number of members of a group rendered concurrently by the `Terarium::render_group_async()`.
+    #[cfg(feature = "async")]
+    pub fn set_group_parallelism(&mut self, parallelism: usize) {
+        self.group_parallelism = Some(parallelism);
+    }
+
+    /// Set rewriter of link URLs in HTML outputs (see `LinkRewriter`).
+    pub fn set_link_rewriter<R: LinkRewriter + 'static>(&mut self, rewriter: R) {
+        self.link_rewriter = Some(Arc::new(rewriter));
+    }
+
+    /// Add rule checked on HTML and text outputs of templates with its tag (see `ComplianceRule`).
+    pub fn add_compliance_rule(&mut self, rule: ComplianceRule) {
+        self.compliance_rules.push(rule);
+    }
+
+    /// Set provider of feature flags evaluated by the `feature()` template function (see `FlagProvider`).
+    pub fn set_flag_provider<P: FlagProvider + 'static>(&mut self, provider: P) {
+        self.flag_provider = Some(Arc::new(provider));
+    }
+
+    /// Set resolver of values requested by the `resolve()` template function (see `ValueResolver`).
+    pub fn set_value_resolver<R: ValueResolver + 'static>(&mut self, resolver: R) {
+        self.value_resolver = Some(Arc::new(resolver));
+    }
+
+    /// Cache outputs of templates with the tag by the `policy` unless the template has own policy.
+    pub fn set_tag_cache_policy(&mut self, tag: String, policy: CachePolicy) {
+        self.tag_cache_policies.retain(|(t, _)| *t != tag);
+        self.tag_cache_policies.push((tag, policy));
+    }
+
+    /// Build new `Terarium` instance shared by the `Arc`, so stale cached outputs are re-rendered in background
+    /// (see `CachePolicy::stale_while_revalidate()`).
+    /// The instance refers to itself weakly, so the `Arc::get_mut()` does not give access to it, mutable access by
+    /// the `Arc::make_mut()` makes the instance re-render stale outputs immediately.
+    #[cfg(feature = "async")]
+    pub fn build_shared(self) -> Result<Arc<Terarium>, TerariumBuilderError> {
+        let mut instance = self.build()?;
+        Ok(Arc::new_cyclic(|shared| {
+            instance.shared = SharedInstance(shared.clone());
+            instance
+        }))
+    }
+
+    /// Build new `Terarium` instance based on stored templates and groups.
+    pub fn build(mut self) -> Result<Terarium, TerariumBuilderError> {
+        let start = Instant::now();
+        let mut instance = Terarium::default();
+        let mut tera = Tera::default();
+        tera.autoescape_on(vec![".html", ".htm", ".xml", AUTOESCAPE_SUFFIX]);
+        register_filters(&mut tera);
+        register_functions(&mut tera, self.determinism);
+        if let Some(provider) = self.flag_provider.clone() {
+            tera.register_function("feature", feature_function(provider));
+        }
+        if let Some(resolver) = self.value_resolver.clone() {
+            tera.register_function("resolve", resolve_function(resolver));
+        }
+        self.snippets.set_aliases(self.language_aliases.clone());
+        let snippets = Arc::new(std::mem::take(&mut self.snippets));
+        if !snippets.is_empty() {
+            tera.register_function("snippet", snippet_function(snippets.clone()));
+        }
+
+        for (template_key, template) in self.templates.iter_mut() {
+            let untranslated = self.preprocessing.apply(template);
+            if !untranslated.is_empty() {
+                instance.untranslated.insert(template_key.clone(), untranslated);
+            }
+        }
+
+        // point members to current keys of their templates and validate groups
+        for (group_key, group) in self.groups.iter_mut() {
+            for (member_key, id) in self.group_member_ids.get(group_key).into_iter().flatten() {
+                let template_key = group.members.get_mut(member_key);
+                if let (Some(template_key), Some(key)) = (template_key, self.current_keys.get(id)) {
+                    *template_key = key.clone();
+                }
+            }
+        }
+        resolve_inheritance(&mut self.groups)?;
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        groups.sort_by_key(|(group_key, _)| *group_key);
+        for (group_key, group) in groups {
+            let languages = |template_key: &str| -> HashSet<&str> {
+                self.templates
+                    .get(template_key)
+                    .map(|t| t.contents().iter().flat_map(|c| c.languages.iter().map(|l| l.as_str())).collect())
+                    .unwrap_or_default()
+            };
+            check_group(group_key, group, self.templates.keys(), languages, self.empty_group_policy)?;
+        }
+
+        // build key lookups
+        instance.template_keys = KeyIndex::new(self.key_normalization, self.templates.keys())
+            .map_err(|(a, b)| TerariumBuilderError::KeyConflict(a, b))?;
+        instance.group_keys = KeyIndex::new(self.key_normalization, self.groups.keys())
+            .map_err(|(a, b)| TerariumBuilderError::KeyConflict(a, b))?;
+
+        // check limits
+        self.limits.check_template_count(self.templates.len())?;
+        for (template_key, template) in self.templates.iter() {
+            self.limits.check_template(template_key, template)?;
+        }
+        let mut template_keys: Vec<_> = self.templates.keys().collect();
+        template_keys.sort();
+        for template_key in template_keys {
+            self.localization.check(template_key, &self.templates[template_key], self.observer.as_deref())?;
+        }
+
+        // build templates, contents are added at once, so inheritance chains are built only once
+        instance.source_storage = self.source_storage;
+        let mut contents = Vec::new();
+        for (template_key, template) in self.templates {
+            contents.extend(instance.insert_template(template_key, template));
+        }
+        let partials = self.handlebars_partials.into_sources();
+        contents.extend(partials.map(|(name, content)| RawContent { name, content, autoescape: None }));
+        add_contents(&mut tera, contents)?;
+        instance.limits = self.limits;
+        instance.localization = self.localization;
+        instance.preprocessing = self.preprocessing;
+        instance.check_dependencies(&tera)?;
+
+        instance.tera = tera;
+        instance.groups = self.groups;
+        instance.observer = self.observer;
+        instance.validators = self.validators;
+        instance.brand_resolver = self.brand_resolver;
+        instance.output_normalization = self.output_normalization;
+        instance.determinism = self.determinism;
+        instance.verify_determinism = self.verify_determinism;
+        instance.placeholder_check = self.placeholder_check;
+        instance.missing_variables = self.missing_variables;
+        instance.profile = self.profile;
+        instance.caching_disabled = self.caching_disabled;
+        instance.watermark = self.watermark.filter(|_| !self.profile.is_production());
+        instance.chaos = self.chaos.filter(|_| !self.profile.is_production());
+        instance.rtl_languages = self.rtl_languages;
+        instance.language_aliases = self.language_aliases;
+        instance.display_variables = self.display_variables;
+        instance.snippets = snippets;
+        instance.buffer_pool = self.buffer_pool;
+        instance.group_hooks = self.group_hooks;
+        instance.deduplicate_members = self.deduplicate_members;
+        instance.validate_group_context = self.validate_group_context;
+        instance.dangling_member_policy = self.dangling_member_policy;
+        instance.empty_group_policy = self.empty_group_policy;
+        #[cfg(feature = "async")]
+        {
+            instance.group_parallelism = self.group_parallelism.unwrap_or(DEFAULT_GROUP_PARALLELISM);
+        }
+        instance.link_rewriter = self.link_rewriter;
+        instance.compliance_rules = self.compliance_rules;
+        instance.tag_cache_policies = self.tag_cache_policies;
+        instance.quota_policy = self.quota_policy;
+        instance.render_cache = Some(self.render_cache.unwrap_or_else(|| Arc::new(MemoryRenderCache::default())));
+        instance.update_templates_fingerprint();
+        instance.shrink_to_fit();
+        instance.build_time = start.elapsed();
+        Ok(instance)
+    }
+}
+
+
+/// Errors returned by `TerariumBuilder` struct.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TerariumBuilderError {
+    /// Cannot build template in underlying `Tera` instance.
+    #[error("Unable to build template")]
+    TemplateBuildingError(#[source] TeraError),
+    /// Template was not found (when building group).
+    #[error("Cannot build template groups - some templates are missing")]
+    TemplateNotFound(String),
+    /// Two keys are the same after normalization.
+    #[error("Keys {0} and {1} are in conflict")]
+    KeyConflict(String, String),
+    /// Group has no members and empty groups are rejected.
+    #[error("Group {0} has no members")]
+    EmptyGroup(String),
+    /// Group does not contain any of the required members.
+    #[error("Group {0} requires one of members {1:?}")]
+    MissingGroupMembers(String, Vec<String>),
+    /// Group member has no content in the required language.
+    #[error("Member {1} of group {0} has no content in language {2}")]
+    MissingMemberLanguage(String, String, String),
+    /// Number of templates exceeds the limit.
+    #[error("There are {0} templates but at most {1} are allowed")]
+    TooManyTemplates(usize, usize),
+    /// Content of the template exceeds the size limit.
+    #[error("Template {0} has content of {1} bytes but at most {2} bytes are allowed")]
+    ContentTooLarge(String, usize, usize),
+    /// Contents include or extend each other in a cycle or group members use outputs of each other in a cycle.
+    #[error("Dependencies form a cycle: {}", .0.join(" -> "))]
+    CyclicDependency(Vec<String>),
+    /// Templates cannot be loaded.
+    #[error("Cannot load templates")]
+    LoadingFailed(#[from] LoaderError),
+    /// Content includes templates nested deeper than allowed.
+    #[error("Content {0} has include depth {1} but at most {2} is allowed")]
+    IncludeDepthExceeded(String, usize, usize),
+    /// Template has no content in a required language (see `LocalizationEnforcement::Error`).
+    #[error("Template {0} has no content in required language {1}")]
+    MissingLocalization(String, String),
+    /// Group extends group which does not exist.
+    #[error("Group {0} extends unknown group {1}")]
+    ParentGroupNotFound(String, String),
+    /// Removed template is referenced by a group member (see `DanglingMemberPolicy::Reject`).
+    #[error("Template {0} is used by member {2} of group {1}")]
+    TemplateInUse(String, String, String),
+}
+
+
+impl TerariumBuilderError {
+    /// Return stable code of the error for machine handling.
+    /// Errors wrapping other errors of this crate return codes of the wrapped errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TemplateBuildingError(_) => "TEMPLATE_BUILD_FAILED",
+            Self::TemplateNotFound(_) => "TEMPLATE_NOT_FOUND",
+            Self::KeyConflict(..) => "KEY_CONFLICT",
+            Self::EmptyGroup(_) => "EMPTY_GROUP",
+            Self::MissingGroupMembers(..) => "MISSING_GROUP_MEMBERS",
+            Self::MissingMemberLanguage(..) => "MISSING_MEMBER_LANGUAGE",
+            Self::TooManyTemplates(..) => "TOO_MANY_TEMPLATES",
+            Self::ContentTooLarge(..) => "CONTENT_TOO_LARGE",
+            Self::CyclicDependency(_) => "CYCLIC_DEPENDENCY",
+            Self::LoadingFailed(error) => error.code(),
+            Self::IncludeDepthExceeded(..) => "INCLUDE_DEPTH_EXCEEDED",
+            Self::MissingLocalization(..) => "MISSING_LOCALIZATION",
+            Self::ParentGroupNotFound(..) => "PARENT_GROUP_NOT_FOUND",
+            Self::TemplateInUse(..) => "TEMPLATE_IN_USE",
+        }
+    }
+}
+
+
+impl From<TeraError> for TerariumBuilderError {
+    fn from(value: TeraError) -> Self {
+        Self::TemplateBuildingError(value)
+    }
+}
+
+
+/// Additional methods for testing
+#[cfg(test)]
+impl TerariumBuilder {
+    /// Get template defined by its `key`.
+    /// If no template defined by given `key` exist, return `None`.
+    pub fn get_template(&mut self, key: &String) -> Option<&mut Template> {
+        self.templates.get_mut(key)
+    }
+
+    /// Remove template defined by the `key` from the builder and return it.
+    /// Returns `None` if no template with given `key` is defined.
+    pub fn remove_template(&mut self, key: &String) -> Option<Template> {
+        self.templates.remove(key)
+    }
+
+    /// Get group defined by the `key`.
+    /// Return `None` if no group defined by the `key` is found.
+    pub fn get_group(&mut self, key: &String) -> Option<&mut HashMap<String, String>> {
+        self.groups.get_mut(key).map(|g| &mut g.members)
+    }
+
+    /// Remove group defined by the `key` from the builder and return it.
+    /// Returns `None` if no group with given `key` is defined.
+    pub fn remove_group(&mut self, key: &String) -> Option<TemplateGroup> {
+        self.group_member_ids.remove(key);
+        self.groups.remove(key)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::TemplateGroupBuilder;
+
+    use super::*;
+
+    mod terarium_builder {
+        use crate::Content;
+
+        use super::*;
+
+        #[test]
+        fn add_template() {
+            let mut instance = make_instance();
+
+            let mut tpl = Template::default();
+            tpl.add_content(Content::new("foo".to_string(), vec!["1".to_owned(), "2".to_owned()])).unwrap();
+
+            instance.add_template(
+                "1".to_owned(),
+                tpl,
+            ).unwrap();
+
+            assert_eq!(instance.templates.len(), 1);
+            let template = instance.templates["1"].clone();
+            let contents = template.collect_contents();
+            assert_eq!(contents.len(), 1);
+        }
+
+        #[test]
+        fn group_manipulation() {
+            let mut instance = make_instance();
+            instance.add_template("1".to_owned(), Template::default()).unwrap();
+            instance.add_group("1".to_owned(), TemplateGroupBuilder::default().add_member("1".to_owned(), "1".to_owned()).build()).unwrap();
+            let grp = instance.get_group(&"1".to_owned());
+            assert!(grp.is_some());
+            let grp = grp.unwrap();
+            assert_eq!(grp.clone(), HashMap::<String, String>::from([("1".to_owned(), "1".to_owned())]));
+
+            instance.remove_group(&"1".to_owned());
+            assert!(instance.get_group(&"1".to_owned()).is_none())
+        }
+
+        #[test]
+        fn check_group_configuration() {
+            let mut instance = make_instance();
+            instance.add_template("1".to_owned(), Template::default()).unwrap();
+            instance.add_template("2".to_owned(), Template::default()).unwrap();
+            let result = instance.add_group(
+                "100".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("10".to_owned(), "1".to_owned())
+                    .add_member("20".to_owned(), "2".to_owned())
+                    .add_member("30".to_owned(), "3".to_owned())
+                    .build(),
+            );
+            assert!(result.is_err())
+        }
+
+        #[test]
+        fn empty_group_policy() {
+            let mut instance = make_instance();
+            for key in ["empty_b", "empty_c", "empty_a", "empty_d"] {
+                instance.add_group(key.to_owned(), TemplateGroupBuilder::default().build()).unwrap();
+            }
+            instance.set_empty_group_policy(EmptyGroupPolicy::Reject);
+            let result = instance.build();
+            assert!(matches!(result, Err(TerariumBuilderError::EmptyGroup(key)) if key == "empty_a"));
+        }
+
+        #[test]
+        fn required_group_members() {
+            let mut instance = make_instance();
+            instance.add_template("1".to_owned(), Template::default()).unwrap();
+            instance.add_group(
+                "email".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("subject".to_owned(), "1".to_owned())
+                    .require_member("subject".to_owned())
+                    .require_any_member(vec!["text".to_owned(), "html".to_owned()])
+                    .build(),
+            ).unwrap();
+            let result = instance.build();
+            assert!(matches!(result, Err(TerariumBuilderError::MissingGroupMembers(key, _)) if key == "email"));
+        }
+
+        #[test]
+        fn required_member_languages() {
+            let build = |group: TemplateGroupBuilder| {
+                let mut instance = make_instance();
+                let content = |language: &str| Content::new("".to_owned(), vec![language.to_owned()]);
+                let template_a = Template::new(vec![content("cs"), content("en")]).unwrap();
+                instance.add_template("template_a".to_owned(), template_a).unwrap();
+                instance.add_template("template_b".to_owned(), Template::new(vec![content("en")]).unwrap()).unwrap();
+                instance.add_group("email".to_owned(), group.build()).unwrap();
+                instance.build()
+            };
+            let group = TemplateGroupBuilder::default()
+                .add_member("html".to_owned(), "template_a".to_owned())
+                .add_member("subject".to_owned(), "template_b".to_owned());
+
+            assert!(build(group.clone().require_languages_of("html".to_owned(), "subject".to_owned())).is_ok());
+            let result = build(group.clone().require_languages_of("subject".to_owned(), "html".to_owned()));
+            assert!(matches!(result, Err(TerariumBuilderError::MissingMemberLanguage(_, m, l)) if m == "subject" && l == "cs"));
+            let result = build(group.require_languages("html".to_owned(), vec!["en".to_owned(), "de".to_owned()]));
+            assert!(matches!(result, Err(TerariumBuilderError::MissingMemberLanguage(_, _, l)) if l == "de"));
+        }
+
+        #[test]
+        fn build_limits() {
+            let content = |text: &str, name: &str| {
+                Template::new(vec![Content::new_named(text.to_owned(), vec!["en".to_owned()], name.to_owned())]).unwrap()
+            };
+            let build = |limits: BuildLimits| {
+                let mut builder = make_instance();
+                builder.add_template("a".to_owned(), content("{% include \"b\" %}", "a")).unwrap();
+                builder.add_template("b".to_owned(), content("{% include \"c\" %}", "b")).unwrap();
+                builder.add_template("c".to_owned(), content("c", "c")).unwrap();
+                builder.set_limits(limits);
+                builder.build()
+            };
+
+            assert!(build(BuildLimits::default().max_templates(3).max_content_bytes(20).max_include_depth(2)).is_ok());
+            assert!(matches!(build(BuildLimits::default().max_templates(2)), Err(TerariumBuilderError::TooManyTemplates(3, 2))));
+            let result = build(BuildLimits::default().max_content_bytes(10));
+            assert!(matches!(result, Err(TerariumBuilderError::ContentTooLarge(_, 17, 10))));
+            let result = build(BuildLimits::default().max_include_depth(1));
+            assert!(matches!(result, Err(TerariumBuilderError::IncludeDepthExceeded(name, 2, 1)) if name == "a"));
+
+            let mut instance = build(BuildLimits::default().max_include_depth(2)).unwrap();
+            let result = instance.add_template("c".to_owned(), content("{% include \"d\" %}", "c"), "admin");
+            assert!(result.is_ok());
+            let result = instance.add_template("d".to_owned(), content("d", "d"), "admin");
+            assert!(matches!(result, Err(TerariumBuilderError::IncludeDepthExceeded(_, 3, 2))));
+            assert!(instance.render_template(&Context::new(), "d", "en", None).is_err());
+        }
+
+        #[test]
+        fn cyclic_dependency() {
+            let mut builder = make_instance();
+            for (key, text) in [("a", "{% include \"b\" %}"), ("b", "{% include \"a\" %}")] {
+                let content = Content::new_named(text.to_owned(), vec!["en".to_owned()], key.to_owned());
+                builder.add_template(key.to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+            }
+            let result = builder.build();
+            assert!(matches!(result, Err(TerariumBuilderError::CyclicDependency(cycle)) if cycle == vec!["a", "b", "a"]));
+        }
+
+        #[test]
+        fn autoescape() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![
+                Content::new("{{ value }}".to_owned(), vec!["en".to_owned()]).with_autoescape(true),
+                Content::new_named("{{ value }}".to_owned(), vec!["cs".to_owned()], "raw.html".to_owned())
+                    .with_autoescape(false),
+                Content::new_named("{{ value }}".to_owned(), vec!["de".to_owned()], "default.html".to_owned()),
+            ]).unwrap();
+            builder.add_template("tpl".to_owned(), template).unwrap();
+            let instance = builder.build().unwrap();
+            let mut ctx = Context::new();
+            ctx.insert("value", "<b>");
+
+            assert_eq!(instance.render_template(&ctx, "tpl", "en", None).unwrap(), "&lt;b&gt;");
+            assert_eq!(instance.render_template(&ctx, "tpl", "cs", None).unwrap(), "<b>");
+            assert_eq!(instance.render_template(&ctx, "tpl", "de", None).unwrap(), "&lt;b&gt;");
+        }
+
+        #[test]
+        fn build_extends_in_any_order() {
+            let mut builder = make_instance();
+            for index in 0..20 {
+                let text = format!("{{% extends \"base\" %}}{{% block body %}}{}{{% endblock %}}", index);
+                let content = Content::new_named(text, vec!["en".to_owned()], format!("child_{}", index));
+                builder.add_template(format!("child_{}", index), Template::new(vec![content]).unwrap()).unwrap();
+            }
+            let base = "<{% block body %}{% endblock %}>".to_owned();
+            let base = Content::new_named(base, vec!["en".to_owned()], "base".to_owned());
+            builder.add_template("base".to_owned(), Template::new(vec![base]).unwrap()).unwrap();
+            let instance = builder.build().unwrap();
+
+            assert_eq!(instance.render_template(&Context::new(), "child_7", "en", None).unwrap(), "<7>");
+            assert!(instance.stats().build_time > Duration::ZERO);
+        }
+
+        #[test]
+        fn malformed_input_does_not_panic() {
+            let sources = [
+                "{{", "{% if %}", "{% for x in %}{% endfor %}", "{% block a %}", "{% extends \"missing\" %}",
+                "{% include \"missing\" %}", "{{ 1 + \"a\" }}", "{{ x | unknown_filter }}", "{% endif %}", "\u{0}{{",
+            ];
+            for source in sources {
+                let mut builder = make_instance();
+                let template = Template::new(vec![Content::new(source.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template("broken".to_owned(), template).unwrap();
+                if let Ok(instance) = builder.build() {
+                    assert!(instance.render_template(&Context::new(), "broken", "en", None).is_err(), "{}", source);
+                }
+            }
+
+            let mut builder = make_instance();
+            let group = TemplateGroupBuilder::default().add_member("a".to_owned(), "missing".to_owned()).build();
+            assert!(builder.add_group("missing".to_owned(), group).is_err());
+            let group = TemplateGroupBuilder::default().add_members_matching("[*?**").build();
+            builder.add_group("group".to_owned(), group).unwrap();
+            builder.build().unwrap();
+
+            let instance = make_instance().build().unwrap();
+            assert!(instance.render_template(&Context::new(), "", "", Some("")).is_err());
+            assert!(instance.render_matching(&Context::new(), "**?[", "en", None).unwrap().is_empty());
+            assert!(instance.render_group_concat(&Context::new(), "", &[""], "", "en", None).is_err());
+        }
+
+        #[test]
+        fn group_inheritance() {
+            let mut builder = make_instance();
+            for key in ["header", "body", "welcome"] {
+                let template = Template::new(vec![Content::new(key.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            let base = TemplateGroupBuilder::default()
+                .add_member("header".to_owned(), "header".to_owned())
+                .add_member("body".to_owned(), "body".to_owned())
+                .build();
+            builder.add_group("base_email".to_owned(), base).unwrap();
+            let group = TemplateGroupBuilder::default()
+                .extends("base_email".to_owned())
+                .add_member("body".to_owned(), "welcome".to_owned())
+                .build();
+            builder.add_group("welcome_email".to_owned(), group).unwrap();
+            let instance = builder.build().unwrap();
+            let output = instance.render_group(&Context::new(), "welcome_email", "en", None).unwrap();
+            assert_eq!(output["header"], "header");
+            assert_eq!(output["body"], "welcome");
+        }
+
+        #[test]
+        fn required_languages() {
+            let template = |languages: &[&str]| Template::new(vec![
+                Content::new("a".to_owned(), languages.iter().map(|l| l.to_string()).collect()),
+            ]).unwrap();
+            let mut builder = make_instance();
+            builder.add_template("a".to_owned(), template(&["en"])).unwrap();
+            builder.set_required_languages(vec!["en".to_owned(), "cs".to_owned()], LocalizationEnforcement::Error);
+            let result = builder.build();
+            assert!(matches!(result, Err(TerariumBuilderError::MissingLocalization(t, l)) if t == "a" && l == "cs"));
+
+            let mut builder = make_instance();
+            builder.add_template("a".to_owned(), template(&["en", "cs"])).unwrap();
+            builder.set_required_languages(vec!["en".to_owned(), "cs".to_owned()], LocalizationEnforcement::Error);
+            let mut instance = builder.build().unwrap();
+            let result = instance.add_template("b".to_owned(), template(&["cs"]), "test");
+            assert!(matches!(result, Err(TerariumBuilderError::MissingLocalization(t, l)) if t == "b" && l == "en"));
+            instance.add_template("b".to_owned(), template(&["cs", "en"]), "test").unwrap();
+        }
+
+        #[test]
+        fn source_dialect() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![
+                Content::new("{{ name|default('guest') }}{{ loop.length }}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            builder.add_template("greeting".to_owned(), template).unwrap();
+            builder.set_source_dialect(SourceDialect::Jinja2);
+            let mut instance = builder.build().unwrap();
+            assert_eq!(instance.untranslated_constructs()["greeting"][0].tag, "{{ loop.length }}");
+
+            let template = Template::new(vec![
+                Content::new("{{ name|default('guest') }}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            instance.add_template("greeting".to_owned(), template, "test").unwrap();
+            assert!(instance.untranslated_constructs().is_empty());
+            assert_eq!(instance.render_template(&Context::new(), "greeting", "en", None).unwrap(), "guest");
+        }
+
+        #[test]
+        fn source_formatting() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![
+                Content::new("{%if name%}\r\n{{name|upper}}  \r\n{%endif%}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            builder.add_template("greeting".to_owned(), template).unwrap();
+            builder.set_source_formatting(true);
+            builder.set_source_storage(SourceStorage::Plain);
+            let instance = builder.build().unwrap();
+            let source = instance.content_source("greeting", "en", None).unwrap().unwrap();
+            assert_eq!(source, "{% if name %}\n{{ name|upper }}\n{% endif %}");
+        }
+
+        #[test]
+        fn translator_notes() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![
+                Content::new("{# translator: Shown as title #}Hello".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            builder.add_template("greeting".to_owned(), template).unwrap();
+            let mut instance = builder.build().unwrap();
+            assert_eq!(instance.translator_notes("greeting")[0].note, "Shown as title");
+            assert_eq!(instance.render_template(&Context::new(), "greeting", "en", None).unwrap(), "Hello");
+
+            instance.remove_template("greeting", "test").unwrap();
+            assert!(instance.translator_notes("greeting").is_empty());
+        }
+
+        #[test]
+        fn display_names() {
+            let mut builder = TerariumBuilder::default();
+            let mut template = Template::new(vec![Content::new("Hello".to_owned(), vec!["en".to_owned()])]).unwrap();
+            let name = DisplayName::new("Uvítání v {product}".to_owned()).with_description("Klíč {key}".to_owned());
+            template.set_display_name("cs".to_owned(), name);
+            builder.add_template("greeting".to_owned(), template).unwrap();
+            builder.add_group("onboarding".to_owned(), TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "greeting".to_owned())
+                .display_name("en".to_owned(), DisplayName::new("Onboarding ({language})".to_owned()))
+                .build()).unwrap();
+            builder.add_display_variable("product".to_owned(), "Shop".to_owned());
+            builder.add_language_alias("sk".to_owned(), "cs".to_owned());
+            let instance = builder.build().unwrap();
+
+            let template = RenderTarget::Template("greeting".to_owned());
+            let display_name = instance.display_name(&template, "sk").unwrap();
+            assert_eq!(display_name.name, "Uvítání v Shop");
+            assert_eq!(display_name.description.as_deref(), Some("Klíč greeting"));
+            assert!(instance.display_name(&template, "en").is_none());
+            let group = RenderTarget::Group("onboarding".to_owned());
+            assert_eq!(instance.display_name(&group, "en").unwrap().name, "Onboarding (en)");
+        }
+
+        #[test]
+        fn search() {
+            let mut builder = TerariumBuilder::default();
+            let texts = [("welcome", "Welcome {{ name }}"), ("welcome_sms", "Hi"), ("invoice", "Your order")];
+            for (key, text) in texts {
+                let mut template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+                template.add_tag("onboarding".to_owned());
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            builder.add_group("greetings".to_owned(), TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "welcome".to_owned())
+                .description("Welcome e-mail".to_owned())
+                .build()).unwrap();
+            builder.set_source_storage(SourceStorage::Plain);
+            let instance = builder.build().unwrap();
+
+            let found: Vec<_> = instance.search("welcome").into_iter().map(|found| found.target).collect();
+            assert_eq!(found, vec![
+                RenderTarget::Template("welcome".to_owned()),
+                RenderTarget::Template("welcome_sms".to_owned()),
+                RenderTarget::Group("greetings".to_owned()),
+            ]);
+            assert_eq!(instance.search("wlcmsms")[0].target, RenderTarget::Template("welcome_sms".to_owned()));
+            assert_eq!(instance.search("onboard").len(), 3);
+            assert!(instance.search("order").is_empty());
+            let found = instance.search(SearchQuery::new("order").contents(true));
+            assert_eq!(found[0].target, RenderTarget::Template("invoice".to_owned()));
+            assert_eq!(found[0].field, SearchField::Content);
+        }
+
+        #[test]
+        fn snippets() {
+            let mut builder = TerariumBuilder::default();
+            let text = "{{ snippet(key=\"cta\") }}";
+            let template = Template::new(vec![Content::new(text.to_owned(), vec!["cs".to_owned(), "de".to_owned()])]);
+            builder.add_template("button".to_owned(), template.unwrap()).unwrap();
+            builder.add_snippet("cta".to_owned(), "cs".to_owned(), "Zobrazit".to_owned());
+            builder.add_snippet("cta".to_owned(), "en".to_owned(), "View".to_owned());
+            builder.add_language_alias("sk".to_owned(), "cs".to_owned());
+            let instance = builder.build().unwrap();
+
+            assert_eq!(instance.render_template(&Context::new(), "button", "cs", None).unwrap(), "Zobrazit");
+            assert_eq!(instance.render_template(&Context::new(), "button", "de", Some("en")).unwrap(), "View");
+            assert!(instance.render_template(&Context::new(), "button", "de", None).is_err());
+            assert_eq!(instance.snippet("cta", "sk"), Some("Zobrazit"));
+            assert_eq!(instance.snippet("cta", "de"), None);
+        }
+
+        #[test]
+        fn language_aliases() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![
+                Content::new("Hei".to_owned(), vec!["nb".to_owned()]),
+                Content::new("Hello".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            builder.add_template("greeting".to_owned(), template).unwrap();
+            builder.add_language_alias("no".to_owned(), "nb".to_owned());
+            builder.add_language_alias("en-GB".to_owned(), "en".to_owned());
+            let instance = builder.build().unwrap();
+            let context = Context::new();
+            assert_eq!(instance.render_template(&context, "greeting", "no", None).unwrap(), "Hei");
+            assert_eq!(instance.render_template(&context, "greeting", "de", Some("en-GB")).unwrap(), "Hello");
+            assert!(instance.render_template(&context, "greeting", "nn", None).is_err());
+        }
+
+        #[test]
+        fn group_context_validation() {
+            let mut builder = make_instance();
+            let subject = Template::new(vec![
+                Content::new("Hello {{ user.name }}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap();
+            let body = Template::new(vec![
+                Content::new(
+                    "{{ subject }} {{ order_id }}{% if vip %}{{ gift }}{% endif %}".to_owned(),
+                    vec!["en".to_owned()],
+                ),
+            ]).unwrap();
+            builder.add_template("subject".to_owned(), subject).unwrap();
+            builder.add_template("body".to_owned(), body).unwrap();
+            let group = TemplateGroupBuilder::default()
+                .add_member("subject".to_owned(), "subject".to_owned())
+                .add_member("body".to_owned(), "body".to_owned())
+                .use_member_output("body".to_owned(), "subject".to_owned(), "subject".to_owned())
+                .build();
+            builder.add_group("mail".to_owned(), group).unwrap();
+            builder.set_group_context_validation(true);
+            let instance = builder.build().unwrap();
+
+            let error = instance.render_group(&Context::new(), "mail", "en", None).unwrap_err();
+            let missing = vec!["order_id".to_owned(), "user".to_owned()];
+            assert!(matches!(&error, TerariumError::MissingContextVariables(g, v) if g == "mail" && *v == missing));
+            assert_eq!(error.code(), "MISSING_CONTEXT_VARIABLES");
+
+            let mut context = Context::new();
+            context.insert("user", &HashMap::from([("name", "john")]));
+            context.insert("order_id", &42);
+            let outputs = instance.render_group(&context, "mail", "en", None).unwrap();
+            assert_eq!(outputs["body"], "Hello john 42");
+        }
+
+        #[test]
+        fn rename_template() {
+            let mut builder = make_instance();
+            let template = Template::new(vec![Content::new("Hi".to_owned(), vec!["en".to_owned()])]).unwrap();
+            builder.add_template("header".to_owned(), template).unwrap();
+            let group = TemplateGroupBuilder::default().add_member("header".to_owned(), "header".to_owned()).build();
+            builder.add_group("before".to_owned(), group.clone()).unwrap();
+            builder.rename_template("header".to_owned(), "layout/header".to_owned()).unwrap();
+            builder.rename_template("layout/header".to_owned(), "layout/top".to_owned()).unwrap();
+            builder.add_group("after".to_owned(), group).unwrap();
+            assert!(builder.rename_template("missing".to_owned(), "other".to_owned()).is_err());
+            let template = Template::new(vec![Content::new("Hello".to_owned(), vec!["en".to_owned()])]).unwrap();
+            builder.add_template("layout/header".to_owned(), template).unwrap();
+            let group = TemplateGroupBuilder::default().add_member("header".to_owned(), "layout/header".to_owned());
+            builder.add_group("replaced".to_owned(), group.build()).unwrap();
+
+            let instance = builder.build().unwrap();
+            assert_eq!(instance.group("before").unwrap().members["header"], "layout/top");
+            assert_eq!(instance.group("after").unwrap().members["header"], "layout/top");
+            assert_eq!(instance.group("replaced").unwrap().members["header"], "layout/header");
+        }
+
+        #[test]
+        fn migrate_keys_keeps_group_references() {
+            let mut builder = TerariumBuilder::default();
+            for key in ["a", "b"] {
+                let template = Template::new(vec![Content::new(key.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            let group = TemplateGroupBuilder::default().add_member("first".to_owned(), "a".to_owned()).build();
+            builder.add_group("group".to_owned(), group).unwrap();
+            let migration = KeyMigration::default()
+                .rename("a".to_owned(), "b".to_owned())
+                .rename("b".to_owned(), "a".to_owned());
+            builder.migrate_keys(&migration).unwrap();
+
+            let instance = builder.build().unwrap();
+            assert_eq!(instance.group("group").unwrap().members["first"], "b");
+            let outputs = instance.render_group(&Context::new(), "group", "en", None).unwrap();
+            assert_eq!(outputs["first"], "a");
+        }
+
+        #[test]
+        fn error_codes() {
+            let instance = make_instance().build().unwrap();
+            let error = instance.render_template(&Context::new(), "missing", "en", None).unwrap_err();
+            assert_eq!(error.code(), "TEMPLATE_NOT_FOUND");
+            let error = TerariumError::RenderingFailed("a".to_owned(), TeraError::msg("failed"));
+            assert_eq!(error.code(), "RENDER_FAILED");
+
+            let template_error = crate::TemplateError::DuplicatedContentName("a".to_owned());
+            let error = TerariumBuilderError::from(LoaderError::InvalidTemplate("a".to_owned(), template_error));
+            assert_eq!(error.code(), "DUPLICATED_CONTENT_NAME");
+        }
+
+        #[test]
+        fn source_storage() {
+            let source = "<p>{{ name }}</p>".repeat(50);
+            let build = |storage: SourceStorage| {
+                let mut builder = TerariumBuilder::default();
+                builder.add_template("page".to_owned(), Template::new(vec![
+                    Content::new(source.clone(), vec!["en".to_owned()]),
+                ]).unwrap()).unwrap();
+                builder.set_source_storage(storage);
+                builder.build().unwrap()
+            };
+            assert_eq!(build(SourceStorage::Dropped).content_source("page", "en", None).unwrap(), None);
+            let instance = build(SourceStorage::Plain);
+            assert_eq!(instance.content_source("page", "en", None).unwrap(), Some(source.clone()));
+            assert_eq!(instance.content_source("page", "en", Some("dark")).unwrap(), None);
+
+            #[cfg(feature = "compression")]
+            {
+                let mut compressed = build(SourceStorage::Compressed);
+                assert_eq!(compressed.content_source("page", "en", None).unwrap(), Some(source.clone()));
+                assert!(compressed.memory_report().total() < instance.memory_report().total());
+                compressed.sources.values_mut().for_each(|source| *source = StoredSource::Compressed(vec![0]));
+                let error = compressed.content_source("page", "en", None).unwrap_err();
+                assert_eq!(error.code(), "SOURCE_UNAVAILABLE");
+            }
+        }
+
+        fn make_instance() -> TerariumBuilder {
+            TerariumBuilder::default()
+        }
+    }
+
+    mod terarium {
+        use std::time::Duration;
+
+        use crate::{Content, RateQuota};
+
+        use super::*;
+
+        #[test]
+        fn render_template() {
+            let instance = make_instance();
+            let ctx = make_context();
+            let result_a = instance.render_template(&ctx, "template_a", "cs", None).unwrap();
+            assert_eq!(result_a, "template_a cs john");
+        }
+
+        #[test]
+        fn render_template_with_fallback() {
+            let instance = make_instance();
+            let ctx = make_context();
+            let result_a = instance.render_template(&ctx, "template_a", "de", Some("en")).unwrap();
+            assert_eq!(result_a, "template_a en john");
+        }
+
+        #[test]
+        #[allow(clippy::match_like_matches_macro)]
+        fn render_template_without_matching_language() {
+            let instance = make_instance();
+            let ctx = make_context();
+            let result = instance.render_template(&ctx, "template_a", "de", Some("fr"));
+
+            assert!(match result.unwrap_err() {
+                TerariumError::LanguageNotFound => true,
+                _ => false
+            })
+        }
+
+        #[test]
+        fn render_group() {
+            let instance = make_instance();
+            let context = make_context();
+            let group_result = instance.render_group(&context, "group_a", "en", None);
+            assert!(group_result.is_ok());
+            let group_result = group_result.unwrap();
+            assert_eq!(group_result.get("A").unwrap(), "template_a en john");
+            assert_eq!(group_result.get("B").unwrap(), "template_b en doe");
+        }
+
+        #[test]
+        fn render_group_with_fallback() {
+            let instance = make_instance();
+            let context = make_context();
+            let group_result = instance.render_group(&context, "group_a", "cs", Some("en"));
+            assert!(group_result.is_ok());
+            let group_result = group_result.unwrap();
+            assert_eq!(group_result.get("A").unwrap(), "template_a cs john");
+            assert_eq!(group_result.get("B").unwrap(), "template_b en doe");
+        }
+
+        #[test]
+        fn fallback_stats() {
+            let instance = make_instance();
+            let context = make_context();
+            instance.render_group(&context, "group_a", "cs", Some("en")).unwrap();
+            instance.render_template(&context, "template_b", "cs", Some("en")).unwrap();
+            instance.render_template(&context, "template_a", "de", Some("en")).unwrap();
+            instance.render_template(&context, "template_a", "en", None).unwrap();
+            let stats: Vec<_> = instance.fallback_stats()
+                .into_iter()
+                .map(|usage| (usage.template_key, usage.requested_language, usage.hits))
+                .collect();
+            assert_eq!(stats, vec![
+                ("template_b".to_owned(), "cs".to_owned(), 2),
+                ("template_a".to_owned(), "de".to_owned(), 1),
+            ]);
+        }
+
+        #[test]
+        fn dangling_members() {
+            let context = make_context();
+            let mut instance = make_instance();
+            instance.remove_template("template_b", "test").unwrap();
+            let error = instance.render_group(&context, "group_a", "en", None).unwrap_err();
+            assert!(matches!(&error, TerariumError::BrokenMember(g, m) if g == "group_a" && m == "B"));
+
+            let mut instance = make_instance();
+            instance.dangling_member_policy = DanglingMemberPolicy::Reject;
+            let error = instance.remove_template("template_b", "test").unwrap_err();
+            assert_eq!(error.code(), "TEMPLATE_IN_USE");
+            assert!(instance.render_template(&context, "template_b", "en", None).is_ok());
+
+            instance.dangling_member_policy = DanglingMemberPolicy::RemoveMember;
+            instance.remove_template("template_b", "test").unwrap();
+            let outputs = instance.render_group(&context, "group_a", "en", None).unwrap();
+            assert_eq!(outputs.keys().collect::<Vec<_>>(), vec!["A"]);
+        }
+
+        #[test]
+        fn swap_validates_dangling_members() {
+            let context = make_context();
+            let broken = || {
+                let mut other = make_instance();
+                other.remove_template("template_b", "test").unwrap();
+                other
+            };
+
+            let mut instance = make_instance();
+            instance.dangling_member_policy = DanglingMemberPolicy::Reject;
+            let fingerprint = instance.fingerprint();
+            let error = instance.swap(broken(), "carol").unwrap_err();
+            assert!(matches!(&error, TerariumBuilderError::TemplateNotFound(key) if key == "template_b"));
+            assert_eq!(instance.fingerprint(), fingerprint);
+            assert!(instance.audit_log().is_empty());
+
+            instance.dangling_member_policy = DanglingMemberPolicy::RemoveMember;
+            instance.swap(broken(), "carol").unwrap();
+            let outputs = instance.render_group(&context, "group_a", "en", None).unwrap();
+            assert_eq!(outputs.keys().collect::<Vec<_>>(), vec!["A"]);
+
+            instance.dangling_member_policy = DanglingMemberPolicy::MarkBroken;
+            instance.swap(broken(), "carol").unwrap();
+            let error = instance.render_group(&context, "group_a", "en", None).unwrap_err();
+            assert!(matches!(&error, TerariumError::BrokenMember(g, m) if g == "group_a" && m == "B"));
+        }
+
+        #[test]
+        fn removed_member_is_pruned() {
+            let mut builder = TerariumBuilder::default();
+            builder.set_dangling_member_policy(DanglingMemberPolicy::RemoveMember);
+            for key in ["body", "invoice"] {
+                let content = Content::new(format!("{} {{{{ name }}}}", key), vec!["en".to_owned()]);
+                builder.add_template(key.to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+            }
+            builder.add_group(
+                "email".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("body".to_owned(), "body".to_owned())
+                    .add_member("invoice".to_owned(), "invoice".to_owned())
+                    .require_languages_of("body".to_owned(), "invoice".to_owned())
+                    .encode_member("invoice".to_owned(), "text/plain".to_owned(), crate::TransferEncoding::Base64)
+                    .attach_member("invoice".to_owned(), "invoice.txt".to_owned(), "text/plain".to_owned())
+                    .build(),
+            ).unwrap();
+            let mut instance = builder.build().unwrap();
+
+            instance.remove_template("invoice", "test").unwrap();
+            let group = instance.group("email").unwrap();
+            assert!(group.member_encodings.is_empty());
+            assert!(group.attachments.is_empty());
+            assert!(group.required_languages.is_empty());
+            let request = RenderRequest::group("email".to_owned(), make_context()).with_language("en".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            assert!(outcome.attachments.is_empty());
+            assert!(outcome.encodings.is_empty());
+        }
+
+        #[test]
+        fn removal_leaving_invalid_group_is_rejected() {
+            let context = make_context();
+            let mut builder = TerariumBuilder::default();
+            builder.set_dangling_member_policy(DanglingMemberPolicy::RemoveMember);
+            builder.set_empty_group_policy(EmptyGroupPolicy::Reject);
+            for key in ["body", "subject"] {
+                let content = Content::new(key.to_owned(), vec!["en".to_owned()]);
+                builder.add_template(key.to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+            }
+            let group = TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "body".to_owned())
+                .add_member("subject".to_owned(), "subject".to_owned())
+                .require_member("subject".to_owned())
+                .build();
+            builder.add_group("email".to_owned(), group).unwrap();
+            let single = TemplateGroupBuilder::default().add_member("body".to_owned(), "body".to_owned()).build();
+            builder.add_group("single".to_owned(), single).unwrap();
+            let mut instance = builder.build().unwrap();
+
+            let error = instance.remove_template("subject", "test").unwrap_err();
+            assert!(matches!(error, TerariumBuilderError::MissingGroupMembers(g, _) if g == "email"));
+            assert_eq!(instance.render_template(&context, "subject", "en", None).unwrap(), "subject");
+            assert_eq!(instance.render_group(&context, "email", "en", None).unwrap().len(), 2);
+
+            let error = instance.remove_template("body", "test").unwrap_err();
+            assert!(matches!(error, TerariumBuilderError::EmptyGroup(g) if g == "single"));
+            assert_eq!(instance.render_group(&context, "single", "en", None).unwrap().len(), 1);
+        }
+
+        #[test]
+        fn failed_removal_keeps_contents() {
+            let mut builder = TerariumBuilder::default();
+            builder.set_source_storage(SourceStorage::Plain);
+            let base = Content::new_named(
+                "<p>{% block body %}base{% endblock %}</p>".to_owned(), vec!["en".to_owned()], "base.html".to_owned(),
+            );
+            let child = Content::new(
+                "{% extends \"base.html\" %}{% block body %}child{% endblock %}".to_owned(), vec!["en".to_owned()],
+            );
+            builder.add_template("base".to_owned(), Template::new(vec![base]).unwrap()).unwrap();
+            builder.add_template("child".to_owned(), Template::new(vec![child]).unwrap()).unwrap();
+            let mut instance = builder.build().unwrap();
+
+            assert!(instance.remove_template("base", "test").is_err());
+            let context = Context::new();
+            assert_eq!(instance.render_template(&context, "base", "en", None).unwrap(), "<p>base</p>");
+            assert_eq!(instance.render_template(&context, "child", "en", None).unwrap(), "<p>child</p>");
+            assert!(instance.content_source("base", "en", None).unwrap().is_some());
+            assert_eq!(instance.memory_report().contents.len(), 2);
+        }
+
+        #[test]
+        #[allow(clippy::match_like_matches_macro)]
+        fn render_group_when_invalid_language() {
+            let instance = make_instance();
+            let context = make_context();
+            let group_result = instance.render_group(&context, "group_a", "cs", Some("fr"));
+            assert!(group_result.is_err());
+            assert!(match group_result.unwrap_err() {
+                TerariumError::LanguageNotFound => true,
+                _ => false
+            })
+        }
+
+        #[test]
+        fn render_nested_templates() {
+            let mut builder = TerariumBuilder::default();
+            let mut tpl_a = Template::default();
+            tpl_a.add_content(
+                Content::new("This is content {{value_1}} {% include 'tpl_b_cs' %}".to_owned(), vec!["cs".to_owned()])
+            ).unwrap();
+            builder.add_template("tpl_a".to_owned(), tpl_a).unwrap();
+
+            let mut tpl_b = Template::default();
+            tpl_b.add_content(
+                Content::new_named("This is nested {{value_2}}".to_owned(), vec!["cs".to_owned()], "tpl_b_cs".to_owned())
+            ).unwrap();
+            builder.add_template("tpl_b".to_owned(), tpl_b).unwrap();
+
+            let instance = builder.build().unwrap();
+            let mut ctx = Context::default();
+            ctx.insert("value_1", "foo");
+            ctx.insert("value_2", "bar");
+
+            let result = instance.render_template(&ctx, "tpl_a", "cs", None).unwrap();
+            assert_eq!(result.as_str(), "This is content foo This is nested bar");
+        }
+
+        #[test]
+        fn report_deprecated_usage() {
+            #[derive(Clone, Default)]
+            struct Observer(Arc<Mutex<Vec<DeprecatedKey>>>);
+
+            impl RenderObserver for Observer {
+                fn deprecated_usage(&self, key: &DeprecatedKey, _deprecation: &Deprecation) {
+                    self.0.lock().unwrap().push(key.clone());
+                }
+            }
+
+            let mut builder = TerariumBuilder::default();
+            let mut tpl = Template::new(vec![Content::new("old".to_owned(), vec!["en".to_owned()])]).unwrap();
+            tpl.set_deprecated(Deprecation::with_replacement("Use new".to_owned(), "new".to_owned()));
+            builder.add_template("old".to_owned(), tpl).unwrap();
+            builder.add_group(
+                "group".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("a".to_owned(), "old".to_owned())
+                    .deprecated(Deprecation::new("Do not use".to_owned()))
+                    .build(),
+            ).unwrap();
+            let observer = Observer::default();
+            builder.set_observer(observer.clone());
+            let instance = builder.build().unwrap();
+
+            assert!(instance.deprecated_usage().is_empty());
+            let result = instance.render_group(&Context::new(), "group", "en", None).unwrap();
+            assert_eq!(result["a"], "old");
+            assert_eq!(
+                instance.deprecated_usage(),
+                vec![DeprecatedKey::Template("old".to_owned()), DeprecatedKey::Group("group".to_owned())]
+            );
+            assert_eq!(observer.0.lock().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn render_failure_reports_owner() {
+            #[derive(Clone, Default)]
+            struct Observer(Arc<Mutex<Vec<String>>>);
+
+            impl RenderObserver for Observer {
+                fn render_failed(&self, failure: &RenderFailure) {
+                    self.0.lock().unwrap().push(format!("{} ({:?})", failure.template_key, failure.owner));
+                }
+            }
+
+            let mut builder = TerariumBuilder::default();
+            let mut tpl = Template::new(vec![Content::new("{{ missing }}".to_owned(), vec!["en".to_owned()])]).unwrap();
+            tpl.set_owner("billing".to_owned());
+            builder.add_template("invoice".to_owned(), tpl).unwrap();
+            let observer = Observer::default();
+            builder.set_observer(observer.clone());
+            let instance = builder.build().unwrap();
+
+            assert_eq!(instance.template_owner("invoice"), Some("billing"));
+            assert!(instance.render_template(&Context::new(), "invoice", "en", None).is_err());
+            assert_eq!(*observer.0.lock().unwrap(), vec!["invoice (Some(\"billing\"))"]);
+        }
+
+        #[test]
+        fn runtime_mutation_is_audited() {
             let mut instance = make_instance();
-            instance.add_template("1".to_owned(), Template::default()).unwrap();
-            instance.add_group("1".to_owned(), TemplateGroupBuilder::default().add_member("1".to_owned(), "1".to_owned()).build()).unwrap();
-            let grp = instance.get_group(&"1".to_owned());
-            assert!(grp.is_some());
-            let grp = grp.unwrap();
-            assert_eq!(grp.clone(), HashMap::<String, String>::from([("1".to_owned(), "1".to_owned())]));
+            let fingerprint = instance.fingerprint();
+            let ctx = make_context();
+
+            instance.add_template(
+                "template_c".to_owned(),
+                Template::new(vec![Content::new("template_c {{name}}".to_owned(), vec!["en".to_owned()])]).unwrap(),
+                "alice",
+            ).unwrap();
+            assert_eq!(instance.render_template(&ctx, "template_c", "en", None).unwrap(), "template_c john");
+
+            instance.add_template(
+                "template_a".to_owned(),
+                Template::new(vec![Content::new("updated {{name}}".to_owned(), vec!["en".to_owned()])]).unwrap(),
+                "bob",
+            ).unwrap();
+            assert_eq!(instance.render_template(&ctx, "template_a", "en", None).unwrap(), "updated john");
+            assert!(instance.render_template(&ctx, "template_a", "cs", None).is_err());
+
+            instance.remove_template("template_c", "alice").unwrap();
+            assert!(matches!(instance.render_template(&ctx, "template_c", "en", None), Err(TerariumError::TemplateNotFound)));
+            assert!(instance.remove_template("template_c", "alice").is_err());
+
+            instance.swap(make_instance(), "carol").unwrap();
+            assert_eq!(instance.fingerprint(), fingerprint);
+
+            let actions: Vec<_> = instance.audit_log().iter().map(|e| (e.actor.as_str(), e.action.clone())).collect();
+            assert_eq!(actions, vec![
+                ("alice", AuditAction::TemplateAdded("template_c".to_owned())),
+                ("bob", AuditAction::TemplateUpdated("template_a".to_owned())),
+                ("alice", AuditAction::TemplateRemoved("template_c".to_owned())),
+                ("carol", AuditAction::CatalogSwapped),
+            ]);
+            let update = &instance.audit_log()[1];
+            assert!(update.fingerprint_before.is_some());
+            assert_ne!(update.fingerprint_before, update.fingerprint_after);
+        }
+
+        #[test]
+        fn failed_runtime_mutation_keeps_instance() {
+            let mut instance = make_instance();
+            let result = instance.add_template(
+                "template_a".to_owned(),
+                Template::new(vec![Content::new("{{ broken".to_owned(), vec!["en".to_owned()])]).unwrap(),
+                "alice",
+            );
+            assert!(result.is_err());
+            assert!(instance.audit_log().is_empty());
+            let result = instance.render_template(&make_context(), "template_a", "cs", None).unwrap();
+            assert_eq!(result, "template_a cs john");
+        }
+
+        #[test]
+        fn failed_runtime_mutation_keeps_dependent_templates() {
+            let named = |content: &str, name: &str| {
+                let content = Content::new_named(content.to_owned(), vec!["en".to_owned()], name.to_owned());
+                Template::new(vec![content]).unwrap()
+            };
+            let mut builder = TerariumBuilder::default();
+            builder.add_template("layout".to_owned(), named("<{% block body %}{% endblock %}>", "layout")).unwrap();
+            builder.add_template(
+                "page".to_owned(),
+                named("{% extends \"layout\" %}{% block body %}{{ name }}{% endblock %}", "page"),
+            ).unwrap();
+            let mut instance = builder.build().unwrap();
+
+            let result = instance.add_template("layout".to_owned(), named("{% include \"page\" %}", "layout"), "alice");
+            assert!(matches!(result, Err(TerariumBuilderError::CyclicDependency(_))));
+            assert_eq!(instance.render_template(&make_context(), "page", "en", None).unwrap(), "<john>");
+
+            let layout = named("[{% block body %}{% endblock %}]", "layout");
+            instance.add_template("layout".to_owned(), layout, "alice").unwrap();
+            assert_eq!(instance.render_template(&make_context(), "page", "en", None).unwrap(), "[john]");
+        }
+
+        #[test]
+        fn warm_up() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "tpl".to_owned(),
+                Template::new(vec![
+                    Content::new("{{ name }}".to_owned(), vec!["en".to_owned()]),
+                    Content::new("static".to_owned(), vec!["cs".to_owned()]),
+                ]).unwrap(),
+            ).unwrap();
+            let instance = builder.build().unwrap();
+
+            let failures = instance.warm_up(&HashMap::new());
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].template_key, "tpl");
+            assert_eq!(failures[0].language, "en");
+
+            let samples = HashMap::from([("tpl".to_owned(), make_context())]);
+            assert!(instance.warm_up(&samples).is_empty());
+        }
+
+        #[test]
+        fn normalized_keys() {
+            let mut builder = TerariumBuilder::default();
+            builder.set_key_normalization(KeyNormalization::Normalized);
+            builder.add_template(
+                "emails.welcome_mail".to_owned(),
+                Template::new(vec![Content::new("welcome".to_owned(), vec!["en".to_owned()])]).unwrap(),
+            ).unwrap();
+            builder.add_group(
+                "Welcome".to_owned(),
+                TemplateGroupBuilder::default().add_member("text".to_owned(), "emails.welcome_mail".to_owned()).build(),
+            ).unwrap();
+            let mut instance = builder.build().unwrap();
+            let ctx = Context::new();
+
+            assert_eq!(instance.render_template(&ctx, " Emails/Welcome-Mail ", "en", None).unwrap(), "welcome");
+            assert_eq!(instance.render_group(&ctx, "welcome", "en", None).unwrap()["text"], "welcome");
+            assert!(instance.group("WELCOME").is_some());
+
+            let result = instance.add_template("EMAILS.WELCOME.MAIL".to_owned(), Template::default(), "alice");
+            assert!(matches!(result, Err(TerariumBuilderError::KeyConflict(_, _))));
+            instance.remove_template("emails/welcome/mail", "alice").unwrap();
+            assert!(instance.render_template(&ctx, "emails.welcome_mail", "en", None).is_err());
+        }
+
+        #[test]
+        fn render_repeated() {
+            let mut builder = TerariumBuilder::default();
+            for (key, content) in [("item", "- {{title}} by {{author}}"), ("header", "Digest:\n"), ("footer", "\nbye")] {
+                builder.add_template(
+                    key.to_owned(),
+                    Template::new(vec![Content::new(content.to_owned(), vec!["en".to_owned()])]).unwrap(),
+                ).unwrap();
+            }
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert("author", "john");
+            let items = ["foo", "bar"].into_iter().map(|title| {
+                let mut item = Context::new();
+                item.insert("title", title);
+                item
+            });
+            let options = RepeatOptions::default()
+                .separator("\n".to_owned())
+                .header("header".to_owned())
+                .footer("footer".to_owned());
+
+            let result = instance.render_repeated(&ctx, "item", items, "en", None, &options).unwrap();
+            assert_eq!(result, "Digest:\n- foo by john\n- bar by john\nbye");
+        }
+
+        #[test]
+        fn render_request() {
+            let instance = make_instance();
+            let template_request = RenderRequest::template("template_a".to_owned(), make_context())
+                .with_language("de".to_owned())
+                .with_language("fr".to_owned())
+                .with_language("en".to_owned());
+            let outcome = instance.render(&template_request).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("template_a en john".to_owned()));
+
+            let group_request = RenderRequest::group("group_a".to_owned(), make_context())
+                .with_language("en".to_owned());
+            let outcome = instance.render(&group_request).unwrap();
+            let RenderOutput::Group(group) = outcome.output else { panic!("group output expected") };
+            assert_eq!(group["B"], "template_b en doe");
+
+            let no_language_request = RenderRequest::template("template_a".to_owned(), make_context());
+            assert!(matches!(instance.render(&no_language_request), Err(TerariumError::LanguageNotFound)));
+
+            let override_request = RenderRequest::group("group_a".to_owned(), make_context())
+                .with_language("en".to_owned())
+                .with_template_override("template_a".to_owned(), "template_b".to_owned());
+            let RenderOutput::Group(group) = instance.render(&override_request).unwrap().output else { panic!() };
+            assert_eq!(group["A"], "template_b en doe");
+            let limited_request = override_request.with_output_limit(10);
+            assert!(matches!(instance.render(&limited_request), Err(TerariumError::OutputTooLarge(34, 10))));
+        }
+
+        #[test]
+        fn render_request_cost() {
+            let instance = make_instance();
+            let request = RenderRequest::group("group_a".to_owned(), make_context()).with_language("en".to_owned());
+            assert!(instance.render(&request).unwrap().cost.is_none());
+            let cost = instance.render(&request.with_cost()).unwrap().cost.unwrap();
+            assert_eq!(cost.output_bytes, "template_a en john".len() + "template_b en doe".len());
+            assert!(cost.tera_time > Duration::ZERO);
+            assert!(cost.wall_time >= cost.tera_time + cost.postprocess_time);
+        }
+
+        #[test]
+        fn render_request_trace() {
+            let mut builder = TerariumBuilder::default();
+            let content = |text: &str, name: &str| {
+                let content = Content::new_named(text.to_owned(), vec!["en".to_owned()], name.to_owned());
+                Template::new(vec![content]).unwrap()
+            };
+            builder.add_template("footer".to_owned(), content("{{ year }}", "footer")).unwrap();
+            let page = "{% include \"footer\" %}{{ title }} {{ resolve(key=\"display_name\") }}";
+            builder.add_template("page".to_owned(), content(page, "page")).unwrap();
+            builder.set_value_resolver(|key: &str, _: Option<&Value>| Ok(Value::from(key)));
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert("title", "Hi");
+            ctx.insert("year", &2024);
+            let request = RenderRequest::template("page".to_owned(), ctx).with_language("en".to_owned());
+            assert!(instance.render(&request).unwrap().trace.is_none());
+            let trace = instance.render(&request.with_trace()).unwrap().trace.unwrap();
+            assert_eq!(trace.steps.len(), 1);
+            let step = &trace.steps[0];
+            assert_eq!((step.template_key.as_str(), step.language.as_str()), ("page", "en"));
+            assert_eq!(step.includes, vec!["footer"]);
+            assert_eq!(step.variables, vec!["title", "year"]);
+            assert_eq!(step.resolved, vec!["display_name"]);
+            assert!(step.tera_time > Duration::ZERO);
+        }
+
+        #[test]
+        fn sensitive_values_are_redacted() {
+            use crate::{REDACTED, Sensitive, SensitiveContext};
+
+            #[derive(Clone, Default)]
+            struct Observer(Arc<Mutex<Vec<String>>>);
+
+            impl RenderObserver for Observer {
+                fn render_failed(&self, failure: &RenderFailure) {
+                    self.0.lock().unwrap().push(failure.message.clone());
+                }
+            }
+
+            let mut builder = TerariumBuilder::default();
+            let content = |text: &str| Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+            builder.add_template("page".to_owned(), content("{{ resolve(key=token) }}")).unwrap();
+            builder.add_template("broken".to_owned(), content("{{ resolve(key=token, recipient=1) }}")).unwrap();
+            builder.set_value_resolver(|key: &str, recipient: Option<&Value>| match recipient {
+                Some(_) => Err("unknown recipient".to_owned()),
+                None => Ok(Value::from(key.len())),
+            });
+            let observer = Observer::default();
+            builder.set_observer(observer.clone());
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert_sensitive("token", &Sensitive("secret"));
+            let request = RenderRequest::template("page".to_owned(), ctx.clone())
+                .with_language("en".to_owned())
+                .with_trace();
+            let trace = instance.render(&request).unwrap().trace.unwrap();
+            assert_eq!(trace.steps[0].resolved, vec![REDACTED]);
+            assert!(!format!("{:?}", trace).contains("secret"));
+
+            let request = RenderRequest::template("broken".to_owned(), ctx).with_language("en".to_owned());
+            let error = instance.render(&request).unwrap_err();
+            let messages = observer.0.lock().unwrap().clone();
+            assert_eq!(messages.len(), 1);
+            assert!(messages[0].contains("Cannot resolve `[REDACTED]`"), "{}", messages[0]);
+            let snapshot = instance.snapshot(&request, &error);
+            assert!(snapshot.error.contains("[REDACTED]"));
+            assert!(!snapshot.error.contains("secret"));
+            assert_eq!(snapshot.request.context().get("token").unwrap(), REDACTED);
+            assert!(!format!("{:?}", snapshot).contains("secret"));
+        }
+
+        #[test]
+        fn member_encodings() {
+            use crate::TransferEncoding;
+            let mut builder = TerariumBuilder::default();
+            for (key, text) in [("text", "Dobrý den {{ name }}"), ("event", "BEGIN:VEVENT")] {
+                let template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            builder.add_group("mail".to_owned(), TemplateGroupBuilder::default()
+                .add_member("text".to_owned(), "text".to_owned())
+                .add_member("attachment".to_owned(), "event".to_owned())
+                .encode_member("text".to_owned(), "text/plain".to_owned(), TransferEncoding::QuotedPrintable)
+                .encode_member("attachment".to_owned(), "text/calendar".to_owned(), TransferEncoding::Base64)
+                .encode_member("missing".to_owned(), "text/html".to_owned(), TransferEncoding::Base64)
+                .build()).unwrap();
+            let instance = builder.build().unwrap();
+
+            let request = RenderRequest::group("mail".to_owned(), make_context()).with_language("en".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            let RenderOutput::Group(members) = &outcome.output else { panic!("group expected") };
+            assert_eq!(members["text"], "Dobr=C3=BD den john");
+            assert_eq!(members["attachment"], "QkVHSU46VkVWRU5U");
+            assert_eq!(outcome.encodings.len(), 2);
+            assert_eq!(outcome.encodings["attachment"].mime_type, "text/calendar");
+            assert_eq!(outcome.encodings["text"].transfer_encoding, TransferEncoding::QuotedPrintable);
+        }
+
+        #[test]
+        fn group_attachments() {
+            let mut builder = TerariumBuilder::default();
+            for (key, text) in [("body", "Hi {{ name }}"), ("csv", "name\n{{ name }}\n")] {
+                let template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            builder.add_group("report".to_owned(), TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "body".to_owned())
+                .add_member("users".to_owned(), "csv".to_owned())
+                .attach_member("users".to_owned(), "users-{{ surname }}.csv".to_owned(), "text/csv".to_owned())
+                .build()).unwrap();
+            let instance = builder.build().unwrap();
+
+            let request = RenderRequest::group("report".to_owned(), make_context()).with_language("en".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            let RenderOutput::Group(members) = &outcome.output else { panic!("group expected") };
+            assert_eq!(members.keys().collect::<Vec<_>>(), vec!["body"]);
+            assert_eq!(outcome.attachments, vec![Attachment {
+                member_key: "users".to_owned(),
+                filename: "users-doe.csv".to_owned(),
+                mime_type: "text/csv".to_owned(),
+                data: b"name\njohn\n".to_vec(),
+            }]);
+            assert!(instance.render_group(&make_context(), "report", "en", None).unwrap().contains_key("users"));
+        }
+
+        #[test]
+        fn render_request_quota() {
+            let mut builder = TerariumBuilder::default();
+            let template = Template::new(vec![Content::new("12345".to_owned(), vec!["en".to_owned()])]).unwrap();
+            builder.add_template("tpl".to_owned(), template).unwrap();
+            builder.set_quota_policy(RateQuota::new().bytes_per_day(8));
+            let instance = builder.build().unwrap();
+            let request = |namespace: &str| {
+                RenderRequest::template("tpl".to_owned(), Context::new())
+                    .with_language("en".to_owned())
+                    .with_namespace(namespace.to_owned())
+            };
+
+            assert!(instance.render(&request("a")).is_ok());
+            assert!(instance.render(&request("a")).is_ok());
+            let result = instance.render(&request("a"));
+            assert!(matches!(result, Err(TerariumError::QuotaExceeded(namespace, _)) if namespace == "a"));
+            assert!(instance.render(&request("b")).is_ok());
+        }
+
+        #[test]
+        fn render_request_revisions() {
+            let mut instance = make_instance();
+            let mut tpl = Template::new(vec![Content::new("B {{surname}}".to_owned(), vec!["en".to_owned()])]).unwrap();
+            tpl.set_revision("3f2a9c1".to_owned());
+            instance.add_template("template_b".to_owned(), tpl, "admin").unwrap();
+
+            let request = RenderRequest::group("group_a".to_owned(), make_context()).with_language("en".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            assert_eq!(outcome.revisions, HashMap::from([("template_b".to_owned(), "3f2a9c1".to_owned())]));
+
+            let request = RenderRequest::template("template_a".to_owned(), make_context())
+                .with_language("en".to_owned());
+            assert!(instance.render(&request).unwrap().revisions.is_empty());
+        }
+
+        #[test]
+        fn render_request_deadline() {
+            let instance = make_instance();
+            let request = RenderRequest::group("group_a".to_owned(), make_context()).with_language("en".to_owned());
+            let now = Instant::now();
+            assert!(instance.render(&request.clone().with_deadline(now + Duration::from_secs(60))).is_ok());
+            let error = instance.render(&request.with_deadline(now)).unwrap_err();
+            assert!(matches!(error, TerariumError::DeadlineExceeded));
+            assert_eq!(error.code(), "DEADLINE_EXCEEDED");
+        }
+
+        #[test]
+        fn render_request_identity() {
+            let mut instance = make_instance();
+            let target = RenderTarget::Group("group_a".to_owned());
+            let identity = instance.identity(&target, "en").unwrap();
+            assert_eq!(instance.identity(&target, "en").unwrap(), identity);
+            assert_ne!(instance.identity(&target, "cs").unwrap(), identity);
+            assert!(instance.identity(&RenderTarget::Template("missing".to_owned()), "en").is_none());
+
+            let request = RenderRequest::group("group_a".to_owned(), make_context())
+                .with_language("en".to_owned())
+                .with_expected_identity(identity.clone());
+            assert!(instance.render(&request).is_ok());
+
+            let tpl = Template::new(vec![Content::new("B {{surname}}".to_owned(), vec!["en".to_owned()])]).unwrap();
+            instance.add_template("template_b".to_owned(), tpl, "admin").unwrap();
+            let error = instance.render(&request).unwrap_err();
+            assert!(matches!(&error, TerariumError::CatalogVersionMismatch(expected, _) if *expected == identity));
+            assert_eq!(error.code(), "CATALOG_VERSION_MISMATCH");
+        }
+
+        #[test]
+        fn render_group_with_member_pattern() {
+            let mut builder = TerariumBuilder::default();
+            let section = |text: &str| {
+                Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap()
+            };
+            builder.add_template("sections.intro".to_owned(), section("intro")).unwrap();
+            builder.add_template("footer".to_owned(), section("footer")).unwrap();
+            builder.add_group(
+                "page".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("footer".to_owned(), "footer".to_owned())
+                    .add_members_matching("sections.*")
+                    .build(),
+            ).unwrap();
+            let mut instance = builder.build().unwrap();
+            instance.add_template("sections.outro".to_owned(), section("outro"), "admin").unwrap();
+
+            let result = instance.render_group(&Context::new(), "page", "en", None).unwrap();
+            assert_eq!(result.len(), 3);
+            assert_eq!(result["sections.outro"], "outro");
+            let result = instance.render_group_concat(&Context::new(), "page", &["sections.intro", "footer"], " ", "en", None);
+            assert_eq!(result.unwrap(), "intro footer");
+        }
+
+        #[test]
+        fn render_group_with_member_outputs() {
+            let mut builder = TerariumBuilder::default();
+            let content = |text: &str| {
+                Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap()
+            };
+            builder.add_template("text".to_owned(), content("Hello {{ name }}")).unwrap();
+            builder.add_template("html".to_owned(), content("<p>{{ summary }}</p>")).unwrap();
+            let group = TemplateGroupBuilder::default()
+                .add_member("text".to_owned(), "text".to_owned())
+                .add_member("html".to_owned(), "html".to_owned())
+                .use_member_output("html".to_owned(), "summary".to_owned(), "text".to_owned());
+            builder.add_group("email".to_owned(), group.clone().build()).unwrap();
+            let instance = builder.build().unwrap();
+            let result = instance.render_group(&make_context(), "email", "en", None).unwrap();
+            assert_eq!(result["html"], "<p>Hello john</p>");
+
+            let mut builder = TerariumBuilder::default();
+            builder.add_template("text".to_owned(), content("{{ html }}")).unwrap();
+            builder.add_template("html".to_owned(), content("{{ summary }}")).unwrap();
+            let group = group.use_member_output("text".to_owned(), "html".to_owned(), "html".to_owned());
+            builder.add_group("email".to_owned(), group.build()).unwrap();
+            assert!(matches!(builder.build(), Err(TerariumBuilderError::CyclicDependency(_))));
+        }
+
+        #[test]
+        fn render_cached_template() {
+            let mut builder = TerariumBuilder::default();
+            let mut template = Template::new(vec![Content::new("{{ now() }}".to_owned(), vec!["en".to_owned()])]).unwrap();
+            template.set_cache_policy(CachePolicy::new().tag("product:{{ id }}".to_owned()));
+            builder.add_template("product".to_owned(), template).unwrap();
+            let instance = builder.build().unwrap();
+            let render = |id: u32| {
+                let mut ctx = Context::new();
+                ctx.insert("id", &id);
+                instance.render_template(&ctx, "product", "en", None).unwrap()
+            };
+
+            // `now()` differs between renders, the same output comes from the cache (outputs are cached regardless of
+            // the `Determinism`)
+            let first = render(1);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            assert_eq!(render(1), first);
+            let mut ctx = Context::new();
+            ctx.insert("id", &1);
+            let request = RenderRequest::template("product".to_owned(), ctx).with_language("en".to_owned());
+            let bypassed = instance.render(&request.with_cache_bypass()).unwrap().output;
+            assert_ne!(bypassed, RenderOutput::Template(first.clone()));
+            assert_ne!(render(2), first);
+            assert_eq!(instance.invalidate_tag("product:1"), 1);
+            assert_ne!(render(1), first);
+        }
+
+        #[test]
+        fn cached_output_depends_on_included_templates() {
+            let named = |content: &str, name: &str| {
+                let content = Content::new_named(content.to_owned(), vec!["en".to_owned()], name.to_owned());
+                Template::new(vec![content]).unwrap()
+            };
+            let mut builder = TerariumBuilder::default();
+            builder.add_template("footer".to_owned(), named("v1", "footer")).unwrap();
+            let mut page = named("{% include \"footer\" %}", "page");
+            page.set_cache_policy(CachePolicy::new());
+            builder.add_template("page".to_owned(), page).unwrap();
+            let mut instance = builder.build().unwrap();
+
+            assert_eq!(instance.render_template(&Context::new(), "page", "en", None).unwrap(), "v1");
+            instance.add_template("footer".to_owned(), named("v2", "footer"), "alice").unwrap();
+            assert_eq!(instance.render_template(&Context::new(), "page", "en", None).unwrap(), "v2");
+        }
+
+        #[test]
+        #[cfg(feature = "async")]
+        fn render_stale_while_revalidate() {
+            let mut builder = TerariumBuilder::default();
+            let mut template = Template::new(vec![Content::new("{{ now() }}".to_owned(), vec!["en".to_owned()])]).unwrap();
+            template.set_cache_policy(
+                CachePolicy::new().ttl(Duration::from_millis(200)).stale_while_revalidate(Duration::from_secs(60))
+            );
+            builder.add_template("clock".to_owned(), template).unwrap();
+            let instance = builder.build_shared().unwrap();
+            let render = || instance.render_template(&Context::new(), "clock", "en", None).unwrap();
+
+            let first = render();
+            std::thread::sleep(Duration::from_millis(250));
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            runtime.block_on(async {
+                assert_eq!(render(), first);
+                let tasks: Vec<_> = instance.revalidating.lock().unwrap().drain().map(|(_, task)| task).collect();
+                for task in tasks {
+                    task.await.unwrap();
+                }
+                assert_ne!(render(), first);
+            });
+        }
+
+        #[test]
+        #[cfg(feature = "async")]
+        fn render_group_async() {
+            let mut builder = TerariumBuilder::default();
+            let texts = [("text", "{{ name }}"), ("html", "<p>{{ summary }}</p>"), ("subject", "Ahoj {{ name }} 👋")];
+            for (key, text) in texts {
+                let template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            builder.add_group("mail".to_owned(), TemplateGroupBuilder::default()
+                .add_member("text".to_owned(), "text".to_owned())
+                .add_member("html".to_owned(), "html".to_owned())
+                .add_member("subject".to_owned(), "subject".to_owned())
+                .use_member_output("html".to_owned(), "summary".to_owned(), "text".to_owned())
+                .build()).unwrap();
+            builder.set_group_parallelism(2);
+            builder.set_output_normalization(crate::OutputNormalization::new().encode_member("subject".to_owned()));
+            let instance = Arc::new(builder.build().unwrap());
+
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let result = runtime.block_on(instance.render_group_async(&make_context(), "mail", "en", None)).unwrap();
+            assert_eq!(result, instance.render_group(&make_context(), "mail", "en", None).unwrap());
+            assert_eq!(result["html"], "<p>john</p>");
+            assert_eq!(result["subject"], crate::encode_header("Ahoj john 👋"));
+
+            use std::sync::atomic::{AtomicBool, Ordering};
+            let (context, cancelled) = (make_context(), Arc::new(AtomicBool::new(false)));
+            let render = instance.render_group_async_cancellable(&context, "mail", "en", None, cancelled.clone());
+            assert_eq!(runtime.block_on(render).unwrap(), result);
+            cancelled.store(true, Ordering::Relaxed);
+            let render = instance.render_group_async_cancellable(&context, "mail", "en", None, cancelled);
+            assert!(matches!(runtime.block_on(render), Err(TerariumError::Cancelled)));
+        }
+
+        #[test]
+        fn render_matching() {
+            let instance = make_instance();
+            let ctx = make_context();
+            let result = instance.render_matching(&ctx, "template_", "en", None).unwrap();
+            assert_eq!(result.len(), 2);
+            assert_eq!(result["template_b"], "template_b en doe");
+
+            let result = instance.render_matching(&ctx, "*_a", "en", None).unwrap();
+            assert_eq!(result.keys().collect::<Vec<_>>(), vec!["template_a"]);
+
+            assert!(instance.render_matching(&ctx, "nothing", "en", None).unwrap().is_empty());
+        }
+
+        #[test]
+        fn render_group_concat() {
+            let instance = make_instance();
+            let ctx = make_context();
+            let result = instance.render_group_concat(&ctx, "group_a", &["B", "A"], "\n---\n", "en", None).unwrap();
+            assert_eq!(result, "template_b en doe\n---\ntemplate_a en john");
+
+            let result = instance.render_group_concat(&ctx, "group_a", &["A", "C"], "", "en", None);
+            assert!(matches!(result, Err(TerariumError::MemberNotFound(member)) if member == "C"));
+        }
 
-            instance.remove_group(&"1".to_owned());
-            assert!(instance.get_group(&"1".to_owned()).is_none())
+        #[test]
+        #[cfg(all(feature = "pdf", unix))]
+        fn render_group_pdf() {
+            let instance = make_instance();
+            let request = RenderRequest::group("group_a".to_owned(), make_context()).with_language("en".to_owned());
+            let backend = crate::CommandPdfBackend::new("cat".to_owned(), vec![]);
+            let (members, pdf) = instance.render_group_pdf(&request, "A", &backend).unwrap();
+            assert_eq!(members.len(), 2);
+            assert_eq!(pdf, b"template_a en john".to_vec());
+            assert!(matches!(
+                instance.render_group_pdf(&request, "X", &backend),
+                Err(TerariumError::MemberNotFound(member)) if member == "X",
+            ));
+
+            let request = RenderRequest::template("template_a".to_owned(), make_context())
+                .with_language("en".to_owned());
+            let error = instance.render_group_pdf(&request, "A", &backend).unwrap_err();
+            assert!(matches!(error, TerariumError::NotAGroup));
+            assert_eq!(error.code(), "NOT_A_GROUP");
         }
 
         #[test]
-        fn check_group_configuration() {
-            let mut instance = make_instance();
-            instance.add_template("1".to_owned(), Template::default()).unwrap();
-            instance.add_template("2".to_owned(), Template::default()).unwrap();
-            let result = instance.add_group(
-                "100".to_owned(),
-                TemplateGroupBuilder::default()
-                    .add_member("10".to_owned(), "1".to_owned())
-                    .add_member("20".to_owned(), "2".to_owned())
-                    .add_member("30".to_owned(), "3".to_owned())
-                    .build(),
-            );
-            assert!(result.is_err())
+        fn render_icalendar() {
+            let mut builder = TerariumBuilder::default();
+            let content = "BEGIN:VCALENDAR\nVERSION:2.0\nPRODID:-//test//EN\n\
+                BEGIN:VEVENT\nUID:1\nDTSTAMP:20240101T100000Z\nSUMMARY:{{ summary | ics_escape }}\nEND:VEVENT\n\
+                {% if broken %}BEGIN:VTODO{% endif %}\nEND:VCALENDAR";
+            builder.add_template(
+                "invite".to_owned(),
+                Template::new(vec![
+                    Content::new(content.to_owned(), vec!["en".to_owned()]).with_kind(ContentKind::ICalendar),
+                ]).unwrap(),
+            ).unwrap();
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert("summary", "Lunch, dinner");
+            ctx.insert("broken", &false);
+            let result = instance.render_template(&ctx, "invite", "en", None).unwrap();
+            assert!(result.contains("\r\nSUMMARY:Lunch\\, dinner\r\n"));
+            assert!(result.ends_with("END:VCALENDAR\r\n"));
+
+            ctx.insert("broken", &true);
+            let result = instance.render_template(&ctx, "invite", "en", None);
+            assert!(matches!(result, Err(TerariumError::InvalidOutput(_))));
         }
 
-        fn make_instance() -> TerariumBuilder {
-            TerariumBuilder::default()
+        #[test]
+        fn validate_output() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "payload".to_owned(),
+                Template::new(vec![
+                    Content::new(r#"{"name": {{ name }}}"#.to_owned(), vec!["en".to_owned()]).with_kind(ContentKind::Json),
+                ]).unwrap(),
+            ).unwrap();
+            builder.add_output_validator(ContentKind::Json, crate::JsonValidator);
+            builder.add_output_validator(ContentKind::Json, |output: &str| match output.len() < 20 {
+                true => Ok(()),
+                false => Err("Too long".to_owned()),
+            });
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert("name", "\"john\"");
+            assert_eq!(instance.render_template(&ctx, "payload", "en", None).unwrap(), r#"{"name": "john"}"#);
+
+            ctx.insert("name", "john");
+            let result = instance.render_template(&ctx, "payload", "en", None);
+            assert!(matches!(result, Err(TerariumError::InvalidOutput(message)) if message.starts_with("Invalid JSON")));
+
+            ctx.insert("name", "\"john john john\"");
+            let result = instance.render_template(&ctx, "payload", "en", None);
+            assert!(matches!(result, Err(TerariumError::InvalidOutput(message)) if message == "Too long"));
         }
-    }
 
-    mod terarium {
-        use crate::Content;
+        #[test]
+        fn csv_content() {
+            let mut builder = TerariumBuilder::default();
+            let source = "name,note\n{% for user in users %}{{ user.name | csv_escape }},{{ user.note }}\n{% endfor %}";
+            builder.add_template("export".to_owned(), Template::new(vec![
+                Content::new(source.to_owned(), vec!["en".to_owned()]).with_kind(ContentKind::Csv),
+            ]).unwrap()).unwrap();
+            let instance = builder.build().unwrap();
 
-        use super::*;
+            let mut ctx = Context::new();
+            ctx.insert("users", &serde_json::json!([{"name": "=HYPERLINK(\"x\")", "note": "a"}]));
+            let output = instance.render_template(&ctx, "export", "en", None).unwrap();
+            assert_eq!(output, "name,note\n\"'=HYPERLINK(\"\"x\"\")\",a\n");
+
+            ctx.insert("users", &serde_json::json!([{"name": "doe", "note": "b,c"}]));
+            let result = instance.render_template(&ctx, "export", "en", None);
+            assert!(matches!(result, Err(TerariumError::InvalidOutput(message)) if message.contains("has 3 fields")));
+        }
 
         #[test]
-        fn render_template() {
-            let instance = make_instance();
-            let ctx = make_context();
-            let result_a = instance.render_template(&ctx, "template_a", "cs", None).unwrap();
-            assert_eq!(result_a, "template_a cs john");
+        #[cfg(feature = "markup")]
+        fn validate_markup() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "page".to_owned(),
+                Template::new(vec![
+                    Content::new("<div>{% if open %}<span>{% endif %}</div>".to_owned(), vec!["en".to_owned()])
+                        .with_kind(ContentKind::Html),
+                ]).unwrap(),
+            ).unwrap();
+            builder.add_output_validator(ContentKind::Html, crate::MarkupValidator::html());
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert("open", &false);
+            assert!(instance.render_template(&ctx, "page", "en", None).is_ok());
+            ctx.insert("open", &true);
+            assert!(matches!(instance.render_template(&ctx, "page", "en", None), Err(TerariumError::InvalidOutput(_))));
         }
 
         #[test]
-        fn render_template_with_fallback() {
+        fn render_for_testing() {
             let instance = make_instance();
-            let ctx = make_context();
-            let result_a = instance.render_template(&ctx, "template_a", "de", Some("en")).unwrap();
-            assert_eq!(result_a, "template_a en john");
+            assert_eq!(instance.template_languages(), vec![
+                ("template_a".to_owned(), "cs".to_owned()),
+                ("template_a".to_owned(), "en".to_owned()),
+                ("template_b".to_owned(), "en".to_owned()),
+            ]);
+
+            let samples = HashMap::from([("template_a".to_owned(), make_context())]);
+            let mut outputs = Vec::new();
+            let failures = instance.render_for_testing(&samples, |render| {
+                outputs.push(format!("{}/{}: {}", render.template_key, render.language, render.output));
+            });
+            assert_eq!(outputs, vec!["template_a/cs: template_a cs john", "template_a/en: template_a en john"]);
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].template_key, "template_b");
         }
 
         #[test]
-        fn render_template_without_matching_language() {
+        fn render_template_variant() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "logo".to_owned(),
+                Template::new(vec![
+                    Content::new("light cs".to_owned(), vec!["cs".to_owned()]),
+                    Content::new("light en".to_owned(), vec!["en".to_owned()]),
+                    Content::new("dark en".to_owned(), vec!["en".to_owned()]).with_variant("dark".to_owned()),
+                ]).unwrap(),
+            ).unwrap();
+            let instance = builder.build().unwrap();
+            let ctx = Context::new();
+
+            assert_eq!(instance.render_template(&ctx, "logo", "en", None).unwrap(), "light en");
+            assert_eq!(instance.render_template_variant(&ctx, "logo", "en", "dark", None).unwrap(), "dark en");
+            assert_eq!(instance.render_template_variant(&ctx, "logo", "cs", "dark", None).unwrap(), "light cs");
+            assert_eq!(instance.render_template_variant(&ctx, "logo", "de", "dark", Some("en")).unwrap(), "dark en");
+            assert_eq!(instance.render_template_variant(&ctx, "logo", "cs", "dark", Some("en")).unwrap(), "light cs");
+
+            let request = RenderRequest::template("logo".to_owned(), ctx)
+                .with_language("en".to_owned())
+                .with_variant("brand_a".to_owned())
+                .with_variant("dark".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("dark en".to_owned()));
+
+            let mut variants = Vec::new();
+            instance.render_for_testing(&HashMap::new(), |render| variants.push(render.variant.map(|v| v.to_owned())));
+            assert_eq!(variants, vec![None, None, Some("dark".to_owned())]);
+        }
+
+        #[test]
+        fn render_brand() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "footer".to_owned(),
+                Template::new(vec![
+                    Content::new("{{ sender }} {{ logo }}".to_owned(), vec!["en".to_owned()]),
+                    Content::new("ACME {{ logo }}".to_owned(), vec!["en".to_owned()]).with_variant("acme".to_owned()),
+                ]).unwrap(),
+            ).unwrap();
+            let mut default = Context::new();
+            default.insert("logo", "default.png");
+            default.insert("sender", "Terarium");
+            let mut acme = Context::new();
+            acme.insert("logo", "acme.png");
+            let mut globex = Context::new();
+            globex.insert("logo", "globex.png");
+            builder.set_brand_resolver(
+                crate::StaticBrandResolver::new(crate::Brand::new(default))
+                    .add_brand("acme".to_owned(), crate::Brand::new(acme))
+                    .add_brand("globex".to_owned(), crate::Brand::new(globex))
+            );
+            let instance = builder.build().unwrap();
+
+            let render = |brand: &str| {
+                let request = RenderRequest::template("footer".to_owned(), Context::new())
+                    .with_language("en".to_owned())
+                    .with_brand(brand.to_owned());
+                instance.render(&request).unwrap().output
+            };
+            assert_eq!(render("acme"), RenderOutput::Template("ACME acme.png".to_owned()));
+            assert_eq!(render("globex"), RenderOutput::Template("Terarium globex.png".to_owned()));
+            assert_eq!(render("unknown"), RenderOutput::Template("Terarium default.png".to_owned()));
+        }
+
+        #[test]
+        fn render_in_timezone() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "sent_at".to_owned(),
+                Template::new(vec![
+                    Content::new("{{ sent_at | date(format=\"%H:%M\") }} {{ tz }} {{ now > 0 }}".to_owned(), vec!["en".to_owned()]),
+                ]).unwrap(),
+            ).unwrap();
+            let instance = builder.build().unwrap();
+            let mut ctx = Context::new();
+            ctx.insert("sent_at", "2024-01-15T08:00:00Z");
+
+            let request = RenderRequest::template("sent_at".to_owned(), ctx)
+                .with_language("en".to_owned())
+                .with_timezone("Europe/Prague".to_owned());
+            let outcome = instance.render(&request).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("09:00 Europe/Prague true".to_owned()));
+        }
+
+        #[test]
+        fn render_group_normalized() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template(
+                "subject".to_owned(),
+                Template::new(vec![Content::new("Ahoj {{ name }}\u{200b}".to_owned(), vec!["cs".to_owned()])]).unwrap(),
+            ).unwrap();
+            builder.add_template(
+                "text".to_owned(),
+                Template::new(vec![Content::new("Ahoj {{ name }}\u{200b}".to_owned(), vec!["cs".to_owned()])]).unwrap(),
+            ).unwrap();
+            builder.add_group(
+                "mail".to_owned(),
+                TemplateGroupBuilder::default()
+                    .add_member("subject".to_owned(), "subject".to_owned())
+                    .add_member("text".to_owned(), "text".to_owned())
+                    .build(),
+            ).unwrap();
+            builder.set_output_normalization(crate::OutputNormalization::new().encode_member("subject".to_owned()));
+            let instance = builder.build().unwrap();
+            let mut ctx = Context::new();
+            ctx.insert("name", "Jir\u{30c}i\u{301}");
+
+            let result = instance.render_group(&ctx, "mail", "cs", None).unwrap();
+            assert_eq!(result["text"], "Ahoj Jiří");
+            assert_eq!(result["subject"], crate::encode_header("Ahoj Jiří"));
+        }
+
+        #[test]
+        fn render_with_integrity() {
             let instance = make_instance();
-            let ctx = make_context();
-            let result = instance.render_template(&ctx, "template_a", "de", Some("fr"));
+            let request = RenderRequest::group("group_a".to_owned(), make_context()).with_language("en".to_owned());
+            assert!(instance.render(&request).unwrap().integrity.is_none());
 
-            assert!(match result.unwrap_err() {
-                TerariumError::LanguageNotFound => true,
-                _ => false
-            })
+            let outcome = instance.render(&request.with_integrity()).unwrap();
+            let RenderOutput::Group(members) = &outcome.output else { panic!("group expected") };
+            let integrity = outcome.integrity.unwrap();
+            assert_eq!(integrity.catalog_fingerprint, instance.fingerprint());
+            assert_eq!(integrity.outputs["A"].template_key, "template_a");
+            assert_eq!(integrity.outputs["A"].template_fingerprint, instance.fingerprints["template_a"]);
+            assert!(integrity.outputs["B"].verify(&members["B"]));
         }
 
         #[test]
-        fn render_group() {
+        fn render_deterministic() {
+            let build = |determinism: Determinism, verify: bool| {
+                let mut builder = TerariumBuilder::default();
+                builder.add_template(
+                    "random".to_owned(),
+                    Template::new(vec![
+                        Content::new("{{ now() }} {{ get_random(end=1000000) }}".to_owned(), vec!["en".to_owned()]),
+                    ]).unwrap(),
+                ).unwrap();
+                builder.set_determinism(determinism);
+                builder.set_verify_determinism(verify);
+                builder.build().unwrap()
+            };
+            let mut ctx = Context::new();
+            ctx.insert("now", "2024-01-01T00:00:00Z");
+
+            let instance = build(Determinism::Replace, true);
+            let output = instance.render_template(&ctx, "random", "en", None).unwrap();
+            assert!(output.starts_with("2024-01-01T00:00:00Z "));
+            assert_eq!(instance.render_template(&ctx, "random", "en", None).unwrap(), output);
+            let request = |seed| RenderRequest::template("random".to_owned(), ctx.clone())
+                .with_language("en".to_owned())
+                .with_random_seed(seed);
+            let seeded = instance.render(&request(7)).unwrap().output;
+            assert_eq!(instance.render(&request(7)).unwrap().output, seeded);
+            assert_ne!(seeded, RenderOutput::Template(output.clone()));
+
+            let instance = build(Determinism::Forbid, false);
+            let result = instance.render_template(&ctx, "random", "en", None);
+            assert!(matches!(result, Err(TerariumError::RenderingFailed(..))));
+
+            let instance = build(Determinism::Allow, true);
+            let result = instance.render_template(&ctx, "random", "en", None);
+            assert!(matches!(result, Err(TerariumError::NondeterministicOutput(key)) if key == "random"));
+        }
+
+        #[test]
+        fn replay() {
+            let mut instance = make_instance();
+            let request = RenderRequest::template("template_b".to_owned(), make_context())
+                .with_language("cs".to_owned());
+            let error = instance.render(&request).unwrap_err();
+            let snapshot = instance.snapshot(&request, &error);
+
+            let report = instance.replay(&snapshot);
+            assert!(!report.succeeded());
+            assert!(!report.catalog_changed);
+
+            let template = Template::new(vec![Content::new("fixed".to_owned(), vec!["cs".to_owned()])]).unwrap();
+            instance.add_template("template_b".to_owned(), template, "admin").unwrap();
+            let report = instance.replay(&snapshot);
+            assert!(report.succeeded());
+            assert!(report.catalog_changed);
+        }
+
+        #[test]
+        fn stats() {
+            let mut instance = make_instance();
+            let stats = instance.stats();
+            assert_eq!((stats.templates, stats.contents, stats.groups, stats.languages), (2, 3, 1, 2));
+            assert_eq!(stats.content_bytes, 69);
+            assert_eq!(stats.largest_templates[0], ("template_a".to_owned(), 44));
+            assert_eq!(stats.to_string(), "2 templates (3 contents in 2 languages, 69 bytes), 1 groups");
+
+            instance.remove_template("template_a", "admin").unwrap();
+            assert_eq!(instance.stats().largest_templates, vec![("template_b".to_owned(), 25)]);
+        }
+
+        #[test]
+        fn generate_docs() {
             let instance = make_instance();
-            let context = make_context();
-            let group_result = instance.render_group(&context, "group_a", "en", None);
-            assert!(group_result.is_ok());
-            let group_result = group_result.unwrap();
-            assert_eq!(group_result.get("A").unwrap(), "template_a en john");
-            assert_eq!(group_result.get("B").unwrap(), "template_b en doe");
+            let samples = HashMap::from([
+                ("template_a".to_owned(), make_context()),
+                ("template_b".to_owned(), Context::new()),
+            ]);
+            let docs = instance.generate_docs(DocsFormat::Markdown, &samples);
+            assert!(docs.contains(
+                "## template_a\n\n- Languages: `cs`, `en`\n- Required variables: `name`\n- Groups: `group_a`\n\n\
+                Sample:\n\n```\ntemplate_a cs john\n```\n"
+            ));
+            assert!(docs.contains("## template_b\n\n- Languages: `en`\n- Required variables: `surname`"));
+            assert!(docs.contains("Sample render failed: Error when rendering template template_b"));
         }
 
         #[test]
-        fn render_group_with_fallback() {
+        fn export_schema() {
             let instance = make_instance();
-            let context = make_context();
-            let group_result = instance.render_group(&context, "group_a", "cs", Some("en"));
-            assert!(group_result.is_ok());
-            let group_result = group_result.unwrap();
-            assert_eq!(group_result.get("A").unwrap(), "template_a cs john");
-            assert_eq!(group_result.get("B").unwrap(), "template_b en doe");
+            let schema = instance.export_schema();
+            let schemas = &schema["components"]["schemas"];
+            assert_eq!(schemas["TemplateKey"]["enum"], serde_json::json!(["template_a", "template_b"]));
+            assert_eq!(schemas["GroupKey"]["enum"], serde_json::json!(["group_a"]));
+            assert_eq!(schemas["Language"]["enum"], serde_json::json!(["cs", "en"]));
+            assert_eq!(schemas["TemplateContext.template_b"]["required"], serde_json::json!(["surname"]));
+            assert_eq!(schemas["GroupContext.group_a"]["required"], serde_json::json!(["name", "surname"]));
+            assert_eq!(schemas["GroupContext.group_a"]["x-terarium-languages"], serde_json::json!(["cs", "en"]));
         }
 
         #[test]
-        fn render_group_when_invalid_language() {
+        fn render_template_multilingual() {
             let instance = make_instance();
-            let context = make_context();
-            let group_result = instance.render_group(&context, "group_a", "cs", Some("fr"));
-            assert!(group_result.is_err());
-            assert!(match group_result.unwrap_err() {
-                TerariumError::LanguageNotFound => true,
-                _ => false
-            })
+            let result = instance.render_template_multilingual(
+                &make_context(),
+                "template_a",
+                &["en", "cs", "de"],
+                |language, ctx| if language == "cs" { ctx.insert("name", "jan") },
+            ).unwrap();
+            assert_eq!(result.len(), 2);
+            assert_eq!(result["en"], "template_a en john");
+            assert_eq!(result["cs"], "template_a cs jan");
         }
 
         #[test]
-        fn render_nested_templates() {
+        fn rtl_language() {
             let mut builder = TerariumBuilder::default();
-            let mut tpl_a = Template::default();
-            tpl_a.add_content(
-                Content::new("This is content {{value_1}} {% include 'tpl_b_cs' %}".to_owned(), vec!["cs".to_owned()])
-            ).unwrap();
-            builder.add_template("tpl_a".to_owned(), tpl_a).unwrap();
+            let mut template = Template::default();
+            let content = Content::new("<p dir=\"{{ dir }}\">{{ is_rtl }}</p>".to_owned(), vec!["ar".to_owned(), "en".to_owned()]);
+            template.add_content(content).unwrap();
+            builder.add_template("tpl".to_owned(), template).unwrap();
+            builder.add_rtl_language("ar".to_owned());
+            let instance = builder.build().unwrap();
 
-            let mut tpl_b = Template::default();
-            tpl_b.add_content(
-                Content::new_named("This is nested {{value_2}}".to_owned(), vec!["cs".to_owned()], "tpl_b_cs".to_owned())
-            ).unwrap();
-            builder.add_template("tpl_b".to_owned(), tpl_b).unwrap();
+            assert_eq!(instance.language_direction("ar-EG"), TextDirection::Rtl);
+            let ctx = Context::new();
+            assert_eq!(instance.render_template(&ctx, "tpl", "ar", None).unwrap(), "<p dir=\"rtl\">true</p>");
+            assert_eq!(instance.render_template(&ctx, "tpl", "en", None).unwrap(), "<p dir=\"ltr\">false</p>");
+        }
 
+        #[test]
+        fn validity_window() {
+            let mut builder = TerariumBuilder::default();
+            let expired = ValidityWindow::new().until(SystemTime::now() - Duration::from_secs(60));
+            let content = |text: &str| Content::new(text.to_owned(), vec!["en".to_owned()]);
+            let mut campaign = Template::new(vec![content("campaign")]).unwrap();
+            campaign.set_validity(expired.clone().fallback("evergreen".to_owned()));
+            builder.add_template("campaign".to_owned(), campaign).unwrap();
+            let mut upcoming = Template::new(vec![content("upcoming")]).unwrap();
+            upcoming.set_validity(ValidityWindow::new().from(SystemTime::now() + Duration::from_secs(3600)));
+            builder.add_template("upcoming".to_owned(), upcoming).unwrap();
+            builder.add_template("evergreen".to_owned(), Template::new(vec![content("evergreen")]).unwrap()).unwrap();
+            builder.add_group("season".to_owned(), TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "upcoming".to_owned())
+                .valid(expired)
+                .build()).unwrap();
             let instance = builder.build().unwrap();
-            let mut ctx = Context::default();
-            ctx.insert("value_1", "foo");
-            ctx.insert("value_2", "bar");
 
-            let result = instance.render_template(&ctx, "tpl_a", "cs", None).unwrap();
-            assert_eq!(result.as_str(), "This is content foo This is nested bar");
+            let ctx = Context::new();
+            assert_eq!(instance.render_template(&ctx, "campaign", "en", None).unwrap(), "evergreen");
+            let result = instance.render_template(&ctx, "upcoming", "en", None);
+            assert!(matches!(result, Err(TerariumError::OutsideValidity(key)) if key == "upcoming"));
+            let result = instance.render_group(&ctx, "season", "en", None);
+            assert!(matches!(result, Err(TerariumError::OutsideValidity(key)) if key == "season"));
+        }
+
+        #[test]
+        fn override_rule() {
+            let mut builder = TerariumBuilder::default();
+            let languages = vec!["cs".to_owned(), "en".to_owned()];
+            let mut template = Template::new(vec![
+                Content::new("banner".to_owned(), languages.clone()),
+                Content::new("holiday".to_owned(), languages.clone()).with_variant("holiday".to_owned()),
+                Content::new("dark".to_owned(), languages).with_variant("dark".to_owned()),
+            ]).unwrap();
+            template.add_override(OverrideRule::new("expired".to_owned(), "dark".to_owned())
+                .until(SystemTime::now() - Duration::from_secs(60)));
+            template.add_override(OverrideRule::new("cs-holiday".to_owned(), "holiday".to_owned())
+                .language("cs".to_owned()));
+            builder.add_template("banner".to_owned(), template).unwrap();
+            let instance = builder.build().unwrap();
+
+            let request = |language: &str| RenderRequest::template("banner".to_owned(), Context::new())
+                .with_language(language.to_owned());
+            let outcome = instance.render(&request("cs")).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("holiday".to_owned()));
+            assert_eq!(outcome.overrides, vec!["cs-holiday"]);
+            let outcome = instance.render(&request("en")).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("banner".to_owned()));
+            assert!(outcome.overrides.is_empty());
+            let outcome = instance.render(&request("cs").with_variant("dark".to_owned())).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("dark".to_owned()));
+            let outcome = instance.render(&request("en").with_time(SystemTime::UNIX_EPOCH)).unwrap();
+            assert_eq!(outcome.output, RenderOutput::Template("dark".to_owned()));
+            assert_eq!(outcome.overrides, vec!["expired"]);
+        }
+
+        #[test]
+        fn group_hook() {
+            let mut builder = TerariumBuilder::default();
+            let content = Content::new("<p>{{ name }}</p>".to_owned(), vec!["en".to_owned()]);
+            let template = Template::new(vec![content]).unwrap();
+            builder.add_template("html".to_owned(), template).unwrap();
+            builder.add_group("mail".to_owned(), TemplateGroupBuilder::default()
+                .add_member("html".to_owned(), "html".to_owned())
+                .build()).unwrap();
+            builder.add_group_hook(|_: &str, members: &mut HashMap<String, String>| {
+                let preheader = members["html"].trim_start_matches("<p>").trim_end_matches("</p>").to_owned();
+                members.insert("preheader".to_owned(), preheader);
+                Ok(())
+            });
+            builder.add_group_hook(|_: &str, members: &mut HashMap<String, String>| {
+                match members["preheader"].is_empty() {
+                    true => Err("Empty preheader".to_owned()),
+                    false => Ok(()),
+                }
+            });
+            let instance = builder.build().unwrap();
+
+            let result = instance.render_group(&make_context(), "mail", "en", None).unwrap();
+            assert_eq!(result["preheader"], "john");
+            let mut ctx = Context::new();
+            ctx.insert("name", "");
+            let result = instance.render_group(&ctx, "mail", "en", None);
+            assert!(matches!(result, Err(TerariumError::GroupRejected(_, reason)) if reason == "Empty preheader"));
+        }
+
+        #[test]
+        fn link_rewriter() {
+            let mut builder = TerariumBuilder::default();
+            let link = "<a href=\"https://example.com\">{{ name }}</a>";
+            let template = Template::new(vec![
+                Content::new(link.to_owned(), vec!["en".to_owned()]).with_kind(ContentKind::Html),
+                Content::new(link.to_owned(), vec!["cs".to_owned()]),
+            ]).unwrap();
+            builder.add_template("link".to_owned(), template).unwrap();
+            builder.set_link_rewriter(|url: &str| format!("{}?utm_source=mail", url));
+            let instance = builder.build().unwrap();
+
+            let result = instance.render_template(&make_context(), "link", "en", None).unwrap();
+            assert_eq!(result, "<a href=\"https://example.com?utm_source=mail\">john</a>");
+            let result = instance.render_template(&make_context(), "link", "cs", None).unwrap();
+            assert_eq!(result, "<a href=\"https://example.com\">john</a>");
+        }
+
+        #[test]
+        fn compliance_rule() {
+            let mut builder = TerariumBuilder::default();
+            let content = |text: &str| Content::new(text.to_owned(), vec!["en".to_owned()]);
+            let mut promo = Template::new(vec![content("Sale! {{ name }}")]).unwrap();
+            promo.add_tag("marketing".to_owned());
+            builder.add_template("promo".to_owned(), promo).unwrap();
+            let mut newsletter = Template::new(vec![content("News <a href=\"{{ unsubscribe_url }}\">")]).unwrap();
+            newsletter.add_tag("marketing".to_owned());
+            builder.add_template("newsletter".to_owned(), newsletter).unwrap();
+            builder.add_template("receipt".to_owned(), Template::new(vec![content("Receipt")]).unwrap()).unwrap();
+            builder.add_compliance_rule(ComplianceRule::variable("marketing".to_owned(), "unsubscribe_url".to_owned()));
+            let instance = builder.build().unwrap();
+
+            let mut ctx = make_context();
+            ctx.insert("unsubscribe_url", "https://example.com/u");
+            assert!(instance.render_template(&ctx, "newsletter", "en", None).is_ok());
+            assert!(instance.render_template(&ctx, "receipt", "en", None).is_ok());
+            let result = instance.render_template(&ctx, "promo", "en", None);
+            assert!(matches!(result, Err(TerariumError::ComplianceViolation(key, _)) if key == "promo"));
+        }
+
+        #[test]
+        fn leftover_placeholder() {
+            #[derive(Clone, Default)]
+            struct Observer(Arc<Mutex<Vec<String>>>);
+
+            impl RenderObserver for Observer {
+                fn leftover_placeholder(&self, leftover: &LeftoverPlaceholder) {
+                    let message = format!("{}:{} {}", leftover.template_key, leftover.line, leftover.snippet);
+                    self.0.lock().unwrap().push(message);
+                }
+            }
+
+            let make = |check: PlaceholderCheck, observer: Observer| {
+                let mut builder = TerariumBuilder::default();
+                let content = Content::new("Hello\n{% raw %}{{ name }}{% endraw %}".to_owned(), vec!["en".to_owned()]);
+                builder.add_template("tpl".to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+                builder.set_placeholder_check(check);
+                builder.set_observer(observer);
+                builder.build().unwrap()
+            };
+            let observer = Observer::default();
+
+            let instance = make(PlaceholderCheck::Warn, observer.clone());
+            assert_eq!(instance.render_template(&Context::new(), "tpl", "en", None).unwrap(), "Hello\n{{ name }}");
+            assert_eq!(*observer.0.lock().unwrap(), vec!["tpl:2 {{ name }}"]);
+
+            let instance = make(PlaceholderCheck::Reject, observer);
+            let result = instance.render_template(&Context::new(), "tpl", "en", None);
+            assert!(matches!(result, Err(TerariumError::LeftoverPlaceholder(_, 2))));
+        }
+
+        #[test]
+        fn missing_variable_markers() {
+            let mut builder = TerariumBuilder::default();
+            let content = Content::new("{{ name }} {{ order.id }}".to_owned(), vec!["en".to_owned()]);
+            builder.add_template("tpl".to_owned(), Template::new(vec![content]).unwrap()).unwrap();
+            builder.set_missing_variables(MissingVariables::Marker);
+            let instance = builder.build().unwrap();
+
+            let result = instance.render_template(&make_context(), "tpl", "en", None).unwrap();
+            assert_eq!(result, "john ⟦order.id⟧");
+            let request = RenderRequest::template("tpl".to_owned(), make_context())
+                .with_language("en".to_owned())
+                .with_missing_variables(MissingVariables::Error);
+            assert!(instance.render(&request).is_err());
+        }
+
+        #[test]
+        fn profile() {
+            let make = |profile: Profile| {
+                let mut builder = TerariumBuilder::default();
+                let mut template = Template::new(vec![
+                    Content::new("{{ name }} {{ get_random(end=1000000) }}".to_owned(), vec!["en".to_owned()]),
+                ]).unwrap();
+                template.set_cache_policy(CachePolicy::new());
+                builder.add_template("tpl".to_owned(), template).unwrap();
+                builder.set_profile(profile);
+                builder.build().unwrap()
+            };
+
+            let instance = make(Profile::Development);
+            assert_eq!(instance.profile(), Profile::Development);
+            let first = instance.render_template(&Context::new(), "tpl", "en", None).unwrap();
+            assert!(first.starts_with("⟦name⟧ "));
+            let outputs: HashSet<_> = (0..5)
+                .map(|_| instance.render_template(&Context::new(), "tpl", "en", None).unwrap())
+                .collect();
+            assert!(outputs.len() > 1);
+
+            let instance = make(Profile::Production);
+            assert!(instance.render_template(&Context::new(), "tpl", "en", None).is_err());
+            let first = instance.render_template(&make_context(), "tpl", "en", None).unwrap();
+            assert_eq!(instance.render_template(&make_context(), "tpl", "en", None).unwrap(), first);
+        }
+
+        #[test]
+        fn chaos_mode() {
+            let make = |profile: Profile| {
+                let mut builder = TerariumBuilder::default();
+                let template = Template::new(vec![Content::new("Hi".to_owned(), vec!["en".to_owned()])]).unwrap();
+                builder.add_template("tpl".to_owned(), template).unwrap();
+                builder.set_profile(profile);
+                builder.set_chaos_mode(ChaosMode::new().failures(1.0).delays(1.0, Duration::from_millis(1)));
+                builder.build().unwrap()
+            };
+
+            let error = make(Profile::Staging).render_template(&Context::new(), "tpl", "en", None).unwrap_err();
+            assert!(matches!(&error, TerariumError::InjectedFailure(key) if key == "tpl"));
+            assert_eq!(error.code(), "INJECTED_FAILURE");
+            assert!(make(Profile::Production).render_template(&Context::new(), "tpl", "en", None).is_ok());
+        }
+
+        #[test]
+        fn watermark() {
+            let make = |profile: Profile| {
+                let mut builder = TerariumBuilder::default();
+                let html = Content::new("<body><p>Hi</p></body>".to_owned(), vec!["en".to_owned()]);
+                let template = Template::new(vec![
+                    html.with_kind(ContentKind::Html),
+                    Content::new("Hi".to_owned(), vec!["cs".to_owned()]),
+                ]).unwrap();
+                builder.add_template("tpl".to_owned(), template).unwrap();
+                builder.set_profile(profile);
+                builder.set_watermark("<!-- TEST SEND -->".to_owned());
+                builder.build().unwrap()
+            };
+
+            let instance = make(Profile::Staging);
+            let result = instance.render_template(&Context::new(), "tpl", "en", None).unwrap();
+            assert_eq!(result, "<body><!-- TEST SEND --><p>Hi</p></body>");
+            assert_eq!(instance.render_template(&Context::new(), "tpl", "cs", None).unwrap(), "Hi");
+            let instance = make(Profile::Production);
+            let result = instance.render_template(&Context::new(), "tpl", "en", None).unwrap();
+            assert_eq!(result, "<body><p>Hi</p></body>");
+        }
+
+        #[test]
+        fn tags() {
+            let mut builder = TerariumBuilder::default();
+            for (key, tag) in [("welcome", "transactional"), ("promo", "marketing"), ("reset", "transactional")] {
+                let content = Content::new("{{ get_random(end=1000000) }}".to_owned(), vec!["en".to_owned()]);
+                let mut template = Template::new(vec![content]).unwrap();
+                template.add_tag(tag.to_owned());
+                builder.add_template(key.to_owned(), template).unwrap();
+            }
+            builder.add_group("mail".to_owned(), TemplateGroupBuilder::default()
+                .add_member("body".to_owned(), "welcome".to_owned())
+                .tag("transactional".to_owned())
+                .build()).unwrap();
+            builder.set_tag_cache_policy("marketing".to_owned(), CachePolicy::new());
+            let instance = builder.build().unwrap();
+
+            assert_eq!(instance.templates_with_tag("transactional"), vec!["reset", "welcome"]);
+            assert_eq!(instance.groups_with_tag("transactional"), vec!["mail"]);
+            assert!(instance.groups_with_tag("marketing").is_empty());
+            let render = |key: &str| instance.render_template(&Context::new(), key, "en", None).unwrap();
+            assert_eq!(render("promo"), render("promo"));
+        }
+
+        #[test]
+        fn feature_flag() {
+            let mut builder = TerariumBuilder::default();
+            let text = "{% if feature(name=\"new_footer\") %}new{% else %}old{% endif %}";
+            builder.add_template("footer".to_owned(), Template::new(vec![
+                Content::new(text.to_owned(), vec!["en".to_owned()]),
+            ]).unwrap()).unwrap();
+            builder.set_flag_provider(|flag: &str, recipient: Option<&Value>| {
+                flag == "new_footer" && recipient.and_then(|r| r.as_u64()).is_some_and(|id| id % 2 == 0)
+            });
+            let instance = builder.build().unwrap();
+
+            let render = |recipient: u64| {
+                let mut ctx = Context::new();
+                ctx.insert(RECIPIENT_VARIABLE, &recipient);
+                instance.render_template(&ctx, "footer", "en", None).unwrap()
+            };
+            assert_eq!(render(2), "new");
+            assert_eq!(render(3), "old");
+        }
+
+        #[test]
+        fn value_resolver() {
+            let mut builder = TerariumBuilder::default();
+            builder.add_template("greeting".to_owned(), Template::new(vec![
+                Content::new("Hello {{ resolve(key=\"display_name\") }}".to_owned(), vec!["en".to_owned()]),
+            ]).unwrap()).unwrap();
+            builder.set_value_resolver(|key: &str, recipient: Option<&Value>| match (key, recipient) {
+                ("display_name", Some(recipient)) => Ok(Value::from(format!("user #{}", recipient))),
+                _ => Err("unknown recipient".to_owned()),
+            });
+            let instance = builder.build().unwrap();
+
+            let mut ctx = Context::new();
+            ctx.insert(RECIPIENT_VARIABLE, &42);
+            assert_eq!(instance.render_template(&ctx, "greeting", "en", None).unwrap(), "Hello user #42");
+            assert!(instance.render_template(&Context::new(), "greeting", "en", None).is_err());
+        }
+
+        #[test]
+        fn member_deduplication() {
+            let render_group = |deduplicate: bool| {
+                let renders = Arc::new(Mutex::new(0));
+                let counter = renders.clone();
+                let mut builder = TerariumBuilder::default();
+                builder.add_template("divider".to_owned(), Template::new(vec![
+                    Content::new("<hr>{{ resolve(key=\"n\") }}".to_owned(), vec!["en".to_owned()]),
+                ]).unwrap()).unwrap();
+                let mut expired = Template::new(vec![Content::new("old".to_owned(), vec!["en".to_owned()])]).unwrap();
+                let window = ValidityWindow::new().until(SystemTime::UNIX_EPOCH).fallback("divider".to_owned());
+                expired.set_validity(window);
+                builder.add_template("old_divider".to_owned(), expired).unwrap();
+                builder.add_group("page".to_owned(), TemplateGroupBuilder::default()
+                    .add_member("top".to_owned(), "divider".to_owned())
+                    .add_member("middle".to_owned(), "old_divider".to_owned())
+                    .add_member("bottom".to_owned(), "divider".to_owned())
+                    .build()).unwrap();
+                builder.set_value_resolver(move |_: &str, _: Option<&Value>| {
+                    *counter.lock().unwrap() += 1;
+                    Ok(Value::from(""))
+                });
+                builder.set_member_deduplication(deduplicate);
+                let result = builder.build().unwrap().render_group(&Context::new(), "page", "en", None).unwrap();
+                assert_eq!(result["top"], result["bottom"]);
+                assert_eq!(result["top"], result["middle"]);
+                let renders = *renders.lock().unwrap();
+                renders
+            };
+            assert_eq!(render_group(true), 1);
+            assert_eq!(render_group(false), 3);
+        }
+
+        #[test]
+        #[cfg(feature = "async")]
+        fn render_group_async_in_scope() {
+            let renders = Arc::new(Mutex::new(0));
+            let counter = renders.clone();
+            let mut builder = TerariumBuilder::default();
+            let text = "{{ 0 | date(format=\"%H\") }}{{ resolve(key=\"n\") }}";
+            let template = Template::new(vec![Content::new(text.to_owned(), vec!["en".to_owned()])]).unwrap();
+            builder.add_template("hour".to_owned(), template).unwrap();
+            let mut expired = Template::new(vec![Content::new("old".to_owned(), vec!["en".to_owned()])]).unwrap();
+            expired.set_validity(ValidityWindow::new().until(SystemTime::UNIX_EPOCH).fallback("hour".to_owned()));
+            builder.add_template("old_hour".to_owned(), expired).unwrap();
+            builder.add_group("page".to_owned(), TemplateGroupBuilder::default()
+                .add_member("top".to_owned(), "hour".to_owned())
+                .add_member("middle".to_owned(), "old_hour".to_owned())
+                .add_member("bottom".to_owned(), "hour".to_owned())
+                .build()).unwrap();
+            builder.set_value_resolver(move |_: &str, _: Option<&Value>| {
+                *counter.lock().unwrap() += 1;
+                Ok(Value::from(""))
+            });
+            builder.set_member_deduplication(true);
+            builder.set_group_parallelism(2);
+            let instance = Arc::new(builder.build().unwrap());
+
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let scope = RenderScope { timezone: Some("Europe/Prague".to_owned()), ..RenderScope::default() };
+            let render = || runtime.block_on(instance.render_group_async(&Context::new(), "page", "en", None));
+            let result = with_scope(scope, render).unwrap();
+            assert_eq!(result["top"], "01");
+            assert_eq!(result["middle"], "01");
+            assert_eq!(result["bottom"], "01");
+            assert_eq!(*renders.lock().unwrap(), 1);
+        }
+
+        #[test]
+        fn error_source_chain() {
+            let instance = make_instance();
+            let error = instance.render_template(&Context::new(), "template_a", "en", None).unwrap_err();
+            assert_eq!(error.to_string(), "Error when rendering template template_a");
+            let mut chain = Vec::new();
+            let mut source = std::error::Error::source(&error);
+            while let Some(error) = source {
+                chain.push(error.to_string());
+                source = error.source();
+            }
+            assert!(chain.iter().any(|message| message.contains("Variable `name` not found")), "{:?}", chain);
+        }
+
+        #[test]
+        fn memory_report() {
+            let instance = make_instance();
+            let report = instance.memory_report();
+            assert_eq!(report.contents.len(), 3);
+            assert_eq!(report.templates.len(), 2);
+            assert!(report.templates["template_a"] > report.templates["template_b"]);
+            assert!(report.groups > 0);
+            assert_eq!(report.total(), report.templates.values().sum::<usize>() + report.groups);
+        }
+
+        #[test]
+        fn render_template_to() {
+            let instance = make_instance();
+            let mut output = Vec::new();
+            instance.render_template_to(&make_context(), "template_a", "en", None, &mut output).unwrap();
+            instance.render_template_to(&make_context(), "template_b", "en", None, &mut output).unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), "template_a en johntemplate_b en doe");
+        }
+
+        #[test]
+        fn render_template_by_handle() {
+            let instance = make_instance();
+            let handle = instance.template_handle("template_a").unwrap();
+            assert_eq!(handle.key(), "template_a");
+            let result = instance.render_template_by_handle(&make_context(), &handle, "de", Some("cs")).unwrap();
+            assert_eq!(result, "template_a cs john");
+            assert!(instance.template_handle("template_x").is_none());
+
+            let en_a = instance.template_map["template_a"].get_key_value("en").unwrap().0;
+            let en_b = instance.template_map["template_b"].get_key_value("en").unwrap().0;
+            assert!(Arc::ptr_eq(en_a, en_b));
         }
 
         fn make_instance() -> Terarium {
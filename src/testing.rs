@@ -0,0 +1,134 @@
+//! Assertions for test suites of applications using the catalog.
+//! Assertions panic with description of the failure, so they are intended for tests only.
+
+use std::collections::{BTreeSet, HashSet};
+
+use tera::Context;
+
+use crate::Terarium;
+
+
+/// Assert the template rendered in the language (without fallback) contains the `needle`.
+#[track_caller]
+pub fn assert_template_contains(
+    terarium: &Terarium,
+    template_key: &str,
+    language: &str,
+    context: &Context,
+    needle: &str,
+) {
+    let output = terarium
+        .render_template(context, template_key, language, None)
+        .unwrap_or_else(|e| panic!("Template {} in language {} cannot be rendered: {}", template_key, language, e));
+    assert!(
+        output.contains(needle),
+        "Template {} in language {} does not contain {:?}, output:\n{}", template_key, language, needle, output,
+    );
+}
+
+
+/// Assert the member of the group rendered in the language (without fallback) contains the `needle`.
+#[track_caller]
+pub fn assert_group_member_contains(
+    terarium: &Terarium,
+    group_key: &str,
+    member_key: &str,
+    language: &str,
+    context: &Context,
+    needle: &str,
+) {
+    let outputs = terarium
+        .render_group(context, group_key, language, None)
+        .unwrap_or_else(|e| panic!("Group {} in language {} cannot be rendered: {}", group_key, language, e));
+    let output = outputs
+        .get(member_key)
+        .unwrap_or_else(|| panic!("Group {} has no member {}", group_key, member_key));
+    assert!(
+        output.contains(needle),
+        "Member {} of group {} in language {} does not contain {:?}, output:\n{}",
+        member_key, group_key, language, needle, output,
+    );
+}
+
+
+/// Assert the template has content in every language of the `languages`.
+#[track_caller]
+pub fn assert_template_languages(terarium: &Terarium, template_key: &str, languages: &[&str]) {
+    let missing = missing_languages(terarium, template_key, languages);
+    assert!(missing.is_empty(), "Template {} has no content in languages {:?}", template_key, missing);
+}
+
+
+/// Assert template of every member of the group has content in every language of the `languages`.
+#[track_caller]
+pub fn assert_group_languages(terarium: &Terarium, group_key: &str, languages: &[&str]) {
+    let group = terarium.group(group_key).unwrap_or_else(|| panic!("Group {} does not exist", group_key));
+    let template_keys: HashSet<String> = terarium.template_languages().into_iter().map(|(key, _)| key).collect();
+    let mut missing: Vec<_> = group
+        .resolve_members(template_keys.iter())
+        .into_iter()
+        .map(|(member_key, template_key)| (member_key, missing_languages(terarium, template_key, languages)))
+        .filter(|(_, languages)| !languages.is_empty())
+        .collect();
+    missing.sort();
+    assert!(missing.is_empty(), "Members of group {} have no content in languages {:?}", group_key, missing);
+}
+
+
+fn missing_languages(terarium: &Terarium, template_key: &str, languages: &[&str]) -> Vec<String> {
+    let available: BTreeSet<String> = terarium
+        .template_languages()
+        .into_iter()
+        .filter(|(key, _)| key == template_key)
+        .map(|(_, language)| language)
+        .collect();
+    languages.iter().filter(|l| !available.contains(**l)).map(|l| l.to_string()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{Content, Template, TemplateGroupBuilder, TerariumBuilder};
+
+    use super::*;
+
+    fn make_instance() -> Terarium {
+        let mut builder = TerariumBuilder::default();
+        builder.add_template("subject".to_owned(), Template::new(vec![
+            Content::new("Hello {{ name }}".to_owned(), vec!["en".to_owned(), "cs".to_owned()]),
+        ]).unwrap()).unwrap();
+        builder.add_template("body".to_owned(), Template::new(vec![
+            Content::new("Body".to_owned(), vec!["en".to_owned()]),
+        ]).unwrap()).unwrap();
+        builder.add_group("email".to_owned(), TemplateGroupBuilder::default()
+            .add_member("subject".to_owned(), "subject".to_owned())
+            .add_member("body".to_owned(), "body".to_owned())
+            .build()).unwrap();
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn passing_assertions() {
+        let instance = make_instance();
+        let mut context = Context::new();
+        context.insert("name", "john");
+        assert_template_contains(&instance, "subject", "cs", &context, "john");
+        assert_group_member_contains(&instance, "email", "subject", "en", &context, "Hello john");
+        assert_template_languages(&instance, "subject", &["en", "cs"]);
+        assert_group_languages(&instance, "email", &["en"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Members of group email have no content in languages [(\"body\", [\"cs\"])]")]
+    fn missing_group_language() {
+        assert_group_languages(&make_instance(), "email", &["en", "cs"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Member subject of group email in language en does not contain \"jane\"")]
+    fn missing_needle() {
+        let mut context = Context::new();
+        context.insert("name", "john");
+        assert_group_member_contains(&make_instance(), "email", "subject", "en", &context, "jane");
+    }
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use tera::Context;
+
+use crate::redaction::{redact_text, sensitive_values};
+
+
+/// Trace of one render call, returned by the `Terarium::render()` when requested by the `RenderRequest::with_trace()`.
+/// The trace is meant for debugging, collecting it slows the render down.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderTrace {
+    /// Rendered contents in order they were rendered. Outputs served from the render cache are not listed.
+    pub steps: Vec<TraceStep>,
+}
+
+
+/// Render of one template content recorded by the `RenderTrace`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Key of the rendered template.
+    pub template_key: String,
+    /// Language of the rendered content.
+    pub language: String,
+    /// Templates included or extended by the content in order of evaluation.
+    /// Includes are found in the source, so includes in skipped branches are listed too.
+    pub includes: Vec<String>,
+    /// Root names of context variables referenced by the content and templates it includes, in order of the first
+    /// reference. Variables in skipped branches are listed too.
+    pub variables: Vec<String>,
+    /// Keys resolved by the `resolve()` function during the render, in order of calls.
+    /// Sensitive values of the context (see `Sensitive`) are masked in keys.
+    pub resolved: Vec<String>,
+    /// Time spent in the `Tera` rendering the content.
+    pub tera_time: Duration,
+    /// Time spent by post-processing and validation of the output.
+    pub postprocess_time: Duration,
+}
+
+
+thread_local! {
+    static TRACE: RefCell<Option<RenderTrace>> = const { RefCell::new(None) };
+}
+
+
+/// Call the `f` and return its result with the trace of renders made by it.
+pub(crate) fn collect_trace<R>(f: impl FnOnce() -> R) -> (R, RenderTrace) {
+    let previous = TRACE.with(|trace| trace.replace(Some(RenderTrace::default())));
+    let result = f();
+    let trace = TRACE.with(|trace| trace.replace(previous)).unwrap_or_default();
+    (result, trace)
+}
+
+
+/// Return `true` when renders are traced by the `collect_trace()`.
+pub(crate) fn is_tracing() -> bool {
+    TRACE.with(|trace| trace.borrow().is_some())
+}
+
+
+/// Start new step of the trace collected by the `collect_trace()`.
+pub(crate) fn start_step(step: TraceStep) {
+    TRACE.with(|trace| {
+        if let Some(trace) = trace.borrow_mut().as_mut() {
+            trace.steps.push(step);
+        }
+    });
+}
+
+
+/// Record the key resolved by the `resolve()` function in the current step.
+pub(crate) fn record_resolved(key: &str) {
+    TRACE.with(|trace| {
+        if let Some(step) = trace.borrow_mut().as_mut().and_then(|trace| trace.steps.last_mut()) {
+            step.resolved.push(key.to_owned());
+        }
+    });
+}
+
+
+/// Call the `f` and add its duration to the current step by the `add`.
+pub(crate) fn traced<R>(add: impl FnOnce(&mut TraceStep, Duration), f: impl FnOnce() -> R) -> R {
+    let start = Instant::now();
+    let result = f();
+    TRACE.with(|trace| {
+        if let Some(step) = trace.borrow_mut().as_mut().and_then(|trace| trace.steps.last_mut()) {
+            add(step, start.elapsed());
+        }
+    });
+    result
+}
+
+
+impl RenderTrace {
+    /// Mask sensitive values of the context in recorded data.
+    pub(crate) fn redact(&mut self, context: &Context) {
+        let values = sensitive_values(context);
+        if values.is_empty() {
+            return;
+        }
+        for step in self.steps.iter_mut() {
+            for key in step.resolved.iter_mut() {
+                *key = redact_text(key, &values);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect() {
+        record_resolved("ignored");
+        assert!(!is_tracing());
+        let (_, trace) = collect_trace(|| {
+            assert!(is_tracing());
+            start_step(TraceStep { template_key: "a".to_owned(), ..TraceStep::default() });
+            record_resolved("user.name");
+            traced(|step, time| step.tera_time += time, || std::thread::sleep(Duration::from_millis(1)));
+        });
+        assert_eq!(trace.steps.len(), 1);
+        assert_eq!(trace.steps[0].resolved, vec!["user.name"]);
+        assert!(trace.steps[0].tera_time >= Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,74 @@
+/// Validates rendered output of the content.
+/// Validators are registered per `ContentKind` by the `TerariumBuilder::add_output_validator()`.
+pub trait OutputValidator: Send + Sync {
+    /// Return description of the problem when the `output` is not valid.
+    fn validate(&self, output: &str) -> Result<(), String>;
+}
+
+
+impl<F> OutputValidator for F where F: Fn(&str) -> Result<(), String> + Send + Sync {
+    fn validate(&self, output: &str) -> Result<(), String> {
+        self(output)
+    }
+}
+
+
+/// Check the output is a valid JSON document.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonValidator;
+
+
+impl OutputValidator for JsonValidator {
+    fn validate(&self, output: &str) -> Result<(), String> {
+        serde_json::from_str::<serde_json::Value>(output)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid JSON: {}", e))
+    }
+}
+
+
+/// Check the output is a valid vCard (RFC 6350) with all required properties.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VCardValidator;
+
+
+impl OutputValidator for VCardValidator {
+    fn validate(&self, output: &str) -> Result<(), String> {
+        let lines: Vec<&str> = output.lines().map(|l| l.trim_end()).filter(|l| !l.is_empty()).collect();
+        if lines.first() != Some(&"BEGIN:VCARD") || lines.last() != Some(&"END:VCARD") {
+            return Err("vCard must be wrapped in BEGIN:VCARD and END:VCARD".to_owned());
+        }
+        for property in ["VERSION", "FN"] {
+            let present = lines.iter().any(|l| {
+                l.split([':', ';']).next().is_some_and(|name| name.eq_ignore_ascii_case(property))
+            });
+            if !present {
+                return Err(format!("vCard requires property {}", property));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        assert!(JsonValidator.validate(r#"{"a": [1, 2]}"#).is_ok());
+        assert!(JsonValidator.validate(r#"{"a": [1, 2}"#).unwrap_err().starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn vcard() {
+        let card = "BEGIN:VCARD\nVERSION:4.0\nFN:John Doe\nEND:VCARD\n";
+        assert!(VCardValidator.validate(card).is_ok());
+        assert_eq!(
+            VCardValidator.validate(&card.replace("FN:John Doe\n", "")).unwrap_err(),
+            "vCard requires property FN"
+        );
+        assert!(VCardValidator.validate("FN:John Doe").is_err());
+    }
+}